@@ -0,0 +1,282 @@
+use crate::mock_sdk::{
+    AccountInfo,
+    Pubkey,
+    ProgramContext,
+    ProgramResult,
+    ProgramError,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use crate::{
+    error::OVTError,
+    events::OVTEvent,
+    msg,
+    timelock::TimelockParams,
+    OVTProgram,
+    OVTState,
+};
+
+/// Largest admin set the program accepts
+pub const MAX_ADMINS: usize = 16;
+
+/// Fund admins and the number of them that must sign privileged instructions
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct AdminConfig {
+    /// Admin keys, without duplicates
+    pub admins: Vec<Pubkey>,
+    /// Distinct admin signatures required
+    pub threshold: u8,
+    /// Delay and guardian veto applied to sensitive operations
+    pub timelock: TimelockParams,
+}
+
+impl AdminConfig {
+    /// Account space for the largest admin and guardian sets
+    pub const MAX_SPACE: usize = 4 + 32 * MAX_ADMINS + 1 + TimelockParams::MAX_SPACE;
+
+    pub fn validate(&self) -> ProgramResult {
+        if self.admins.is_empty() || self.admins.len() > MAX_ADMINS {
+            return Err(OVTError::InvalidAdminConfig.into());
+        }
+        if self.threshold == 0 || self.threshold as usize > self.admins.len() {
+            return Err(OVTError::InvalidAdminConfig.into());
+        }
+        for (i, admin) in self.admins.iter().enumerate() {
+            if self.admins[..i].contains(admin) {
+                return Err(OVTError::InvalidAdminConfig.into());
+            }
+        }
+        self.timelock.validate()
+    }
+
+    pub fn is_admin(&self, key: &Pubkey) -> bool {
+        self.admins.contains(key)
+    }
+
+    /// Number of distinct admins among the signers in `account_infos`
+    pub fn count_signers(&self, account_infos: &[AccountInfo]) -> usize {
+        let mut signers: Vec<Pubkey> = Vec::new();
+        for info in account_infos {
+            if info.is_signer && self.is_admin(&info.key) && !signers.contains(&info.key) {
+                signers.push(info.key);
+            }
+        }
+        signers.len()
+    }
+
+    pub fn add(&mut self, admin: Pubkey) -> ProgramResult {
+        if self.is_admin(&admin) {
+            return Err(OVTError::InvalidAdminConfig.into());
+        }
+        self.admins.push(admin);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, admin: &Pubkey) -> ProgramResult {
+        let index = self.position(admin)?;
+        self.admins.remove(index);
+        Ok(())
+    }
+
+    pub fn replace(&mut self, old_admin: &Pubkey, new_admin: Pubkey) -> ProgramResult {
+        if self.is_admin(&new_admin) {
+            return Err(OVTError::InvalidAdminConfig.into());
+        }
+        let index = self.position(old_admin)?;
+        self.admins[index] = new_admin;
+        Ok(())
+    }
+
+    fn position(&self, admin: &Pubkey) -> Result<usize, ProgramError> {
+        self.admins.iter()
+            .position(|key| key == admin)
+            .ok_or_else(|| OVTError::InvalidAdminConfig.into())
+    }
+
+    /// Load the admin set stored in `config_info`, checking it belongs to this program
+    pub fn load(program_id: &Pubkey, config_info: &AccountInfo) -> Result<Self, ProgramError> {
+        if *config_info.owner.borrow() != *program_id {
+            return Err(OVTError::InvalidAdminConfig.into());
+        }
+        config_info.get_data()
+            .map_err(|_| OVTError::InvalidAdminConfig.into())
+    }
+}
+
+impl OVTProgram {
+    /// Find the admin config account recorded in `state` among the instruction accounts
+    pub(crate) fn load_admin_config<'a>(
+        ctx: &'a ProgramContext,
+        state: &OVTState,
+    ) -> Result<(&'a AccountInfo, AdminConfig), ProgramError> {
+        let config_info = ctx.accounts.iter()
+            .find(|info| info.key == state.admin_config)
+            .ok_or(OVTError::InvalidAdminConfig)?;
+        let config = AdminConfig::load(&ctx.program_id, config_info)?;
+        Ok((config_info, config))
+    }
+
+    /// Check that at least the threshold of the admin set recorded in `state`
+    /// signed the instruction. The admin config account and the signers may
+    /// appear anywhere in the account list.
+    pub(crate) fn verify_admin_quorum(ctx: &ProgramContext, state: &OVTState) -> ProgramResult {
+        let (_, config) = Self::load_admin_config(ctx, state)?;
+
+        let signers = config.count_signers(&ctx.accounts);
+        if signers < config.threshold as usize {
+            msg!("Only {} of {} required admin signatures", signers, config.threshold);
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        Ok(())
+    }
+
+    /// Apply `change` to the admin set once the current threshold has approved
+    /// it. The resulting set must still be valid, so a change can never leave
+    /// the threshold above the admin count.
+    pub(crate) fn process_change_admins(
+        ctx: &ProgramContext,
+        change: impl FnOnce(&mut AdminConfig) -> ProgramResult,
+    ) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+
+        let state: OVTState = state_info.get_data()?;
+        Self::verify_admin_quorum(ctx, &state)?;
+
+        let (config_info, mut config) = Self::load_admin_config(ctx, &state)?;
+        change(&mut config)?;
+        config.validate()?;
+
+        config_info.set_data(&config)?;
+        ctx.emit(&OVTEvent::AdminChanged {
+            admins: config.admins,
+            threshold: config.threshold,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock_sdk::AccountMeta, test_fixtures::Fixture, OVTInstruction};
+
+    fn config(admins: Vec<Pubkey>, threshold: u8) -> AdminConfig {
+        AdminConfig { admins, threshold, timelock: TimelockParams::default() }
+    }
+
+    #[test]
+    fn test_admin_config_validation() {
+        let admins: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let valid = config(admins.clone(), 2);
+        assert!(valid.validate().is_ok());
+
+        // Threshold must be between one and the admin count
+        assert!(config(admins.clone(), 0).validate().is_err());
+        assert!(config(admins.clone(), 4).validate().is_err());
+        assert!(config(vec![admins[0], admins[0]], 1).validate().is_err());
+
+        let too_many = config((0..=MAX_ADMINS).map(|_| Pubkey::new_unique()).collect(), 1);
+        assert!(too_many.validate().is_err());
+
+        // The largest valid set fits the space allocated at Initialize
+        let largest = AdminConfig {
+            timelock: TimelockParams {
+                delay_secs: 0,
+                guardians: too_many.admins[1..].to_vec(),
+                guardian_threshold: 1,
+            },
+            ..config(too_many.admins[1..].to_vec(), 1)
+        };
+        assert!(largest.validate().is_ok());
+        assert_eq!(borsh::object_length(&largest).unwrap(), AdminConfig::MAX_SPACE);
+    }
+
+    #[test]
+    fn test_count_signers() {
+        let admins: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let config = config(admins.clone(), 2);
+
+        // Repeated, unsigned and non-admin accounts do not count
+        let infos = vec![
+            AccountInfo::new(admins[0], true, false),
+            AccountInfo::new(admins[0], true, false),
+            AccountInfo::new(admins[1], false, false),
+            AccountInfo::new(Pubkey::new_unique(), true, false),
+        ];
+        assert_eq!(config.count_signers(&infos), 1);
+
+        let infos = vec![
+            AccountInfo::new(admins[0], true, false),
+            AccountInfo::new(admins[2], true, false),
+        ];
+        assert_eq!(config.count_signers(&infos), 2);
+    }
+
+    #[test]
+    fn test_admin_set_changes() {
+        let mut fixture = Fixture::new();
+        let new_admin = fixture.create_signer();
+        let accounts = vec![
+            AccountMeta::new_readonly(fixture.state, false),
+            AccountMeta::new(fixture.admin_config, true),
+        ];
+        let stored = |fixture: &Fixture| -> AdminConfig {
+            fixture.client.get_account_data(&fixture.admin_config).unwrap()
+        };
+        let admins = fixture.admins.clone();
+
+        // Changes need the current threshold
+        let err = fixture.process_timelocked(accounts.clone(), &OVTInstruction::AddAdmin { admin: new_admin }, &admins[..2])
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Missing required signature");
+
+        fixture.process_timelocked(accounts.clone(), &OVTInstruction::AddAdmin { admin: new_admin }, &admins[..3]).unwrap();
+        assert_eq!(stored(&fixture).admins.len(), 6);
+        assert!(fixture.process_timelocked(accounts.clone(), &OVTInstruction::AddAdmin { admin: new_admin }, &admins[..3])
+            .is_err());
+
+        // A lost key is swapped out and the new key can approve straight away
+        let replacement = fixture.create_signer();
+        fixture.process_timelocked(
+            accounts.clone(),
+            &OVTInstruction::ReplaceAdmin { old_admin: admins[4], new_admin: replacement },
+            &admins[..3],
+        ).unwrap();
+        let current = stored(&fixture);
+        assert!(!current.is_admin(&admins[4]));
+        assert!(current.is_admin(&replacement));
+        fixture.process_timelocked(
+            accounts.clone(),
+            &OVTInstruction::SetThreshold { threshold: 4 },
+            &[admins[0], new_admin, replacement],
+        ).unwrap();
+        assert_eq!(stored(&fixture).threshold, 4);
+
+        // The removed key can no longer approve
+        let err = fixture.process_timelocked(
+            accounts.clone(),
+            &OVTInstruction::SetThreshold { threshold: 2 },
+            &[admins[0], admins[1], admins[2], admins[4]],
+        ).unwrap_err();
+        assert_eq!(err.to_string(), "Missing required signature");
+
+        // Thresholds above the admin count are refused, whether set directly
+        // or left behind by a removal
+        let quorum = [admins[0], admins[1], admins[2], new_admin];
+        let err = fixture.process_timelocked(accounts.clone(), &OVTInstruction::SetThreshold { threshold: 7 }, &quorum)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "InvalidAdminConfig");
+        assert!(fixture.process_timelocked(accounts.clone(), &OVTInstruction::SetThreshold { threshold: 0 }, &quorum)
+            .is_err());
+
+        fixture.process_timelocked(accounts.clone(), &OVTInstruction::RemoveAdmin { admin: admins[3] }, &quorum).unwrap();
+        fixture.process_timelocked(accounts.clone(), &OVTInstruction::RemoveAdmin { admin: replacement }, &quorum).unwrap();
+        let err = fixture.process_timelocked(accounts.clone(), &OVTInstruction::RemoveAdmin { admin: admins[2] }, &quorum)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "InvalidAdminConfig");
+        assert_eq!(stored(&fixture).admins, quorum.to_vec());
+
+        // Removing a key that is not an admin is refused
+        let err = fixture.process_timelocked(accounts, &OVTInstruction::RemoveAdmin { admin: replacement }, &quorum)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "InvalidAdminConfig");
+    }
+}
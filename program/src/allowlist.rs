@@ -0,0 +1,503 @@
+//! Investor allowlist. While the fund is restricted, only allowlisted holders
+//! can receive OVT, whether minted, converted, transferred, vested or paid
+//! as fees, and each holder receives into a single OVT account, so their
+//! limit bounds all they can acquire. Sending and redeeming are never gated,
+//! so a holder whose entry lapses or is removed keeps their exit.
+//!
+//! Each holder's eligibility is an `AllowlistRecord` account at
+//! `allowlist_entry_address`. The admins can write entries one by one, or
+//! publish the Merkle root of a whole investor list and let each investor
+//! register their own entry with a proof. Registration only creates entries,
+//! so it cannot undo terms the admins set, and removed entries are kept as
+//! tombstones so the holder cannot register again from the same root.
+
+use crate::mock_sdk::{
+    AccountInfo,
+    Pubkey,
+    ProgramContext,
+    ProgramResult,
+    ProgramError,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use crate::{
+    error::OVTError,
+    events::OVTEvent,
+    merkle,
+    msg,
+    system::{create_program_account, initialize_account},
+    OVTProgram,
+    OVTState,
+};
+
+/// Seed prefix of allowlist entry addresses
+pub const ALLOWLIST_ENTRY_SEED: &[u8] = b"allowlist";
+
+/// Governance-controlled allowlist mode
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AllowlistParams {
+    /// Whether only allowlisted holders can receive OVT
+    pub restricted: bool,
+    /// Root of the Merkle tree of entries holders may register themselves,
+    /// all zero when there is none
+    pub merkle_root: [u8; 32],
+}
+
+/// Eligibility of one holder. Merkle leaves are `merkle::leaf_hash` of the
+/// borsh encoding.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct AllowlistEntry {
+    pub holder: Pubkey,
+    /// Investor tier, recorded for off-chain eligibility rules
+    pub tier: u8,
+    /// Most OVT base units the holder may hold, zero for no limit
+    pub max_balance: u64,
+    /// Unix timestamp the entry lapses at, zero if it never does
+    pub expires_at: u64,
+}
+
+/// What the entry account of a holder stores
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct AllowlistRecord {
+    pub entry: AllowlistEntry,
+    /// The one OVT account the holder receives into while the fund is
+    /// restricted, bound on their first receipt. Other accounts of the
+    /// holder can only send.
+    pub token_account: Pubkey,
+    /// Set by `RemoveAllowlistEntry`. Only the admins can re-admit the holder.
+    pub removed: bool,
+}
+
+/// Entry address of `holder` in the fund whose state is `state`
+pub fn allowlist_entry_address(program_id: &Pubkey, state: &Pubkey, holder: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[ALLOWLIST_ENTRY_SEED, &state.0, &holder.0], program_id).0
+}
+
+impl AllowlistParams {
+    /// Whether these settings restrict a fund that `current` leaves open
+    pub fn restricts(&self, current: &AllowlistParams) -> bool {
+        self.restricted && !current.restricted
+    }
+}
+
+impl AllowlistEntry {
+    pub fn leaf(&self) -> Result<[u8; 32], ProgramError> {
+        Ok(merkle::leaf_hash(&borsh::to_vec(self)?))
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at > 0 && now >= self.expires_at
+    }
+}
+
+impl AllowlistRecord {
+    /// Load the record stored in `entry_info`, checking it belongs to this program
+    pub fn load(program_id: &Pubkey, entry_info: &AccountInfo) -> Result<Self, ProgramError> {
+        if *entry_info.owner.borrow() != *program_id {
+            return Err(OVTError::AllowlistRejected.into());
+        }
+        entry_info.get_data()
+            .map_err(|_| OVTError::AllowlistRejected.into())
+    }
+}
+
+impl OVTProgram {
+    /// Change the allowlist mode. Restricting an open fund is timelocked, so
+    /// holders have the delay to register before they are held to it.
+    pub(crate) fn process_set_allowlist(ctx: &ProgramContext, allowlist: AllowlistParams) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+
+        let mut state: OVTState = state_info.get_data()?;
+        Self::verify_admin_quorum(ctx, &state)?;
+
+        state.allowlist = allowlist;
+        ctx.emit(&OVTEvent::AllowlistUpdated { allowlist })?;
+        state_info.set_data(&state)
+    }
+
+    /// Write `entry`, replacing any earlier entry of the holder and
+    /// re-admitting a removed one. Requires the admin quorum.
+    pub(crate) fn process_set_allowlist_entry(ctx: &ProgramContext, entry: AllowlistEntry) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+
+        let state: OVTState = state_info.get_data()?;
+        Self::verify_admin_quorum(ctx, &state)?;
+        Self::write_allowlist_entry(ctx, entry, true)
+    }
+
+    /// Create the entry of `entry.holder` on the strength of a proof that it
+    /// is in the published Merkle root. Holders who already have an entry,
+    /// or had one removed, cannot register. Anyone may pay for the
+    /// registration.
+    pub(crate) fn process_register_allowlist_entry(
+        ctx: &ProgramContext,
+        entry: AllowlistEntry,
+        proof: Vec<[u8; 32]>,
+    ) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+
+        let state: OVTState = state_info.get_data()?;
+        let root = state.allowlist.merkle_root;
+        if root == [0u8; 32] || !merkle::verify(&root, &entry.leaf()?, &proof) {
+            msg!("No allowlist proof for holder {:?}", entry.holder);
+            return Err(OVTError::AllowlistRejected.into());
+        }
+        Self::write_allowlist_entry(ctx, entry, false)
+    }
+
+    /// Mark an entry removed. The account is kept as a tombstone, so the
+    /// holder cannot register again against the published root. Requires the
+    /// admin quorum.
+    pub(crate) fn process_remove_allowlist_entry(ctx: &ProgramContext) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+        let entry_info = ctx.get(1)?;
+
+        let state: OVTState = state_info.get_data()?;
+        Self::verify_admin_quorum(ctx, &state)?;
+        let mut record = AllowlistRecord::load(&ctx.program_id, entry_info)?;
+        let holder = record.entry.holder;
+        if entry_info.key != allowlist_entry_address(&ctx.program_id, &state_info.key, &holder) {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        record.removed = true;
+        entry_info.set_data(&record)?;
+        ctx.emit(&OVTEvent::AllowlistEntryRemoved { holder })
+    }
+
+    /// Write the entry account of `entry.holder`, creating it if needed. An
+    /// existing entry, removed or not, is only replaced with `overwrite`, and
+    /// keeps its bound OVT account. Accounts: [state, entry, payer, system
+    /// program].
+    fn write_allowlist_entry(ctx: &ProgramContext, entry: AllowlistEntry, overwrite: bool) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+        let entry_info = ctx.get(1)?;
+        let payer_info = ctx.get(2)?;
+        let system_program = ctx.get(3)?;
+
+        if !payer_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if entry_info.key != allowlist_entry_address(&ctx.program_id, &state_info.key, &entry.holder) {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let existing = AllowlistRecord::load(&ctx.program_id, entry_info).ok();
+        if existing.is_some() && !overwrite {
+            msg!("Holder {:?} already has an allowlist entry", entry.holder);
+            return Err(OVTError::AllowlistRejected.into());
+        }
+
+        let record = AllowlistRecord {
+            entry,
+            token_account: existing.map_or(Pubkey::new(), |existing| existing.token_account),
+            removed: false,
+        };
+        if *entry_info.owner.borrow() != ctx.program_id {
+            create_program_account(
+                &ctx.program_id,
+                entry_info,
+                payer_info,
+                borsh::object_length(&record)? as u64,
+                system_program,
+            )?;
+        }
+        initialize_account(&ctx.program_id, entry_info, &record)?;
+        ctx.emit(&OVTEvent::AllowlistEntrySet { entry: record.entry })
+    }
+
+    /// Refuse a credit to `token_account` of `holder` unless the fund is open
+    /// or the holder has a live entry, which may appear anywhere in the
+    /// account list. The account must be the holder's bound account and
+    /// `balance`, its balance after the credit, must stay within their
+    /// limit. The first receipt binds the account, so the entry must then be
+    /// writable.
+    pub(crate) fn verify_allowlisted(
+        ctx: &ProgramContext,
+        state: &OVTState,
+        holder: &Pubkey,
+        token_account: &Pubkey,
+        balance: u64,
+    ) -> ProgramResult {
+        if !state.allowlist.restricted {
+            return Ok(());
+        }
+        let entry_key = allowlist_entry_address(&ctx.program_id, &ctx.get(0)?.key, holder);
+        let entry_info = ctx.accounts.iter()
+            .find(|info| info.key == entry_key)
+            .ok_or_else(|| {
+                msg!("Holder {:?} is not allowlisted", holder);
+                OVTError::AllowlistRejected
+            })?;
+        let mut record = AllowlistRecord::load(&ctx.program_id, entry_info)?;
+        let entry = record.entry.clone();
+
+        if entry.holder != *holder || record.removed || entry.is_expired(ctx.clock()?.unix_timestamp) {
+            msg!("Allowlist entry of {:?} was removed or has lapsed", holder);
+            return Err(OVTError::AllowlistRejected.into());
+        }
+        if record.token_account == Pubkey::new() {
+            if !entry_info.is_writable {
+                return Err(ProgramError::InvalidArgument);
+            }
+            record.token_account = *token_account;
+            entry_info.set_data(&record)?;
+        } else if record.token_account != *token_account {
+            msg!("{:?} only receives into {:?}", holder, record.token_account);
+            return Err(OVTError::AllowlistRejected.into());
+        }
+        if entry.max_balance > 0 && balance > entry.max_balance {
+            msg!("Balance {} would exceed the limit {} of {:?}", balance, entry.max_balance, holder);
+            return Err(OVTError::AllowlistRejected.into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock_sdk::AccountMeta,
+        test_fixtures::{Events, Fixture},
+        OVTInstruction,
+    };
+
+    const BTC_ADDRESS: &str = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+
+    fn entry(holder: Pubkey, max_balance: u64, expires_at: u64) -> AllowlistEntry {
+        AllowlistEntry { holder, tier: 1, max_balance, expires_at }
+    }
+
+    fn set_entry(fixture: &mut Fixture, entry: AllowlistEntry) -> Events {
+        let accounts = vec![
+            AccountMeta::new_readonly(fixture.state, false),
+            AccountMeta::new(fixture.allowlist_entry(&entry.holder), true),
+            AccountMeta::new_readonly(fixture.authority(), true),
+            AccountMeta::new_readonly(fixture.system_program, false),
+        ];
+        fixture.process(accounts, &OVTInstruction::SetAllowlistEntry { entry })
+    }
+
+    fn set_allowlist(fixture: &mut Fixture, allowlist: AllowlistParams) -> Events {
+        let instruction = OVTInstruction::SetAllowlist { allowlist };
+        if !allowlist.restricts(&fixture.state().allowlist) {
+            return fixture.process(vec![AccountMeta::new(fixture.state, true)], &instruction);
+        }
+        let mut accounts = vec![AccountMeta::new(fixture.state, true)];
+        accounts.extend(fixture.signers(Fixture::THRESHOLD as usize));
+        let approvers = fixture.admins[..3].to_vec();
+        fixture.process_timelocked(accounts, &instruction, &approvers)
+    }
+
+    #[test]
+    fn test_admin_entries_gate_transfers() {
+        let mut fixture = Fixture::new();
+        let alice = fixture.create_signer();
+        let bob = fixture.create_signer();
+        let alice_account = fixture.token_account(alice);
+        let bob_account = fixture.token_account(bob);
+        fixture.set_balance(&alice_account, 1_000);
+        let alice_entry = fixture.allowlist_entry(&alice);
+        let bob_entry = fixture.allowlist_entry(&bob);
+
+        let transfer = |fixture: &mut Fixture, amount, entries: &[Pubkey]| {
+            let mut accounts = vec![
+                AccountMeta::new_readonly(fixture.state, false),
+                AccountMeta::new(alice_account, true),
+                AccountMeta::new(bob_account, true),
+                AccountMeta::new_readonly(alice, true),
+            ];
+            accounts.extend(entries.iter().map(|entry| AccountMeta::new(*entry, true)));
+            fixture.process_raw(accounts, &OVTInstruction::Transfer { amount })
+        };
+
+        // Restricting an open fund waits out the timelock
+        let restricted = AllowlistParams { restricted: true, merkle_root: [0u8; 32] };
+        let err = fixture.process(vec![AccountMeta::new(fixture.state, true)], &OVTInstruction::SetAllowlist {
+            allowlist: restricted,
+        }).unwrap_err();
+        assert_eq!(err.to_string(), "TimelockRequired");
+        let now = fixture.client.clock().unix_timestamp;
+        set_entry(&mut fixture, entry(alice, 0, 0)).unwrap();
+        set_entry(&mut fixture, entry(bob, 500, now + 2 * Fixture::TIMELOCK_DELAY_SECS)).unwrap();
+        set_allowlist(&mut fixture, restricted).unwrap();
+        assert!(fixture.state().allowlist.restricted);
+
+        // Only the receiver needs a live entry, and it stays within its limit
+        let err = transfer(&mut fixture, 400, &[]).unwrap_err();
+        assert_eq!(err.to_string(), "AllowlistRejected");
+        let err = transfer(&mut fixture, 400, &[alice_entry]).unwrap_err();
+        assert_eq!(err.to_string(), "AllowlistRejected");
+        transfer(&mut fixture, 400, &[bob_entry]).unwrap();
+        assert_eq!(fixture.balance(&bob_account), 400);
+        let err = transfer(&mut fixture, 101, &[alice_entry, bob_entry]).unwrap_err();
+        assert_eq!(err.to_string(), "AllowlistRejected");
+        transfer(&mut fixture, 100, &[alice_entry, bob_entry]).unwrap();
+
+        // The first receipt bound Bob to one account, so a second account
+        // cannot take him past his limit
+        let stored: AllowlistRecord = fixture.client.get_account_data(&bob_entry).unwrap();
+        assert_eq!(stored.token_account, bob_account);
+        let bob_second = fixture.token_account(bob);
+        let err = fixture.process_raw(vec![
+            AccountMeta::new_readonly(fixture.state, false),
+            AccountMeta::new(alice_account, true),
+            AccountMeta::new(bob_second, true),
+            AccountMeta::new_readonly(alice, true),
+            AccountMeta::new(alice_entry, true),
+            AccountMeta::new(bob_entry, true),
+        ], &OVTInstruction::Transfer { amount: 1 }).unwrap_err();
+        assert_eq!(err.to_string(), "AllowlistRejected");
+
+        // Entries lapse at their expiry
+        fixture.set_balance(&bob_account, 0);
+        fixture.client.advance_clock(Fixture::TIMELOCK_DELAY_SECS);
+        let err = transfer(&mut fixture, 1, &[alice_entry, bob_entry]).unwrap_err();
+        assert_eq!(err.to_string(), "AllowlistRejected");
+        set_entry(&mut fixture, entry(bob, 500, 0)).unwrap();
+        transfer(&mut fixture, 1, &[alice_entry, bob_entry]).unwrap();
+
+        // Removed holders can still send, but receive nothing until the fund
+        // is opened again
+        let events = fixture.process(vec![
+            AccountMeta::new_readonly(fixture.state, false),
+            AccountMeta::new(alice_entry, true),
+        ], &OVTInstruction::RemoveAllowlistEntry).unwrap();
+        assert_eq!(events, vec![OVTEvent::AllowlistEntryRemoved { holder: alice }]);
+        transfer(&mut fixture, 1, &[alice_entry, bob_entry]).unwrap();
+        let transfer_back = |fixture: &mut Fixture| {
+            fixture.process_raw(vec![
+                AccountMeta::new_readonly(fixture.state, false),
+                AccountMeta::new(bob_account, true),
+                AccountMeta::new(alice_account, true),
+                AccountMeta::new_readonly(bob, true),
+                AccountMeta::new(alice_entry, true),
+                AccountMeta::new(bob_entry, true),
+            ], &OVTInstruction::Transfer { amount: 1 })
+        };
+        let err = transfer_back(&mut fixture).unwrap_err();
+        assert_eq!(err.to_string(), "AllowlistRejected");
+        set_allowlist(&mut fixture, AllowlistParams::default()).unwrap();
+        transfer_back(&mut fixture).unwrap();
+        transfer(&mut fixture, 1, &[]).unwrap();
+
+        // Entries only live at the holder's derived address
+        let other = fixture.create_account();
+        let err = fixture.process(vec![
+            AccountMeta::new_readonly(fixture.state, false),
+            AccountMeta::new(other, true),
+            AccountMeta::new_readonly(fixture.authority(), true),
+            AccountMeta::new_readonly(fixture.system_program, false),
+        ], &OVTInstruction::SetAllowlistEntry { entry: entry(alice, 0, 0) }).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid argument");
+    }
+
+    #[test]
+    fn test_merkle_registration_gates_mint() {
+        let mut fixture = Fixture::new();
+        let utxo_account = fixture.create_account();
+        let holder = fixture.create_signer();
+        let holder_account = fixture.token_account(holder);
+        let holder_entry = fixture.allowlist_entry(&holder);
+
+        // A published list of investors, of which the holder is one
+        let entries: Vec<AllowlistEntry> = (0..4)
+            .map(|_| entry(Pubkey::new_unique(), 0, 0))
+            .chain(std::iter::once(entry(holder, 1_000_000, 0)))
+            .collect();
+        let leaves: Vec<[u8; 32]> = entries.iter().map(|entry| entry.leaf().unwrap()).collect();
+        let merkle_root = merkle::root(&leaves);
+        set_allowlist(&mut fixture, AllowlistParams { restricted: true, merkle_root }).unwrap();
+
+        let mint = |fixture: &mut Fixture, payment_vout, payment_amount_sats| {
+            let accounts = vec![
+                AccountMeta::new(fixture.state, true),
+                AccountMeta::new_readonly(fixture.authority(), true),
+                AccountMeta::new_readonly(utxo_account, false),
+                AccountMeta::new(fixture.payment_marker(Fixture::PAYMENT_TXID, payment_vout), true),
+                AccountMeta::new_readonly(fixture.system_program, false),
+                AccountMeta::new(holder_account, true),
+                AccountMeta::new(holder_entry, true),
+            ];
+            fixture.process(accounts, &OVTInstruction::Mint {
+                payment_txid: Fixture::PAYMENT_TXID.to_string(),
+                payment_vout,
+                payment_amount_sats,
+                recipient: holder,
+            })
+        };
+        let err = mint(&mut fixture, 0, 1_000_000).unwrap_err();
+        assert_eq!(err.to_string(), "AllowlistRejected");
+
+        // Registration needs a proof of exactly the published entry
+        let register = |fixture: &mut Fixture, entry: AllowlistEntry, proof: Vec<[u8; 32]>| {
+            let accounts = vec![
+                AccountMeta::new_readonly(fixture.state, false),
+                AccountMeta::new(holder_entry, true),
+                AccountMeta::new_readonly(holder, true),
+                AccountMeta::new_readonly(fixture.system_program, false),
+            ];
+            fixture.process_raw(accounts, &OVTInstruction::RegisterAllowlistEntry { entry, proof })
+        };
+        let proof = merkle::proof(&leaves, 4);
+        let raised = AllowlistEntry { max_balance: 0, ..entries[4].clone() };
+        let err = register(&mut fixture, raised, proof.clone()).unwrap_err();
+        assert_eq!(err.to_string(), "AllowlistRejected");
+        let err = register(&mut fixture, entries[4].clone(), merkle::proof(&leaves, 3)).unwrap_err();
+        assert_eq!(err.to_string(), "AllowlistRejected");
+        let events = register(&mut fixture, entries[4].clone(), proof.clone()).unwrap();
+        assert_eq!(events, vec![OVTEvent::AllowlistEntrySet { entry: entries[4].clone() }]);
+        let stored: AllowlistRecord = fixture.client.get_account_data(&holder_entry).unwrap();
+        assert_eq!(stored, AllowlistRecord { entry: entries[4].clone(), token_account: Pubkey::new(), removed: false });
+
+        // Registration cannot replay the leaf over an entry the admins tightened
+        let tightened = AllowlistEntry { max_balance: 1_000_100, ..entries[4].clone() };
+        set_entry(&mut fixture, tightened.clone()).unwrap();
+        let err = register(&mut fixture, entries[4].clone(), proof.clone()).unwrap_err();
+        assert_eq!(err.to_string(), "AllowlistRejected");
+        let stored: AllowlistRecord = fixture.client.get_account_data(&holder_entry).unwrap();
+        assert_eq!(stored.entry, tightened);
+
+        // Mints stop at the holder's limit
+        mint(&mut fixture, 0, 1_000_000).unwrap();
+        assert_eq!(fixture.balance(&holder_account), 1_000_000);
+        let stored: AllowlistRecord = fixture.client.get_account_data(&holder_entry).unwrap();
+        assert_eq!(stored.token_account, holder_account);
+        let err = mint(&mut fixture, 1, 50_000).unwrap_err();
+        assert_eq!(err.to_string(), "AllowlistRejected");
+
+        // Redeeming needs no entry
+        let redeem = |fixture: &mut Fixture, entries: &[Pubkey]| {
+            let mut accounts = vec![
+                AccountMeta::new(fixture.state, true),
+                AccountMeta::new(fixture.create_system_account(), true),
+                AccountMeta::new_readonly(holder, true),
+                AccountMeta::new_readonly(fixture.authority(), true),
+                AccountMeta::new_readonly(fixture.system_program, false),
+                AccountMeta::new(holder_account, true),
+            ];
+            accounts.extend(entries.iter().map(|entry| AccountMeta::new_readonly(*entry, false)));
+            fixture.process(accounts, &OVTInstruction::Redeem {
+                amount: 50_000,
+                btc_address: BTC_ADDRESS.to_string(),
+            })
+        };
+        redeem(&mut fixture, &[]).unwrap();
+        assert_eq!(fixture.balance(&holder_account), 950_000);
+
+        // A removed holder cannot register again from the same root, nor
+        // receive, but keeps their exit; only the admins can re-admit them
+        fixture.process(vec![
+            AccountMeta::new_readonly(fixture.state, false),
+            AccountMeta::new(holder_entry, true),
+        ], &OVTInstruction::RemoveAllowlistEntry).unwrap();
+        let err = register(&mut fixture, entries[4].clone(), proof).unwrap_err();
+        assert_eq!(err.to_string(), "AllowlistRejected");
+        let err = mint(&mut fixture, 1, 10_000).unwrap_err();
+        assert_eq!(err.to_string(), "AllowlistRejected");
+        redeem(&mut fixture, &[holder_entry]).unwrap();
+        assert_eq!(fixture.balance(&holder_account), 900_000);
+        set_entry(&mut fixture, entries[4].clone()).unwrap();
+        mint(&mut fixture, 1, 10_000).unwrap();
+    }
+}
@@ -0,0 +1,118 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use crate::{
+    error::OVTError,
+    math::{ratio_above, ratio_below, BPS_DENOMINATOR},
+    mock_sdk::{ProgramError, ProgramResult},
+    msg,
+};
+
+/// Governance-controlled bounds on NAV movements. Ratios are in basis points,
+/// so 2x is 20_000 and 0.5x is 5_000.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CircuitBreakerParams {
+    /// Highest NAV accepted, relative to the baseline NAV
+    pub max_cumulative_bps: u64,
+    /// Lowest NAV accepted, relative to the baseline NAV
+    pub min_cumulative_bps: u64,
+    /// Single-update increase above which a change is logged for monitoring
+    pub warn_upper_bps: u64,
+    /// Single-update decrease below which a change is logged for monitoring
+    pub warn_lower_bps: u64,
+}
+
+impl Default for CircuitBreakerParams {
+    fn default() -> Self {
+        Self {
+            max_cumulative_bps: 410_000,
+            min_cumulative_bps: 500,
+            warn_upper_bps: 20_000,
+            warn_lower_bps: 5_000,
+        }
+    }
+}
+
+impl CircuitBreakerParams {
+    pub fn validate(&self) -> ProgramResult {
+        // Each band must contain the unchanged NAV
+        if self.min_cumulative_bps > BPS_DENOMINATOR
+            || self.max_cumulative_bps < BPS_DENOMINATOR
+            || self.warn_lower_bps > BPS_DENOMINATOR
+            || self.warn_upper_bps < BPS_DENOMINATOR
+        {
+            return Err(OVTError::InvalidNAVUpdate.into());
+        }
+        Ok(())
+    }
+
+    /// Whether these bounds accept a NAV that `current` would reject
+    pub fn widens(&self, current: &CircuitBreakerParams) -> bool {
+        self.max_cumulative_bps > current.max_cumulative_bps
+            || self.min_cumulative_bps < current.min_cumulative_bps
+    }
+
+    /// Whether moving from `current_nav_sats` to `new_nav_sats` falls outside
+    /// the warning band
+    pub fn is_significant_change(&self, current_nav_sats: u64, new_nav_sats: u64) -> Result<bool, ProgramError> {
+        Ok(ratio_above(new_nav_sats, current_nav_sats, self.warn_upper_bps)?
+            || ratio_below(new_nav_sats, current_nav_sats, self.warn_lower_bps)?)
+    }
+
+    /// Check `new_nav_sats` against the previous NAV and the baseline NAV.
+    /// A zero previous or baseline NAV skips the corresponding check.
+    pub fn check(&self, baseline_nav_sats: u64, current_nav_sats: u64, new_nav_sats: u64) -> ProgramResult {
+        // For significant changes, log for monitoring
+        if current_nav_sats > 0 && self.is_significant_change(current_nav_sats, new_nav_sats)? {
+            msg!("Significant NAV change detected: {} sats from {} sats", new_nav_sats, current_nav_sats);
+        }
+
+        if baseline_nav_sats > 0
+            && (ratio_above(new_nav_sats, baseline_nav_sats, self.max_cumulative_bps)?
+                || ratio_below(new_nav_sats, baseline_nav_sats, self.min_cumulative_bps)?)
+        {
+            msg!("Rejecting NAV update - cumulative change too large: {} sats against baseline {} sats", new_nav_sats, baseline_nav_sats);
+            return Err(OVTError::InvalidNAVUpdate.into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_circuit_breaker_bounds() {
+        let params = CircuitBreakerParams::default();
+        assert!(params.validate().is_ok());
+
+        // Default bounds are 41x and 0.05x of the baseline
+        assert!(params.check(1_000_000, 1_000_000, 41_000_000).is_ok());
+        assert!(params.check(1_000_000, 1_000_000, 42_000_000).is_err());
+        assert!(params.check(1_000_000, 1_000_000, 50_000).is_ok());
+        assert!(params.check(1_000_000, 1_000_000, 40_000).is_err());
+
+        // Bounds follow the baseline, not the previous NAV
+        assert!(params.check(2_000_000, 40_000_000, 82_000_000).is_ok());
+
+        // Warning band edges at exactly 2x and 0.5x
+        assert!(!params.is_significant_change(1_000_000, 2_000_000).unwrap());
+        assert!(params.is_significant_change(1_000_000, 2_000_001).unwrap());
+        assert!(!params.is_significant_change(1_000_000, 500_000).unwrap());
+        assert!(params.is_significant_change(1_000_000, 499_999).unwrap());
+
+        // Exactly 41x is accepted, one sat more is not
+        assert!(params.check(1_000_000, 1_000_000, 41_000_001).is_err());
+
+        // A NAV too large to express in basis points is still refused as a
+        // NAV change, not an arithmetic error
+        let err = params.check(1, 1, u64::MAX).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidNAVUpdate");
+
+        let tight = CircuitBreakerParams { max_cumulative_bps: 15_000, ..params };
+        assert!(tight.check(1_000_000, 1_000_000, 1_600_000).is_err());
+
+        // A band that excludes an unchanged NAV is rejected
+        let inverted = CircuitBreakerParams { max_cumulative_bps: 9_000, ..params };
+        assert!(inverted.validate().is_err());
+    }
+}
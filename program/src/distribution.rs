@@ -0,0 +1,541 @@
+//! Pro-rata distributions of the proceeds of exited positions to holders.
+//!
+//! Each distribution shares out proceeds of one exited position, at the
+//! address derived from the position, so a position funds at most one. The
+//! admins snapshot holder balances off-chain and commit to the Merkle root
+//! of each owner's balance and entitlement, with the snapshot supply and the
+//! sum of the entitlements. Vesting escrows cannot sign a claim, so their
+//! share is listed under the beneficiary instead.
+//!
+//! Owners then claim with a proof and receive a payout obligation, settled
+//! like a redemption. Each claim is checked against the committed totals:
+//! the entitlement must be the snapshot rate applied to the claimed balance,
+//! and claims together cannot cover more than the snapshot supply or pay
+//! out more than the entitlements committed to.
+//!
+//! Every holder is paid the same rate per whole OVT, rounded down to the
+//! satoshi at `OVT_DECIMALS` precision, and their entitlement is rounded
+//! down again. Entitlements below `DISTRIBUTION_DUST_SATS` could not be paid
+//! as a Bitcoin output and are dropped. Rounding remainders and dropped dust
+//! stay in the fund.
+
+use crate::mock_sdk::{
+    AccountInfo,
+    Pubkey,
+    ProgramContext,
+    ProgramResult,
+    ProgramError,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use crate::{
+    error::OVTError,
+    events::OVTEvent,
+    math::mul_div,
+    merkle,
+    msg,
+    position::PositionData,
+    redemption::RedemptionObligation,
+    runes_client::PositionStatus,
+    system::{create_program_account, initialize_account, verify_uninitialized},
+    utxo::validate_btc_address,
+    OVTProgram,
+    OVTState,
+    OVT_DECIMALS,
+};
+
+/// Seed prefix of distribution addresses
+pub const DISTRIBUTION_SEED: &[u8] = b"exit-distribution";
+/// Seed prefix of distribution claim addresses
+pub const DISTRIBUTION_CLAIM_SEED: &[u8] = b"distribution";
+/// Smallest entitlement paid out, the dust limit of a Bitcoin output
+pub const DISTRIBUTION_DUST_SATS: u64 = 546;
+
+/// Proceeds of an exited position shared out over one snapshot of holder
+/// balances
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct Distribution {
+    /// State account of the fund making the distribution
+    pub state: Pubkey,
+    /// Exited position whose proceeds are distributed
+    pub position: Pubkey,
+    /// Proceeds the admins set aside
+    pub amount_sats: u64,
+    /// Satoshis paid per whole OVT
+    pub sats_per_token: u64,
+    /// Supply at the snapshot
+    pub total_supply: u64,
+    /// Sum of the entitlements, which the NAV was reduced by
+    pub distributed_sats: u64,
+    /// Entitlements claimed so far
+    pub claimed_sats: u64,
+    /// Snapshot balances claimed against so far
+    pub claimed_balance: u64,
+    /// Root of the Merkle tree of `DistributionEntitlement`s
+    pub merkle_root: [u8; 32],
+    /// Unix timestamp the distribution was committed
+    pub snapshot_at: u64,
+}
+
+/// What one owner held at the snapshot and is owed. Merkle leaves are
+/// `merkle::leaf_hash` of the borsh encoding.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct DistributionEntitlement {
+    pub holder: Pubkey,
+    /// OVT base units held at the snapshot, across all the owner's accounts
+    pub balance: u64,
+    pub entitlement_sats: u64,
+}
+
+/// Address of the distribution of the proceeds of `position` in the fund
+/// whose state is `state`
+pub fn distribution_address(program_id: &Pubkey, state: &Pubkey, position: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[DISTRIBUTION_SEED, &state.0, &position.0], program_id).0
+}
+
+/// Address of the payout obligation `holder` claims from `distribution`.
+/// There is one per holder, so each entitlement is claimed once.
+pub fn distribution_claim_address(program_id: &Pubkey, distribution: &Pubkey, holder: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[DISTRIBUTION_CLAIM_SEED, &distribution.0, &holder.0], program_id).0
+}
+
+/// Satoshis per whole OVT when `amount_sats` is shared over `total_supply`,
+/// rounded down
+pub fn sats_per_token(amount_sats: u64, total_supply: u64) -> Result<u64, ProgramError> {
+    mul_div(amount_sats, 10u64.pow(OVT_DECIMALS as u32), total_supply)
+}
+
+/// Entitlement to `balance` OVT base units at `sats_per_token`, rounded down
+pub fn entitlement_of(balance: u64, sats_per_token: u64) -> Result<u64, ProgramError> {
+    mul_div(balance, sats_per_token, 10u64.pow(OVT_DECIMALS as u32))
+}
+
+/// Entitlements of the owners of `balances` at `sats_per_token`, one per
+/// owner and sorted by owner, leaving out dust. Off-chain tools build the
+/// root and claim proofs from the leaves of this list.
+pub fn entitlements(balances: &[(Pubkey, u64)], sats_per_token: u64) -> Result<Vec<DistributionEntitlement>, ProgramError> {
+    let mut balances = balances.to_vec();
+    balances.sort_by_key(|(owner, _)| owner.0);
+    let mut merged: Vec<(Pubkey, u64)> = Vec::new();
+    for (owner, balance) in balances {
+        match merged.last_mut() {
+            Some((last, total)) if *last == owner => {
+                *total = total.checked_add(balance).ok_or(OVTError::ArithmeticOverflow)?;
+            }
+            _ => merged.push((owner, balance)),
+        }
+    }
+
+    let mut entitlements = Vec::new();
+    for (holder, balance) in merged {
+        let entitlement_sats = entitlement_of(balance, sats_per_token)?;
+        if entitlement_sats >= DISTRIBUTION_DUST_SATS {
+            entitlements.push(DistributionEntitlement { holder, balance, entitlement_sats });
+        }
+    }
+    Ok(entitlements)
+}
+
+impl DistributionEntitlement {
+    pub fn leaf(&self) -> Result<[u8; 32], ProgramError> {
+        Ok(merkle::leaf_hash(&borsh::to_vec(self)?))
+    }
+}
+
+impl Distribution {
+    /// Load the distribution stored in `distribution_info`, checking it
+    /// belongs to this program and was made by the fund whose state is `state`
+    pub fn load(program_id: &Pubkey, state: &Pubkey, distribution_info: &AccountInfo) -> Result<Self, ProgramError> {
+        if *distribution_info.owner.borrow() != *program_id {
+            return Err(OVTError::InvalidDistribution.into());
+        }
+        let distribution: Self = distribution_info.get_data()
+            .map_err(|_| OVTError::InvalidDistribution)?;
+        if distribution.state != *state {
+            return Err(OVTError::InvalidDistribution.into());
+        }
+        Ok(distribution)
+    }
+}
+
+impl OVTProgram {
+    /// Commit to sharing `amount_sats` of the proceeds of an exited position
+    /// over a snapshot of `total_supply` OVT. `merkle_root` commits to the
+    /// entitlements, which sum to `distributed_sats`. The entitlements leave
+    /// the NAV at once. Requires the admin quorum.
+    pub(crate) fn process_distribute(
+        ctx: &ProgramContext,
+        amount_sats: u64,
+        total_supply: u64,
+        distributed_sats: u64,
+        merkle_root: [u8; 32],
+    ) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+        let distribution_info = ctx.get(1)?;
+        let position_info = ctx.get(2)?;
+        let authority_info = ctx.get(3)?;
+        let system_program = ctx.get(4)?;
+
+        let mut state: OVTState = state_info.get_data()?;
+        Self::verify_admin_quorum(ctx, &state)?;
+        let position = PositionData::load(&ctx.program_id, &state_info.key, position_info)?;
+        if position.status != PositionStatus::Exited {
+            msg!("Only the proceeds of an exited position can be distributed");
+            return Err(OVTError::InvalidDistribution.into());
+        }
+        if distribution_info.key != distribution_address(&ctx.program_id, &state_info.key, &position_info.key) {
+            return Err(ProgramError::InvalidArgument);
+        }
+        verify_uninitialized(distribution_info)?;
+        if amount_sats > state.nav_sats
+            || total_supply == 0
+            || distributed_sats == 0
+            || distributed_sats > amount_sats
+            || merkle_root == [0u8; 32]
+        {
+            return Err(OVTError::InvalidDistribution.into());
+        }
+
+        let rate = sats_per_token(amount_sats, total_supply)?;
+        let distribution = Distribution {
+            state: state_info.key,
+            position: position_info.key,
+            amount_sats,
+            sats_per_token: rate,
+            total_supply,
+            distributed_sats,
+            claimed_sats: 0,
+            claimed_balance: 0,
+            merkle_root,
+            snapshot_at: ctx.clock()?.unix_timestamp,
+        };
+        create_program_account(
+            &ctx.program_id,
+            distribution_info,
+            authority_info,
+            borsh::object_length(&distribution)? as u64,
+            system_program,
+        )?;
+        initialize_account(&ctx.program_id, distribution_info, &distribution)?;
+
+        // The treasury now owes the entitlements, so they leave the NAV
+        state.nav_sats -= distributed_sats;
+
+        ctx.emit(&OVTEvent::Distributed {
+            distribution: distribution_info.key,
+            position: position_info.key,
+            amount_sats,
+            distributed_sats,
+            sats_per_token: rate,
+            merkle_root,
+        })?;
+        state_info.set_data(&state)
+    }
+
+    /// Turn the holder's proven entitlement into a payout obligation to
+    /// `btc_address`, at the holder's claim address
+    pub(crate) fn process_claim_distribution(
+        ctx: &ProgramContext,
+        balance: u64,
+        entitlement_sats: u64,
+        proof: Vec<[u8; 32]>,
+        btc_address: String,
+    ) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+        let distribution_info = ctx.get(1)?;
+        let obligation_info = ctx.get(2)?;
+        let holder_info = ctx.get(3)?;
+        let system_program = ctx.get(4)?;
+
+        if !holder_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        state_info.get_data::<OVTState>()?;
+        validate_btc_address(&btc_address)?;
+        let mut distribution = Distribution::load(&ctx.program_id, &state_info.key, distribution_info)?;
+
+        let entitlement = DistributionEntitlement { holder: holder_info.key, balance, entitlement_sats };
+        if !merkle::verify(&distribution.merkle_root, &entitlement.leaf()?, &proof) {
+            return Err(OVTError::InvalidDistribution.into());
+        }
+        if obligation_info.key != distribution_claim_address(&ctx.program_id, &distribution_info.key, &holder_info.key) {
+            return Err(ProgramError::InvalidArgument);
+        }
+        if *obligation_info.owner.borrow() == ctx.program_id
+            && obligation_info.get_data::<RedemptionObligation>().is_ok()
+        {
+            return Err(OVTError::DistributionAlreadyClaimed.into());
+        }
+        verify_uninitialized(obligation_info)?;
+
+        // The root is only trusted as far as the committed totals bound it
+        if entitlement_sats < DISTRIBUTION_DUST_SATS
+            || entitlement_sats != entitlement_of(balance, distribution.sats_per_token)?
+        {
+            msg!("Entitlement {} does not match a balance of {}", entitlement_sats, balance);
+            return Err(OVTError::InvalidDistribution.into());
+        }
+        distribution.claimed_balance = distribution.claimed_balance
+            .checked_add(balance)
+            .filter(|claimed| *claimed <= distribution.total_supply)
+            .ok_or(OVTError::InvalidDistribution)?;
+        distribution.claimed_sats = distribution.claimed_sats
+            .checked_add(entitlement_sats)
+            .filter(|claimed| *claimed <= distribution.distributed_sats)
+            .ok_or(OVTError::InvalidDistribution)?;
+
+        let obligation = RedemptionObligation {
+            holder: holder_info.key,
+            ovt_amount: 0,
+            payout_sats: entitlement_sats,
+            btc_address,
+            created_at: ctx.clock()?.unix_timestamp,
+            payout_txid: None,
+            settled_at: 0,
+        };
+        // Room for the payout txid once settled
+        let space = borsh::object_length(&obligation)? + 4 + 64;
+        create_program_account(
+            &ctx.program_id,
+            obligation_info,
+            holder_info,
+            space as u64,
+            system_program,
+        )?;
+        initialize_account(&ctx.program_id, obligation_info, &obligation)?;
+
+        ctx.emit(&OVTEvent::DistributionClaimed {
+            distribution: distribution_info.key,
+            holder: holder_info.key,
+            obligation: obligation_info.key,
+            payout_sats: entitlement_sats,
+        })?;
+        distribution_info.set_data(&distribution)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock_sdk::AccountMeta,
+        runes_client::PositionType,
+        test_fixtures::{Events, Fixture},
+        OVTInstruction,
+    };
+
+    const BTC_ADDRESS: &str = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+
+    fn position(fixture: &mut Fixture, status: PositionStatus) -> Pubkey {
+        let position = fixture.create_account();
+        fixture.client.set_account_data(&position, &PositionData {
+            state: fixture.state,
+            name: "Exited project".to_string(),
+            amount: 0,
+            price_per_token: 0,
+            currency_spent: 1_000_000,
+            position_type: PositionType::PreTGE,
+            status,
+            entry_timestamp: 0,
+        }).unwrap();
+        position
+    }
+
+    fn distribute(
+        fixture: &mut Fixture,
+        distribution: Pubkey,
+        position: Pubkey,
+        instruction: &OVTInstruction,
+    ) -> Events {
+        let accounts = vec![
+            AccountMeta::new(fixture.state, true),
+            AccountMeta::new(distribution, true),
+            AccountMeta::new_readonly(position, false),
+            AccountMeta::new_readonly(fixture.authority(), true),
+            AccountMeta::new_readonly(fixture.system_program, false),
+        ];
+        fixture.process(accounts, instruction)
+    }
+
+    fn claim(
+        fixture: &mut Fixture,
+        distribution: Pubkey,
+        entitlement: &DistributionEntitlement,
+        proof: Vec<[u8; 32]>,
+    ) -> Events {
+        let obligation = fixture.distribution_claim(&distribution, &entitlement.holder);
+        fixture.process_raw(vec![
+            AccountMeta::new_readonly(fixture.state, false),
+            AccountMeta::new(distribution, true),
+            AccountMeta::new(obligation, true),
+            AccountMeta::new_readonly(entitlement.holder, true),
+            AccountMeta::new_readonly(fixture.system_program, false),
+        ], &OVTInstruction::ClaimDistribution {
+            balance: entitlement.balance,
+            entitlement_sats: entitlement.entitlement_sats,
+            proof,
+            btc_address: BTC_ADDRESS.to_string(),
+        })
+    }
+
+    fn leaves(entitlements: &[DistributionEntitlement]) -> Vec<[u8; 32]> {
+        entitlements.iter().map(|entitlement| entitlement.leaf().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_rounding_and_dust() {
+        // 10 OVT, 5 OVT and a sliver share 1M sats: the rate per whole OVT
+        // and each entitlement round down, and the sliver's share is dust
+        let (alice, bob, carol) = (Pubkey([1u8; 32]), Pubkey([2u8; 32]), Pubkey([3u8; 32]));
+        let rate = sats_per_token(1_000_000, 1_500_001_000).unwrap();
+        assert_eq!(rate, 66_666);
+        let balances = [(bob, 500_000_000), (alice, 600_000_000), (carol, 1_000), (alice, 400_000_000)];
+        assert_eq!(entitlements(&balances, rate).unwrap(), vec![
+            DistributionEntitlement { holder: alice, balance: 1_000_000_000, entitlement_sats: 666_660 },
+            DistributionEntitlement { holder: bob, balance: 500_000_000, entitlement_sats: 333_330 },
+        ]);
+
+        // An entitlement of exactly the dust limit is paid
+        let dust_balance = mul_div(DISTRIBUTION_DUST_SATS, 10u64.pow(OVT_DECIMALS as u32), rate).unwrap() + 1;
+        let paid = entitlements(&[(carol, dust_balance)], rate).unwrap();
+        assert_eq!(paid[0].entitlement_sats, DISTRIBUTION_DUST_SATS);
+        assert!(entitlements(&[(carol, dust_balance - 2)], rate).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_distribute_and_claim() {
+        let mut fixture = Fixture::new();
+        let alice = fixture.create_signer();
+        let bob = fixture.create_signer();
+        let carol = fixture.create_signer();
+        let mut state = fixture.state();
+        state.total_supply = 1_500_001_000;
+        state.nav_sats = 30_000_000;
+        fixture.set_state(&state);
+
+        // The admins build the entitlements off-chain from a snapshot
+        let rate = sats_per_token(1_000_000, 1_500_001_000).unwrap();
+        let entitlements = entitlements(&[(alice, 1_000_000_000), (bob, 500_000_000), (carol, 1_000)], rate).unwrap();
+        let leaves = leaves(&entitlements);
+        let distributed_sats = entitlements.iter().map(|entitlement| entitlement.entitlement_sats).sum();
+        assert_eq!(distributed_sats, 999_990);
+        let instruction = OVTInstruction::Distribute {
+            amount_sats: 1_000_000,
+            total_supply: 1_500_001_000,
+            distributed_sats,
+            merkle_root: merkle::root(&leaves),
+        };
+
+        // Only the proceeds of an exited position are distributed, once, at
+        // the address derived from the position
+        let active = position(&mut fixture, PositionStatus::Active);
+        let distribution = fixture.distribution(&active);
+        let err = distribute(&mut fixture, distribution, active, &instruction).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidDistribution");
+        let exited = position(&mut fixture, PositionStatus::Exited);
+        let elsewhere = fixture.create_system_account();
+        let err = distribute(&mut fixture, elsewhere, exited, &instruction).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid argument");
+        let distribution = fixture.distribution(&exited);
+
+        // The entitlements cannot come to more than the proceeds
+        let inflated = OVTInstruction::Distribute {
+            amount_sats: 1_000_000,
+            total_supply: 1_500_001_000,
+            distributed_sats: 1_000_001,
+            merkle_root: merkle::root(&leaves),
+        };
+        let err = distribute(&mut fixture, distribution, exited, &inflated).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidDistribution");
+
+        let events = distribute(&mut fixture, distribution, exited, &instruction).unwrap();
+        assert_eq!(events, vec![OVTEvent::Distributed {
+            distribution,
+            position: exited,
+            amount_sats: 1_000_000,
+            distributed_sats,
+            sats_per_token: rate,
+            merkle_root: merkle::root(&leaves),
+        }]);
+        let stored: Distribution = fixture.client.get_account_data(&distribution).unwrap();
+        assert_eq!((stored.state, stored.position, stored.total_supply), (fixture.state, exited, 1_500_001_000));
+        // Only the entitlements leave the NAV; rounding and dust stay
+        assert_eq!(fixture.state().nav_sats, 30_000_000 - 999_990);
+        let err = distribute(&mut fixture, distribution, exited, &instruction).unwrap_err();
+        assert_eq!(err.to_string(), "AccountAlreadyInitialized");
+
+        // Claims must match a leaf exactly and be made by its holder
+        let index = |holder: Pubkey| entitlements.iter().position(|entitlement| entitlement.holder == holder).unwrap();
+        let alice_entitlement = entitlements[index(alice)].clone();
+        let alice_proof = merkle::proof(&leaves, index(alice));
+        let overclaim = DistributionEntitlement { entitlement_sats: 666_661, ..alice_entitlement.clone() };
+        let err = claim(&mut fixture, distribution, &overclaim, alice_proof.clone()).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidDistribution");
+        let as_bob = DistributionEntitlement { holder: bob, ..alice_entitlement.clone() };
+        let err = claim(&mut fixture, distribution, &as_bob, alice_proof.clone()).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidDistribution");
+        let dust = DistributionEntitlement { holder: carol, balance: 1_000, entitlement_sats: 0 };
+        let err = claim(&mut fixture, distribution, &dust, Vec::new()).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidDistribution");
+
+        let obligation = fixture.distribution_claim(&distribution, &alice);
+        let events = claim(&mut fixture, distribution, &alice_entitlement, alice_proof.clone()).unwrap();
+        assert_eq!(events, vec![OVTEvent::DistributionClaimed {
+            distribution,
+            holder: alice,
+            obligation,
+            payout_sats: 666_660,
+        }]);
+        let recorded: RedemptionObligation = fixture.client.get_account_data(&obligation).unwrap();
+        assert_eq!((recorded.holder, recorded.ovt_amount, recorded.payout_sats), (alice, 0, 666_660));
+        assert!(!recorded.is_settled());
+        let err = claim(&mut fixture, distribution, &alice_entitlement, alice_proof).unwrap_err();
+        assert_eq!(err.to_string(), "DistributionAlreadyClaimed");
+
+        claim(&mut fixture, distribution, &entitlements[index(bob)], merkle::proof(&leaves, index(bob))).unwrap();
+        let stored: Distribution = fixture.client.get_account_data(&distribution).unwrap();
+        assert_eq!(stored.claimed_sats, stored.distributed_sats);
+        assert_eq!(stored.claimed_balance, 1_500_000_000);
+    }
+
+    #[test]
+    fn test_claims_bounded_by_totals() {
+        let mut fixture = Fixture::new();
+        let alice = fixture.create_signer();
+        let bob = fixture.create_signer();
+        let mallory = fixture.create_signer();
+        let mut state = fixture.state();
+        state.total_supply = 1_000_000_000;
+        state.nav_sats = 30_000_000;
+        fixture.set_state(&state);
+
+        // A root that lists more OVT than the snapshot supply, and an
+        // entitlement off the committed rate
+        let rate = sats_per_token(1_000_000, 1_000_000_000).unwrap();
+        let mut entitlements = entitlements(&[(alice, 600_000_000), (bob, 600_000_000)], rate).unwrap();
+        entitlements.push(DistributionEntitlement { holder: mallory, balance: 1_000, entitlement_sats: 600_000 });
+        let leaves = leaves(&entitlements);
+        let position = position(&mut fixture, PositionStatus::Exited);
+        let distribution = fixture.distribution(&position);
+        distribute(&mut fixture, distribution, position, &OVTInstruction::Distribute {
+            amount_sats: 1_000_000,
+            total_supply: 1_000_000_000,
+            distributed_sats: 1_000_000,
+            merkle_root: merkle::root(&leaves),
+        }).unwrap();
+
+        // Claims are held to the committed rate and snapshot supply even
+        // where the root lets them through
+        let err = claim(&mut fixture, distribution, &entitlements[2], merkle::proof(&leaves, 2)).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidDistribution");
+        claim(&mut fixture, distribution, &entitlements[0], merkle::proof(&leaves, 0)).unwrap();
+        let err = claim(&mut fixture, distribution, &entitlements[1], merkle::proof(&leaves, 1)).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidDistribution");
+
+        // A distribution of another fund cannot be claimed against this one
+        let stored: Distribution = fixture.client.get_account_data(&distribution).unwrap();
+        let foreign = fixture.create_account();
+        fixture.client.set_account_data(&foreign, &Distribution { state: Pubkey::new_unique(), ..stored }).unwrap();
+        let err = claim(&mut fixture, foreign, &entitlements[1], merkle::proof(&leaves, 1)).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidDistribution");
+    }
+}
@@ -0,0 +1,227 @@
+//! Per-epoch issuance and burn budgets. Mints, including fee issuance, and
+//! burns by buybacks and redemptions are counted against the supply at the
+//! start of the epoch, so a run of small changes cannot move supply further
+//! than one large one. Epochs are aligned to multiples of the epoch length
+//! and roll over on the first mint or burn after the boundary.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use crate::mock_sdk::{
+    ProgramContext,
+    ProgramResult,
+    ProgramError,
+};
+use crate::{
+    error::OVTError,
+    events::OVTEvent,
+    math::{mul_div, BPS_DENOMINATOR},
+    msg,
+    OVTProgram,
+    OVTState,
+    MAX_SUPPLY_CHANGE_BPS,
+};
+
+/// Longest epoch accepted
+pub const MAX_EPOCH_SECS: u64 = 30 * 24 * 3_600;
+
+/// Governance-controlled epoch length and budgets. Budgets are in basis
+/// points of the supply at the start of the epoch.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EpochParams {
+    pub epoch_secs: u64,
+    /// Most OVT minted per epoch
+    pub max_mint_bps: u64,
+    /// Most OVT burned by buybacks and redemptions per epoch
+    pub max_burn_bps: u64,
+}
+
+impl Default for EpochParams {
+    fn default() -> Self {
+        Self {
+            epoch_secs: 24 * 3_600,
+            max_mint_bps: MAX_SUPPLY_CHANGE_BPS,
+            max_burn_bps: MAX_SUPPLY_CHANGE_BPS,
+        }
+    }
+}
+
+impl EpochParams {
+    pub fn validate(&self) -> ProgramResult {
+        if self.epoch_secs == 0
+            || self.epoch_secs > MAX_EPOCH_SECS
+            || self.max_mint_bps > BPS_DENOMINATOR
+            || self.max_burn_bps > BPS_DENOMINATOR
+        {
+            return Err(OVTError::InvalidEpochParams.into());
+        }
+        Ok(())
+    }
+
+    /// Whether these budgets let through more than `current` does. A shorter
+    /// epoch refills the budget sooner, so it counts as wider.
+    pub fn widens(&self, current: &EpochParams) -> bool {
+        self.max_mint_bps > current.max_mint_bps
+            || self.max_burn_bps > current.max_burn_bps
+            || self.epoch_secs < current.epoch_secs
+    }
+
+    /// Start of the epoch `now` falls in
+    pub fn epoch_start(&self, now: u64) -> u64 {
+        now - now % self.epoch_secs
+    }
+}
+
+impl OVTState {
+    /// Reset the counters if `now` is past the current epoch
+    pub fn roll_epoch(&mut self, now: u64) {
+        let start = self.epoch.epoch_start(now);
+        if start != self.epoch_start {
+            self.epoch_start = start;
+            self.epoch_start_supply = self.total_supply;
+            self.epoch_minted = 0;
+            self.epoch_burned = 0;
+        }
+    }
+
+    /// Count `amount` minted at `now` against the epoch budget. The first
+    /// issuance of a fund is not bounded, and the budgets of the rest of its
+    /// epoch are shares of the supply it creates.
+    pub fn record_mint(&mut self, amount: u64, now: u64) -> ProgramResult {
+        self.roll_epoch(now);
+        if self.epoch_start_supply == 0 {
+            self.epoch_start_supply = self.total_supply.checked_add(amount).ok_or(OVTError::ArithmeticOverflow)?;
+            return Ok(());
+        }
+        self.epoch_minted = Self::within_budget(self.epoch_minted, amount, self.epoch_start_supply, self.epoch.max_mint_bps)?;
+        Ok(())
+    }
+
+    /// Count `amount` burned by a buyback or redemption at `now` against the
+    /// epoch budget
+    pub fn record_burn(&mut self, amount: u64, now: u64) -> ProgramResult {
+        self.roll_epoch(now);
+        self.epoch_burned = Self::within_budget(self.epoch_burned, amount, self.epoch_start_supply, self.epoch.max_burn_bps)?;
+        Ok(())
+    }
+
+    fn within_budget(used: u64, amount: u64, start_supply: u64, budget_bps: u64) -> Result<u64, ProgramError> {
+        let used = used.checked_add(amount).ok_or(OVTError::ArithmeticOverflow)?;
+        let budget = mul_div(start_supply, budget_bps, BPS_DENOMINATOR)?;
+        if used > budget {
+            msg!("Epoch budget exceeded: {} of {} OVT", used, budget);
+            return Err(OVTError::InvalidSupplyChange.into());
+        }
+        Ok(used)
+    }
+}
+
+impl OVTProgram {
+    /// Change the epoch length and budgets. What the current epoch has used
+    /// carries over into the epoch of the new length that `now` falls in.
+    /// Widening a budget is timelocked.
+    pub(crate) fn process_set_epoch_params(ctx: &ProgramContext, epoch: EpochParams) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+
+        let mut state: OVTState = state_info.get_data()?;
+        Self::verify_admin_quorum(ctx, &state)?;
+        epoch.validate()?;
+
+        let now = ctx.clock()?.unix_timestamp;
+        state.roll_epoch(now);
+        state.epoch = epoch;
+        state.epoch_start = epoch.epoch_start(now);
+        ctx.emit(&OVTEvent::EpochParamsUpdated { epoch })?;
+        state_info.set_data(&state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock_sdk::AccountMeta, test_fixtures::Fixture, OVTInstruction};
+
+    #[test]
+    fn test_burn_budget() {
+        let mut fixture = Fixture::new();
+        let mut state = fixture.state();
+        state.total_supply = 1_000_000;
+        state.nav_sats = 1_000_000;
+        fixture.set_state(&state);
+        let source = fixture.token_account(fixture.authority());
+        fixture.set_balance(&source, 1_000_000);
+
+        let burn = |fixture: &mut Fixture, payment_vout, payment_amount_sats| {
+            let accounts = vec![
+                AccountMeta::new(fixture.state, true),
+                AccountMeta::new_readonly(fixture.authority(), true),
+                AccountMeta::new(fixture.payment_marker(Fixture::PAYMENT_TXID, payment_vout), true),
+                AccountMeta::new_readonly(fixture.system_program, false),
+                AccountMeta::new(source, true),
+            ];
+            fixture.process(accounts, &OVTInstruction::BuybackBurn {
+                payment_txid: Fixture::PAYMENT_TXID.to_string(),
+                payment_vout,
+                payment_amount_sats,
+            })
+        };
+
+        // Each burn is under 10% of supply, but together they may not
+        // take more than 10% of the supply the epoch started with
+        burn(&mut fixture, 0, 40_000).unwrap();
+        burn(&mut fixture, 1, 40_000).unwrap();
+        assert_eq!(fixture.state().epoch_burned, 80_000);
+        let err = burn(&mut fixture, 2, 40_000).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidSupplyChange");
+        burn(&mut fixture, 2, 20_000).unwrap();
+        let state = fixture.state();
+        assert_eq!(state.epoch_burned, 100_000);
+        assert_eq!(state.epoch_start_supply, 1_000_000);
+        assert_eq!(state.total_supply, 900_000);
+
+        // Redemptions draw on the same budget
+        let redeem = |fixture: &mut Fixture, amount| {
+            let accounts = vec![
+                AccountMeta::new(fixture.state, true),
+                AccountMeta::new(fixture.create_system_account(), true),
+                AccountMeta::new_readonly(fixture.authority(), true),
+                AccountMeta::new_readonly(fixture.authority(), true),
+                AccountMeta::new_readonly(fixture.system_program, false),
+                AccountMeta::new(source, true),
+            ];
+            fixture.process_raw(accounts, &OVTInstruction::Redeem {
+                amount,
+                btc_address: "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4".to_string(),
+            })
+        };
+        let err = redeem(&mut fixture, 1).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidSupplyChange");
+
+        // The budget refills at the next boundary, as a share of the supply then
+        let boundary = state.epoch.epoch_start(fixture.client.clock().unix_timestamp) + state.epoch.epoch_secs;
+        fixture.client.advance_clock(boundary - fixture.client.clock().unix_timestamp);
+        burn(&mut fixture, 3, 40_000).unwrap();
+        redeem(&mut fixture, 5_000).unwrap();
+        let state = fixture.state();
+        assert_eq!(state.epoch_start, boundary);
+        assert_eq!(state.epoch_start_supply, 900_000);
+        assert_eq!(state.epoch_burned, 45_000);
+
+        // Tightening applies at once and keeps what the epoch has used;
+        // loosening or shortening the epoch waits out the timelock
+        let set = |fixture: &mut Fixture, epoch: EpochParams| {
+            fixture.process(vec![AccountMeta::new(fixture.state, true)], &OVTInstruction::SetEpochParams { epoch })
+        };
+        let tighter = EpochParams { max_burn_bps: 500, ..state.epoch };
+        let events = set(&mut fixture, tighter).unwrap();
+        assert_eq!(events, vec![OVTEvent::EpochParamsUpdated { epoch: tighter }]);
+        assert_eq!(fixture.state().epoch_burned, 45_000);
+        let err = burn(&mut fixture, 4, 1_000).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidSupplyChange");
+
+        let err = set(&mut fixture, EpochParams::default()).unwrap_err();
+        assert_eq!(err.to_string(), "TimelockRequired");
+        let err = set(&mut fixture, EpochParams { epoch_secs: 3_600, ..tighter }).unwrap_err();
+        assert_eq!(err.to_string(), "TimelockRequired");
+        assert!(EpochParams { epoch_secs: 0, ..tighter }.validate().is_err());
+        assert!(EpochParams { max_mint_bps: 10_001, ..tighter }.validate().is_err());
+    }
+}
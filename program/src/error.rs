@@ -59,6 +59,78 @@ pub enum OVTError {
 
     #[error("UTXO verification failed")]
     UTXOVerificationFailed,
+
+    #[error("Invalid portfolio position")]
+    InvalidPosition,
+
+    #[error("Position not found")]
+    PositionNotFound,
+
+    #[error("Position already exited")]
+    PositionAlreadyExited,
+
+    #[error("Invalid Bitcoin address")]
+    InvalidBitcoinAddress,
+
+    #[error("Redemption not found")]
+    RedemptionNotFound,
+
+    #[error("Redemption already settled")]
+    RedemptionAlreadySettled,
+
+    #[error("Invalid admin configuration")]
+    InvalidAdminConfig,
+
+    #[error("Invalid proposal")]
+    InvalidProposal,
+
+    #[error("Proposal expired")]
+    ProposalExpired,
+
+    #[error("Proposal already executed")]
+    ProposalAlreadyExecuted,
+
+    #[error("Proposal cancelled")]
+    ProposalCancelled,
+
+    #[error("Operation must go through the timelock")]
+    TimelockRequired,
+
+    #[error("Timelock delay has not elapsed")]
+    TimelockNotElapsed,
+
+    #[error("Instruction class is paused")]
+    InstructionPaused,
+
+    #[error("Payment already processed")]
+    PaymentAlreadyProcessed,
+
+    #[error("Invalid NAV history account")]
+    InvalidNavHistory,
+
+    #[error("Invalid fee parameters")]
+    InvalidFeeParams,
+
+    #[error("Invalid vesting account")]
+    InvalidVesting,
+
+    #[error("Nothing vested to claim")]
+    NothingVested,
+
+    #[error("Rejected by the investor allowlist")]
+    AllowlistRejected,
+
+    #[error("Invalid epoch parameters")]
+    InvalidEpochParams,
+
+    #[error("Invalid distribution")]
+    InvalidDistribution,
+
+    #[error("Distribution already claimed")]
+    DistributionAlreadyClaimed,
+
+    #[error("Account already initialized")]
+    AccountAlreadyInitialized,
 }
 
 impl From<OVTError> for ProgramError {
@@ -0,0 +1,267 @@
+use crate::mock_sdk::Pubkey;
+use borsh::{BorshDeserialize, BorshSerialize};
+use crate::{
+    allowlist::{AllowlistEntry, AllowlistParams},
+    circuit_breaker::CircuitBreakerParams,
+    epoch::EpochParams,
+    fees::FeeParams,
+    runes_client::PositionStatus,
+};
+
+/// Program activity for indexers and dashboards. Each event is emitted to the
+/// transaction log as a base64 borsh data line. New variants are appended so
+/// existing ones keep their tags.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub enum OVTEvent {
+    Initialized {
+        admin_config: Pubkey,
+        admins: Vec<Pubkey>,
+        threshold: u8,
+    },
+    NavUpdated {
+        previous_nav_sats: u64,
+        nav_sats: u64,
+        timestamp: u64,
+    },
+    SupplyBurned {
+        payment_txid: String,
+        payment_vout: u32,
+        payment_amount_sats: u64,
+        amount: u64,
+        total_supply: u64,
+    },
+    Minted {
+        recipient: Pubkey,
+        payment_txid: String,
+        payment_vout: u32,
+        payment_amount_sats: u64,
+        amount: u64,
+        total_supply: u64,
+    },
+    Redeemed {
+        holder: Pubkey,
+        obligation: Pubkey,
+        amount: u64,
+        payout_sats: u64,
+        btc_address: String,
+    },
+    RedemptionSettled {
+        obligation: Pubkey,
+        payout_txid: String,
+        payout_vout: u32,
+    },
+    SAFEAdded {
+        safe_id: u64,
+        amount_sats: u64,
+    },
+    SAFEConverted {
+        safe_id: u64,
+        investor: Pubkey,
+        amount: u64,
+        total_supply: u64,
+    },
+    PositionAdded {
+        position: Pubkey,
+        name: String,
+        amount: u64,
+    },
+    PositionExited {
+        position: Pubkey,
+        name: String,
+    },
+    CircuitBreakerUpdated {
+        params: CircuitBreakerParams,
+        baseline_nav_sats: u64,
+    },
+    /// The admin set, threshold or timelock settings changed
+    AdminChanged {
+        admins: Vec<Pubkey>,
+        threshold: u8,
+    },
+    TreasuryRotated {
+        previous_treasury_pubkey_bytes: [u8; 33],
+        treasury_pubkey_bytes: [u8; 33],
+    },
+    Paused {
+        classes: u8,
+        paused: u8,
+    },
+    Unpaused {
+        classes: u8,
+        paused: u8,
+    },
+    StateMigrated {
+        from_version: u8,
+        to_version: u8,
+    },
+    ProposalCreated {
+        proposal: Pubkey,
+        proposer: Pubkey,
+        expires_at: u64,
+    },
+    ProposalApproved {
+        proposal: Pubkey,
+        approver: Pubkey,
+        approvals: u8,
+    },
+    ProposalCancelled {
+        proposal: Pubkey,
+    },
+    ProposalExecuted {
+        proposal: Pubkey,
+    },
+    TimelockQueued {
+        proposal: Pubkey,
+        eta: u64,
+    },
+    TimelockExecuted {
+        proposal: Pubkey,
+    },
+    TimelockVetoed {
+        proposal: Pubkey,
+    },
+    NavHistoryInitialized {
+        nav_history: Pubkey,
+        twap_window_secs: u64,
+    },
+    TwapWindowUpdated {
+        twap_window_secs: u64,
+    },
+    TokenAccountInitialized {
+        token_account: Pubkey,
+        owner: Pubkey,
+    },
+    Transferred {
+        source: Pubkey,
+        destination: Pubkey,
+        amount: u64,
+    },
+    DelegateApproved {
+        token_account: Pubkey,
+        delegate: Pubkey,
+        amount: u64,
+    },
+    DelegateRevoked {
+        token_account: Pubkey,
+    },
+    TokenAccountClosed {
+        token_account: Pubkey,
+        destination: Pubkey,
+    },
+    FeesAccrued {
+        management_fee: u64,
+        performance_fee: u64,
+        high_water_mark_sats: u64,
+        total_supply: u64,
+    },
+    FeeParamsUpdated {
+        fees: FeeParams,
+    },
+    VestingCreated {
+        vesting: Pubkey,
+        beneficiary: Pubkey,
+        total_amount: u64,
+    },
+    VestedClaimed {
+        vesting: Pubkey,
+        amount: u64,
+    },
+    VestingRevoked {
+        vesting: Pubkey,
+        returned: u64,
+    },
+    AllowlistUpdated {
+        allowlist: AllowlistParams,
+    },
+    AllowlistEntrySet {
+        entry: AllowlistEntry,
+    },
+    AllowlistEntryRemoved {
+        holder: Pubkey,
+    },
+    EpochParamsUpdated {
+        epoch: EpochParams,
+    },
+    Distributed {
+        distribution: Pubkey,
+        position: Pubkey,
+        amount_sats: u64,
+        distributed_sats: u64,
+        sats_per_token: u64,
+        merkle_root: [u8; 32],
+    },
+    DistributionClaimed {
+        distribution: Pubkey,
+        holder: Pubkey,
+        obligation: Pubkey,
+        payout_sats: u64,
+    },
+    PositionUpdated {
+        position: Pubkey,
+        amount: u64,
+        price_per_token: u64,
+        status: PositionStatus,
+    },
+    OraclePublisherSet {
+        previous_publisher: Pubkey,
+        publisher: Pubkey,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock_sdk::{log::decode_log_line, AccountMeta, LogSink},
+        test_fixtures::Fixture,
+        OVTInstruction,
+    };
+
+    #[test]
+    fn test_events_are_captured() {
+        let mut fixture = Fixture::new();
+        let new_admin = fixture.create_signer();
+        let accounts = vec![
+            AccountMeta::new_readonly(fixture.state, false),
+            AccountMeta::new(fixture.admin_config, true),
+        ];
+        let proposal = fixture.create_system_account();
+        let approvers = fixture.admins[..3].to_vec();
+        let instruction = OVTInstruction::AddAdmin { admin: new_admin };
+        fixture.propose_and_approve(proposal, &instruction, &accounts, &approvers).unwrap();
+
+        // Queueing and executing a timelocked change each report what happened
+        let events = fixture.execute(proposal, &accounts).unwrap();
+        let eta = fixture.proposal(&proposal).eta;
+        assert_eq!(events, vec![OVTEvent::TimelockQueued { proposal, eta }]);
+
+        fixture.client.advance_clock(Fixture::TIMELOCK_DELAY_SECS);
+        let events = fixture.execute(proposal, &accounts).unwrap();
+        let mut admins = fixture.admins.clone();
+        admins.push(new_admin);
+        assert_eq!(events, vec![
+            OVTEvent::AdminChanged { admins, threshold: Fixture::THRESHOLD },
+            OVTEvent::TimelockExecuted { proposal },
+            OVTEvent::ProposalExecuted { proposal },
+        ]);
+    }
+
+    #[test]
+    fn test_log_lines_round_trip() {
+        let sink = LogSink::default();
+        let event = OVTEvent::NavUpdated { previous_nav_sats: 1_000_000, nav_sats: 1_100_000, timestamp: 1_700_000_000 };
+        sink.emit(&event).unwrap();
+        sink.emit(&OVTEvent::SAFEAdded { safe_id: 7, amount_sats: 50_000_000 }).unwrap();
+
+        let lines = sink.log_lines();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("Program data: "));
+        assert_eq!(decode_log_line::<OVTEvent>(&lines[0]), Some(event));
+        assert_eq!(decode_log_line::<OVTEvent>(&lines[1]), Some(OVTEvent::SAFEAdded { safe_id: 7, amount_sats: 50_000_000 }));
+        assert_eq!(decode_log_line::<OVTEvent>("Program log: NAV updated"), None);
+
+        // Clones log into the same sink
+        sink.clone().emit(&OVTEvent::SAFEAdded { safe_id: 8, amount_sats: 50_000_000 }).unwrap();
+        assert_eq!(sink.events::<OVTEvent>().unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,426 @@
+//! Management and performance fees, paid by minting OVT to the fee recipient.
+//!
+//! Fees accrue over the time between NAV updates, so each accrual charges the
+//! period up to `last_nav_update` against the NAV measured then. Fee units
+//! are issued so that, after issue, they are worth exactly the fee in sats.
+
+use crate::mock_sdk::{
+    Pubkey,
+    ProgramContext,
+    ProgramResult,
+    ProgramError,
+    TokenAccount,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use crate::{
+    error::OVTError,
+    events::OVTEvent,
+    math::{mul_div, BPS_DENOMINATOR},
+    OVTProgram,
+    OVTState,
+    OVT_DECIMALS,
+};
+
+pub const SECS_PER_YEAR: u64 = 365 * 24 * 3_600;
+/// Highest annual management fee accepted
+pub const MAX_MANAGEMENT_FEE_BPS: u64 = 500;
+/// Highest share of gains above the high-water mark accepted
+pub const MAX_PERFORMANCE_FEE_BPS: u64 = 5_000;
+
+/// Governance-controlled fee rates, in basis points
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FeeParams {
+    /// Annual fee on NAV, charged pro rata
+    pub management_fee_bps: u64,
+    /// Fee on per-token NAV gains above the high-water mark
+    pub performance_fee_bps: u64,
+    /// OVT account fees are minted to
+    pub fee_recipient: Pubkey,
+}
+
+/// OVT issued by one accrual and the high-water mark after it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FeeAccrual {
+    pub management_fee: u64,
+    pub performance_fee: u64,
+    pub high_water_mark_sats: u64,
+}
+
+impl FeeParams {
+    pub fn validate(&self) -> ProgramResult {
+        if self.management_fee_bps > MAX_MANAGEMENT_FEE_BPS || self.performance_fee_bps > MAX_PERFORMANCE_FEE_BPS {
+            return Err(OVTError::InvalidFeeParams.into());
+        }
+        let charges_fees = self.management_fee_bps > 0 || self.performance_fee_bps > 0;
+        if charges_fees && self.fee_recipient == Pubkey::new() {
+            return Err(OVTError::InvalidFeeParams.into());
+        }
+        Ok(())
+    }
+
+    /// Whether these rates charge more than `current`, or pay someone else
+    pub fn raises(&self, current: &FeeParams) -> bool {
+        self.management_fee_bps > current.management_fee_bps
+            || self.performance_fee_bps > current.performance_fee_bps
+            || self.fee_recipient != current.fee_recipient
+    }
+
+    /// Fees on `nav_sats` backing `total_supply` over `elapsed_secs`. The
+    /// performance fee is charged on the per-token NAV left after the
+    /// management fee, and the mark moves up to the per-token NAV left after
+    /// both.
+    pub fn accrue(
+        &self,
+        nav_sats: u64,
+        total_supply: u64,
+        high_water_mark_sats: u64,
+        elapsed_secs: u64,
+    ) -> Result<FeeAccrual, ProgramError> {
+        let one_token = 10u64.pow(OVT_DECIMALS as u32);
+        if total_supply == 0 || nav_sats == 0 {
+            return Ok(FeeAccrual { management_fee: 0, performance_fee: 0, high_water_mark_sats });
+        }
+
+        let management_sats = (nav_sats as u128)
+            .checked_mul(self.management_fee_bps as u128 * elapsed_secs as u128)
+            .ok_or(OVTError::ArithmeticOverflow)?
+            / (BPS_DENOMINATOR as u128 * SECS_PER_YEAR as u128);
+        let management_sats = u64::try_from(management_sats).map_err(|_| OVTError::ArithmeticOverflow)?;
+        let management_fee = units_worth(management_sats, nav_sats, total_supply)?;
+        let supply = total_supply.checked_add(management_fee).ok_or(OVTError::ArithmeticOverflow)?;
+
+        let nav_per_token = mul_div(nav_sats, one_token, supply)?;
+        let performance_fee = if nav_per_token > high_water_mark_sats {
+            let gain_sats = mul_div(nav_per_token - high_water_mark_sats, supply, one_token)?;
+            let performance_sats = mul_div(gain_sats, self.performance_fee_bps, BPS_DENOMINATOR)?;
+            units_worth(performance_sats, nav_sats, supply)?
+        } else {
+            0
+        };
+        let supply = supply.checked_add(performance_fee).ok_or(OVTError::ArithmeticOverflow)?;
+
+        Ok(FeeAccrual {
+            management_fee,
+            performance_fee,
+            high_water_mark_sats: high_water_mark_sats.max(mul_div(nav_sats, one_token, supply)?),
+        })
+    }
+}
+
+/// OVT base units to issue against `nav_sats` backing `supply` so that the
+/// new units are worth `fee_sats` once issued
+fn units_worth(fee_sats: u64, nav_sats: u64, supply: u64) -> Result<u64, ProgramError> {
+    if fee_sats == 0 {
+        return Ok(0);
+    }
+    if fee_sats >= nav_sats {
+        return Err(OVTError::ArithmeticOverflow.into());
+    }
+    mul_div(supply, fee_sats, nav_sats - fee_sats)
+}
+
+impl OVTProgram {
+    pub(crate) fn process_accrue_fees(ctx: &ProgramContext) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+
+        let mut state: OVTState = state_info.get_data()?;
+        Self::verify_admin_quorum(ctx, &state)?;
+        Self::accrue_fees(ctx, &mut state)?;
+        state_info.set_data(&state)
+    }
+
+    /// Change the fee rates. Fees due at the old rates are accrued first.
+    pub(crate) fn process_set_fee_params(ctx: &ProgramContext, fees: FeeParams) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+
+        let mut state: OVTState = state_info.get_data()?;
+        Self::verify_admin_quorum(ctx, &state)?;
+        fees.validate()?;
+        Self::accrue_fees(ctx, &mut state)?;
+
+        state.fees = fees;
+        ctx.emit(&OVTEvent::FeeParamsUpdated { fees })?;
+        state_info.set_data(&state)
+    }
+
+    /// Charge the fees due up to the last NAV update, minting them to the fee
+    /// recipient, which may appear anywhere in the account list along with
+    /// its allowlist entry. Fee issuance counts against the epoch mint
+    /// budget. The first accrual only starts the fee clock.
+    /// Instructions that price OVT against the NAV accrue first, so they
+    /// never trade at a per-token NAV the fees due have not diluted yet.
+    pub(crate) fn accrue_fees(ctx: &ProgramContext, state: &mut OVTState) -> ProgramResult {
+        if state.last_fee_accrual == 0 || state.total_supply == 0 {
+            state.last_fee_accrual = state.last_nav_update;
+            state.high_water_mark_sats = state.high_water_mark_sats.max(state.nav_per_token_sats()?);
+            return Ok(());
+        }
+        let elapsed_secs = state.last_nav_update.saturating_sub(state.last_fee_accrual);
+        if elapsed_secs == 0 {
+            return Ok(());
+        }
+
+        let accrual = state.fees.accrue(
+            state.nav_sats,
+            state.total_supply,
+            state.high_water_mark_sats,
+            elapsed_secs,
+        )?;
+        let minted = accrual.management_fee
+            .checked_add(accrual.performance_fee)
+            .ok_or(OVTError::ArithmeticOverflow)?;
+        if minted > 0 {
+            state.record_mint(minted, ctx.clock()?.unix_timestamp)?;
+            let recipient_info = ctx.accounts.iter()
+                .find(|info| info.key == state.fees.fee_recipient)
+                .ok_or(OVTError::InvalidTokenAccount)?;
+            let mut recipient = TokenAccount::load(&ctx.program_id, &ctx.get(0)?.key, recipient_info)?;
+            recipient.credit(minted)?;
+            Self::verify_allowlisted(ctx, state, &recipient.owner, &recipient_info.key, recipient.amount)?;
+            recipient_info.set_data(&recipient)?;
+        }
+
+        state.total_supply = state.total_supply
+            .checked_add(minted)
+            .ok_or(OVTError::ArithmeticOverflow)?;
+        state.high_water_mark_sats = accrual.high_water_mark_sats;
+        state.last_fee_accrual = state.last_nav_update;
+
+        ctx.emit(&OVTEvent::FeesAccrued {
+            management_fee: accrual.management_fee,
+            performance_fee: accrual.performance_fee,
+            high_water_mark_sats: accrual.high_water_mark_sats,
+            total_supply: state.total_supply,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        allowlist::{AllowlistEntry, AllowlistParams},
+        mock_sdk::AccountMeta,
+        pause::PAUSE_MINT,
+        test_fixtures::Fixture,
+        OVTInstruction,
+    };
+
+    #[test]
+    fn test_fee_math() {
+        let fees = FeeParams {
+            management_fee_bps: 200,
+            performance_fee_bps: 2_000,
+            fee_recipient: Pubkey::new_unique(),
+        };
+
+        // A year at 2% on a flat NAV: the fee units are worth 2% of the NAV
+        let accrual = fees.accrue(1_000_000_000, 1_000_000_000, 100_000_000, SECS_PER_YEAR).unwrap();
+        assert_eq!(accrual.management_fee, 20_408_163);
+        assert_eq!(accrual.performance_fee, 0);
+        assert_eq!(mul_div(accrual.management_fee, 1_000_000_000, 1_020_408_163).unwrap(), 19_999_999);
+        assert_eq!(accrual.high_water_mark_sats, 100_000_000);
+
+        // Half a year is charged half the fee
+        let accrual = fees.accrue(1_000_000_000, 1_000_000_000, 100_000_000, SECS_PER_YEAR / 2).unwrap();
+        assert_eq!(accrual.management_fee, 10_101_010);
+
+        // A 50% gain over the mark pays 20% of the gain and resets the mark
+        let no_management = FeeParams { management_fee_bps: 0, ..fees };
+        let accrual = no_management.accrue(1_500_000_000, 1_000_000_000, 100_000_000, 1).unwrap();
+        assert_eq!(accrual.performance_fee, 71_428_571);
+        assert_eq!(accrual.high_water_mark_sats, 140_000_000);
+        // Below the mark nothing is charged and the mark stays
+        let accrual = no_management.accrue(1_300_000_000, 1_000_000_000, 140_000_000, 1).unwrap();
+        assert_eq!(accrual.performance_fee, 0);
+        assert_eq!(accrual.high_water_mark_sats, 140_000_000);
+
+        // Rates are capped and need a recipient
+        assert!(FeeParams { management_fee_bps: MAX_MANAGEMENT_FEE_BPS + 1, ..fees }.validate().is_err());
+        assert!(FeeParams { performance_fee_bps: MAX_PERFORMANCE_FEE_BPS + 1, ..fees }.validate().is_err());
+        assert!(FeeParams { fee_recipient: Pubkey::new(), ..fees }.validate().is_err());
+        assert!(FeeParams::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_accrue_fees() {
+        let mut fixture = Fixture::new();
+        let recipient = fixture.create_signer();
+        let recipient_account = fixture.token_account(recipient);
+        let holder_account = fixture.token_account(Pubkey::new_unique());
+        let fees = FeeParams {
+            management_fee_bps: 200,
+            performance_fee_bps: 2_000,
+            fee_recipient: recipient_account,
+        };
+
+        let mut state = fixture.state();
+        state.nav_sats = 1_000_000_000;
+        state.total_supply = 1_000_000_000;
+        state.last_nav_update = fixture.client.clock().unix_timestamp;
+        fixture.set_state(&state);
+        fixture.set_balance(&holder_account, 1_000_000_000);
+
+        let accounts = vec![
+            AccountMeta::new(fixture.state, true),
+            AccountMeta::new(recipient_account, true),
+        ];
+
+        // Raising fees waits out the timelock
+        let err = fixture.process(accounts.clone(), &OVTInstruction::SetFeeParams { fees }).unwrap_err();
+        assert_eq!(err.to_string(), "TimelockRequired");
+        let mut proposal_accounts = accounts.clone();
+        proposal_accounts.push(AccountMeta::new_readonly(fixture.admin_config, false));
+        let approvers = fixture.admins[..3].to_vec();
+        let events = fixture.process_timelocked(proposal_accounts, &OVTInstruction::SetFeeParams { fees }, &approvers)
+            .unwrap();
+        assert_eq!(events[0], OVTEvent::FeeParamsUpdated { fees });
+        let state = fixture.state();
+        assert_eq!(state.fees, fees);
+        assert_eq!(state.last_fee_accrual, state.last_nav_update);
+        assert_eq!(state.high_water_mark_sats, 100_000_000);
+
+        // A year on, at a 50% higher NAV, both fees are due
+        let mut state = fixture.state();
+        state.last_nav_update += SECS_PER_YEAR;
+        state.nav_sats = 1_500_000_000;
+        fixture.set_state(&state);
+
+        // Freezing issuance freezes fee minting too
+        let pause = |fixture: &mut Fixture, instruction: &OVTInstruction| {
+            fixture.process(vec![AccountMeta::new(fixture.state, true)], instruction).unwrap();
+        };
+        pause(&mut fixture, &OVTInstruction::Pause { classes: PAUSE_MINT });
+        let err = fixture.process(accounts.clone(), &OVTInstruction::AccrueFees).unwrap_err();
+        assert_eq!(err.to_string(), "InstructionPaused");
+        let err = fixture.process(accounts.clone(), &OVTInstruction::SetFeeParams { fees }).unwrap_err();
+        assert_eq!(err.to_string(), "InstructionPaused");
+        pause(&mut fixture, &OVTInstruction::Unpause { classes: PAUSE_MINT });
+        assert_eq!(fixture.state().total_supply, 1_000_000_000);
+
+        let err = fixture.process(vec![AccountMeta::new(fixture.state, true)], &OVTInstruction::AccrueFees)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "InvalidTokenAccount");
+        let events = fixture.process(accounts.clone(), &OVTInstruction::AccrueFees).unwrap();
+        let expected = fees.accrue(1_500_000_000, 1_000_000_000, 100_000_000, SECS_PER_YEAR).unwrap();
+        assert_eq!(events, vec![OVTEvent::FeesAccrued {
+            management_fee: expected.management_fee,
+            performance_fee: expected.performance_fee,
+            high_water_mark_sats: expected.high_water_mark_sats,
+            total_supply: 1_000_000_000 + expected.management_fee + expected.performance_fee,
+        }]);
+        assert!(expected.management_fee > 0 && expected.performance_fee > 0);
+        let state = fixture.state();
+        assert_eq!(state.total_supply, fixture.balance(&holder_account) + fixture.balance(&recipient_account));
+        assert_eq!(state.high_water_mark_sats, expected.high_water_mark_sats);
+
+        // Nothing more is due until the next NAV update
+        let events = fixture.process(accounts.clone(), &OVTInstruction::AccrueFees).unwrap();
+        assert!(events.is_empty());
+
+        // Lowering fees needs only the admin quorum
+        let lower = FeeParams { performance_fee_bps: 1_000, ..fees };
+        fixture.process(accounts, &OVTInstruction::SetFeeParams { fees: lower }).unwrap();
+        assert_eq!(fixture.state().fees, lower);
+    }
+
+    #[test]
+    fn test_fees_accrue_before_pricing() {
+        let mut fixture = Fixture::new();
+        let recipient = fixture.create_signer();
+        let recipient_account = fixture.token_account(recipient);
+        let holder = fixture.create_signer();
+        let holder_account = fixture.token_account(holder);
+        let fees = FeeParams {
+            management_fee_bps: 200,
+            performance_fee_bps: 2_000,
+            fee_recipient: recipient_account,
+        };
+
+        // A year of fees is due on a NAV that rose 50%
+        let mut state = fixture.state();
+        state.nav_sats = 1_500_000_000;
+        state.total_supply = 1_000_000_000;
+        state.fees = fees;
+        state.high_water_mark_sats = 100_000_000;
+        state.last_fee_accrual = fixture.client.clock().unix_timestamp;
+        state.last_nav_update = state.last_fee_accrual + SECS_PER_YEAR;
+        fixture.set_state(&state);
+        fixture.set_balance(&holder_account, 1_000_000_000);
+        let expected = fees.accrue(1_500_000_000, 1_000_000_000, 100_000_000, SECS_PER_YEAR).unwrap();
+        let diluted_supply = 1_000_000_000 + expected.management_fee + expected.performance_fee;
+
+        // A redemption pays out at the per-token NAV net of the fees due
+        let obligation = fixture.create_system_account();
+        let accounts = vec![
+            AccountMeta::new(fixture.state, true),
+            AccountMeta::new(obligation, true),
+            AccountMeta::new_readonly(holder, true),
+            AccountMeta::new_readonly(holder, true),
+            AccountMeta::new_readonly(fixture.system_program, false),
+            AccountMeta::new(holder_account, true),
+            AccountMeta::new(recipient_account, true),
+        ];
+        let events = fixture.process_raw(accounts, &OVTInstruction::Redeem {
+            amount: 10_000_000,
+            btc_address: "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4".to_string(),
+        }).unwrap();
+        assert_eq!(events[0], OVTEvent::FeesAccrued {
+            management_fee: expected.management_fee,
+            performance_fee: expected.performance_fee,
+            high_water_mark_sats: expected.high_water_mark_sats,
+            total_supply: diluted_supply,
+        });
+        let OVTEvent::Redeemed { payout_sats, .. } = events[1] else {
+            panic!("expected a redemption, got {:?}", events[1]);
+        };
+        assert_eq!(payout_sats, mul_div(10_000_000, 1_500_000_000, diluted_supply).unwrap());
+        let state = fixture.state();
+        assert_eq!(state.total_supply, diluted_supply - 10_000_000);
+        assert_eq!(fixture.balance(&recipient_account), diluted_supply - 1_000_000_000);
+
+        // The fee units count against the mint budget, the redemption
+        // against the burn budget
+        assert_eq!(state.epoch_minted, diluted_supply - 1_000_000_000);
+        assert_eq!(state.epoch_burned, 10_000_000);
+    }
+
+    #[test]
+    fn test_fee_recipient_must_be_allowlisted() {
+        let mut fixture = Fixture::new();
+        let recipient = fixture.create_signer();
+        let recipient_account = fixture.token_account(recipient);
+        let mut state = fixture.state();
+        state.nav_sats = 1_000_000_000;
+        state.total_supply = 1_000_000_000;
+        state.fees = FeeParams { management_fee_bps: 200, performance_fee_bps: 0, fee_recipient: recipient_account };
+        state.high_water_mark_sats = 100_000_000;
+        state.last_fee_accrual = fixture.client.clock().unix_timestamp;
+        state.last_nav_update = state.last_fee_accrual + SECS_PER_YEAR;
+        state.allowlist = AllowlistParams { restricted: true, merkle_root: [0u8; 32] };
+        fixture.set_state(&state);
+
+        // While the fund is restricted, fees only go to an allowlisted holder
+        let entry = fixture.allowlist_entry(&recipient);
+        let accounts = vec![
+            AccountMeta::new(fixture.state, true),
+            AccountMeta::new(recipient_account, true),
+            AccountMeta::new(entry, true),
+        ];
+        let err = fixture.process(accounts.clone(), &OVTInstruction::AccrueFees).unwrap_err();
+        assert_eq!(err.to_string(), "AllowlistRejected");
+        assert_eq!(fixture.balance(&recipient_account), 0);
+
+        let entry_accounts = vec![
+            AccountMeta::new_readonly(fixture.state, false),
+            AccountMeta::new(entry, true),
+            AccountMeta::new_readonly(fixture.authority(), true),
+            AccountMeta::new_readonly(fixture.system_program, false),
+        ];
+        let recipient_entry = AllowlistEntry { holder: recipient, tier: 1, max_balance: 0, expires_at: 0 };
+        fixture.process(entry_accounts, &OVTInstruction::SetAllowlistEntry { entry: recipient_entry }).unwrap();
+        fixture.process(accounts, &OVTInstruction::AccrueFees).unwrap();
+        assert_eq!(fixture.state().total_supply, 1_000_000_000 + fixture.balance(&recipient_account));
+        assert!(fixture.balance(&recipient_account) > 0);
+    }
+}
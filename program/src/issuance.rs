@@ -0,0 +1,182 @@
+use crate::mock_sdk::{
+    Pubkey,
+    ProgramContext,
+    ProgramResult,
+    TokenAccount,
+};
+use crate::{
+    error::OVTError,
+    events::OVTEvent,
+    utxo::verify_treasury_payment,
+    OVTProgram,
+    OVTState,
+};
+
+impl OVTProgram {
+    pub(crate) fn process_mint(
+        ctx: &ProgramContext,
+        payment_txid: &str,
+        payment_vout: u32,
+        payment_amount_sats: u64,
+        recipient: Pubkey,
+    ) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+        let authority_info = ctx.get(1)?;
+        let utxo_info = ctx.get(2)?;
+        let marker_info = ctx.get(3)?;
+        let system_program = ctx.get(4)?;
+        let recipient_info = ctx.get(5)?;
+
+        let mut state: OVTState = state_info.get_data()?;
+        Self::verify_admin_quorum(ctx, &state)?;
+        state.validate_treasury()?;
+        Self::accrue_fees(ctx, &mut state)?;
+        let mut recipient_account = TokenAccount::load(&ctx.program_id, &state_info.key, recipient_info)?;
+        if recipient_account.owner != recipient {
+            return Err(OVTError::InvalidTokenAccount.into());
+        }
+
+        // The payment must have reached the treasury before anything is issued
+        let now = ctx.clock()?.unix_timestamp;
+        let treasury_keys = state.accepted_treasury_keys(now);
+        verify_treasury_payment(utxo_info, payment_amount_sats, &treasury_keys)?;
+        Self::consume_payment(ctx, marker_info, authority_info, system_program, payment_txid, payment_vout)?;
+
+        // Against a TWAP, issue at the higher of it and the spot NAV so a
+        // fresh mark-down cannot be bought into cheaply
+        let ovt_to_mint = match Self::twap_nav_per_token(ctx, &state, now)? {
+            Some(twap) => state.ovt_for_sats_at(payment_amount_sats, twap.max(state.nav_per_token_sats()?))?,
+            None => state.ovt_for_sats(payment_amount_sats)?,
+        };
+        if ovt_to_mint == 0 {
+            return Err(OVTError::InsufficientFunds.into());
+        }
+
+        let new_supply = state.total_supply
+            .checked_add(ovt_to_mint)
+            .ok_or(OVTError::ArithmeticOverflow)?;
+        state.record_mint(ovt_to_mint, now)?;
+
+        // The payment joins the treasury, so the per-token NAV is unchanged,
+        // or rises when issued above the spot NAV
+        state.nav_sats = state.nav_sats
+            .checked_add(payment_amount_sats)
+            .ok_or(OVTError::ArithmeticOverflow)?;
+        state.total_supply = new_supply;
+        recipient_account.credit(ovt_to_mint)?;
+        Self::verify_allowlisted(ctx, &state, &recipient, &recipient_info.key, recipient_account.amount)?;
+
+        ctx.emit(&OVTEvent::Minted {
+            recipient,
+            payment_txid: payment_txid.to_string(),
+            payment_vout,
+            payment_amount_sats,
+            amount: ovt_to_mint,
+            total_supply: state.total_supply,
+        })?;
+        recipient_info.set_data(&recipient_account)?;
+        state_info.set_data(&state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        mock_sdk::{AccountMeta, Pubkey},
+        test_fixtures::Fixture,
+        OVTEvent,
+        OVTInstruction,
+    };
+
+    #[test]
+    fn test_mint() {
+        let mut fixture = Fixture::new();
+        let utxo_account = fixture.create_account();
+        let holder = Pubkey::new_unique();
+        let holder_account = fixture.token_account(holder);
+
+        let mint = |fixture: &mut Fixture, payment_vout, payment_amount_sats| {
+            let accounts = vec![
+                AccountMeta::new(fixture.state, true),
+                AccountMeta::new_readonly(fixture.authority(), true),
+                AccountMeta::new_readonly(utxo_account, false),
+                AccountMeta::new(fixture.payment_marker(Fixture::PAYMENT_TXID, payment_vout), true),
+                AccountMeta::new_readonly(fixture.system_program, false),
+                AccountMeta::new(holder_account, true),
+            ];
+            fixture.process(
+                accounts,
+                &OVTInstruction::Mint {
+                    payment_txid: Fixture::PAYMENT_TXID.to_string(),
+                    payment_vout,
+                    payment_amount_sats,
+                    recipient: holder,
+                },
+            )
+        };
+
+        // The first mint issues one base unit per satoshi
+        let events = mint(&mut fixture, 0, 1_000_000).unwrap();
+        let state = fixture.state();
+        assert!(matches!(
+            events.as_slice(),
+            [OVTEvent::Minted { amount: 1_000_000, total_supply: 1_000_000, .. }]
+        ));
+        assert_eq!(state.total_supply, 1_000_000);
+        assert_eq!(state.nav_sats, 1_000_000);
+        assert_eq!(fixture.balance(&holder_account), 1_000_000);
+
+        // Once the NAV doubles, a payment buys half as much OVT
+        let mut state = state;
+        state.nav_sats = 2_000_000;
+        fixture.set_state(&state);
+        assert_eq!(state.nav_per_token_sats().unwrap(), 200_000_000);
+
+        mint(&mut fixture, 1, 100_000).unwrap();
+        let state = fixture.state();
+        assert_eq!(state.total_supply, 1_050_000);
+        assert_eq!(fixture.balance(&holder_account), 1_050_000);
+        assert_eq!(state.nav_sats, 2_100_000);
+        assert_eq!(state.nav_per_token_sats().unwrap(), 200_000_000);
+
+        // Mints past the epoch budget, 10% of the supply the first mint
+        // created, are rejected until the next epoch
+        let err = mint(&mut fixture, 2, 100_002).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidSupplyChange");
+        mint(&mut fixture, 2, 100_000).unwrap();
+        assert_eq!(fixture.state().epoch_minted, 100_000);
+        let err = mint(&mut fixture, 4, 2).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidSupplyChange");
+        fixture.client.advance_clock(fixture.state().epoch.epoch_secs);
+        mint(&mut fixture, 4, 2).unwrap();
+        assert_eq!(fixture.state().epoch_minted, 1);
+        assert_eq!(fixture.state().epoch_start_supply, 1_100_000);
+
+        // Payments worth less than one base unit are rejected
+        let err = mint(&mut fixture, 5, 1).unwrap_err();
+        assert_eq!(err.to_string(), "InsufficientFunds");
+
+        // Each payment output backs one mint
+        let err = mint(&mut fixture, 1, 100_000).unwrap_err();
+        assert_eq!(err.to_string(), "PaymentAlreadyProcessed");
+
+        // The recipient must own the credited account
+        let other_account = fixture.token_account(Pubkey::new_unique());
+        let mut accounts = vec![
+            AccountMeta::new(fixture.state, true),
+            AccountMeta::new_readonly(fixture.authority(), true),
+            AccountMeta::new_readonly(utxo_account, false),
+            AccountMeta::new(fixture.payment_marker(Fixture::PAYMENT_TXID, 3), true),
+            AccountMeta::new_readonly(fixture.system_program, false),
+            AccountMeta::new(other_account, true),
+        ];
+        accounts.extend(fixture.signers(Fixture::THRESHOLD as usize));
+        let err = fixture.process_raw(accounts, &OVTInstruction::Mint {
+            payment_txid: Fixture::PAYMENT_TXID.to_string(),
+            payment_vout: 3,
+            payment_amount_sats: 10_000,
+            recipient: holder,
+        }).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidTokenAccount");
+    }
+}
@@ -1,8 +1,32 @@
 pub mod error;
+pub mod admin;
 pub mod mock_sdk;
 pub mod system;
 pub mod utxo;
 pub mod runes_client;
+pub mod safe;
+pub mod position;
+pub mod oracle;
+pub mod circuit_breaker;
+pub mod math;
+pub mod issuance;
+pub mod redemption;
+pub mod proposal;
+pub mod timelock;
+pub mod pause;
+pub mod migration;
+pub mod payment;
+pub mod events;
+pub mod nav_history;
+pub mod token;
+pub mod fees;
+pub mod vesting;
+pub mod merkle;
+pub mod allowlist;
+pub mod epoch;
+pub mod distribution;
+#[cfg(test)]
+mod test_fixtures;
 
 use mock_sdk::{
     AccountInfo,
@@ -11,23 +35,47 @@ use mock_sdk::{
     ProgramContext,
     ProgramResult,
     ProgramError,
+    TokenAccount,
 };
 use borsh::{BorshDeserialize, BorshSerialize};
 use bitcoin::PublicKey;
 use crate::{
+    allowlist::AllowlistParams,
+    circuit_breaker::CircuitBreakerParams,
+    epoch::EpochParams,
     error::OVTError,
+    fees::FeeParams,
+    math::{mul_div, ratio_above, ratio_below},
+    oracle::{OracleAccount, NAV_ASSET_ID},
     system::{create_program_account, initialize_account},
+    timelock::TimelockParams,
 };
 
+pub use admin::AdminConfig;
+pub use safe::SAFEData;
+pub use position::PositionData;
+pub use redemption::RedemptionObligation;
+pub use proposal::Proposal;
+pub use events::OVTEvent;
+
 /// OVT Token identifier in Runes protocol
 pub const OVT_RUNE_SYMBOL: &str = "OVT";
 pub const OVT_DECIMALS: u8 = 8;
+/// Minimum number of seconds between two NAV updates
+pub const MIN_NAV_UPDATE_INTERVAL_SECS: u64 = 300;
+/// Largest supply change accepted in a single step, in basis points, and
+/// the default per-epoch mint and burn budget
+pub const MAX_SUPPLY_CHANGE_BPS: u64 = 1_000;
+/// Seconds after a treasury rotation during which payments to the previous
+/// key are still accepted
+pub const TREASURY_ROTATION_GRACE_SECS: u64 = 7 * 24 * 3_600;
 
 #[derive(BorshSerialize, BorshDeserialize)]
 pub struct OVTProgram;
 
-/// Program state storing NAV and treasury data
-#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+/// Program state storing NAV and treasury data. Serialized inside a versioned
+/// envelope, see `migration`.
+#[derive(Clone, Debug)]
 pub struct OVTState {
     /// Current NAV in satoshis
     pub nav_sats: u64,
@@ -37,6 +85,45 @@ pub struct OVTState {
     pub total_supply: u64,
     /// Last NAV update timestamp
     pub last_nav_update: u64,
+    /// Number of open portfolio positions
+    pub position_count: u64,
+    /// Program that owns the price oracle accounts
+    pub oracle_program_id: Pubkey,
+    /// Bounds applied to NAV updates
+    pub circuit_breaker: CircuitBreakerParams,
+    /// NAV the circuit breaker bounds are measured against
+    pub baseline_nav_sats: u64,
+    /// Account holding the admin set and signing threshold
+    pub admin_config: Pubkey,
+    /// Treasury key replaced by the last rotation
+    pub previous_treasury_pubkey_bytes: [u8; 33],
+    /// Time of the last treasury rotation, zero if never rotated
+    pub treasury_rotated_at: u64,
+    /// Paused instruction classes, a mask of the `pause::PAUSE_*` bits
+    pub paused: u8,
+    /// Account holding the NAV history, zero until one is initialized
+    pub nav_history: Pubkey,
+    /// Management and performance fee rates
+    pub fees: FeeParams,
+    /// Highest per-token NAV performance fees have been charged up to
+    pub high_water_mark_sats: u64,
+    /// NAV update time fees have been accrued up to, zero before the first accrual
+    pub last_fee_accrual: u64,
+    /// Whether holders must be allowlisted, and the root they register against
+    pub allowlist: AllowlistParams,
+    /// Epoch length and mint and burn budgets
+    pub epoch: EpochParams,
+    /// Start of the epoch the counters below cover
+    pub epoch_start: u64,
+    /// Supply when the current epoch started, which the budgets are shares of
+    pub epoch_start_supply: u64,
+    /// OVT minted in the current epoch
+    pub epoch_minted: u64,
+    /// OVT burned by buybacks and redemptions in the current epoch
+    pub epoch_burned: u64,
+    /// Key that must have signed the NAV oracle feed, zero until the admins
+    /// set one on a migrated fund
+    pub oracle_publisher: Pubkey,
 }
 
 impl OVTState {
@@ -51,22 +138,30 @@ impl OVTState {
     }
 
     pub fn validate_nav_update(&self, new_nav_sats: u64) -> ProgramResult {
-        if self.nav_sats > 0 {
-            let change_ratio = (new_nav_sats as f64) / (self.nav_sats as f64);
-            
-            // For significant changes (>100%), log for monitoring
-            if change_ratio > 2.0 || change_ratio < 0.5 {
-                msg!("Significant NAV change detected: {}%", (change_ratio - 1.0) * 100.0);
-            }
-            
-            // Only reject extremely large changes (>4000% or <-95%)
-            // For subsequent updates, we need to consider the cumulative change
-            let initial_nav = 1_000_000; // Initial NAV from test setup
-            let cumulative_ratio = (new_nav_sats as f64) / (initial_nav as f64);
-            if cumulative_ratio > 41.0 || cumulative_ratio < 0.05 {
-                msg!("Rejecting NAV update - cumulative change too large: {}%", (cumulative_ratio - 1.0) * 100.0);
-                return Err(OVTError::InvalidNAVUpdate.into());
-            }
+        self.circuit_breaker.check(self.baseline_nav_sats, self.nav_sats, new_nav_sats)
+    }
+
+    /// Record a validated NAV. The first NAV becomes the circuit breaker baseline.
+    pub fn apply_nav(&mut self, nav_sats: u64, now: u64) {
+        if self.baseline_nav_sats == 0 {
+            self.baseline_nav_sats = nav_sats;
+        }
+        self.nav_sats = nav_sats;
+        self.last_nav_update = now;
+    }
+
+    pub fn validate_nav_timestamp(&self, now: u64) -> ProgramResult {
+        // The first update only needs a positive timestamp
+        if self.last_nav_update == 0 {
+            return if now > 0 { Ok(()) } else { Err(OVTError::InvalidTimestamp.into()) };
+        }
+        if now <= self.last_nav_update {
+            msg!("Rejecting NAV update - timestamp {} not after {}", now, self.last_nav_update);
+            return Err(OVTError::InvalidTimestamp.into());
+        }
+        if now - self.last_nav_update < MIN_NAV_UPDATE_INTERVAL_SECS {
+            msg!("Rejecting NAV update - only {}s since last update", now - self.last_nav_update);
+            return Err(OVTError::InvalidTimestamp.into());
         }
         Ok(())
     }
@@ -74,74 +169,562 @@ impl OVTState {
     pub fn validate_supply_change(&self, new_supply: u64) -> ProgramResult {
         // Ensure supply changes are within acceptable limits
         if self.total_supply > 0 {
-            let change_ratio = (new_supply as f64) / (self.total_supply as f64);
-            if change_ratio > 1.1 || change_ratio < 0.9 {
+            let upper_bps = math::BPS_DENOMINATOR + MAX_SUPPLY_CHANGE_BPS;
+            let lower_bps = math::BPS_DENOMINATOR - MAX_SUPPLY_CHANGE_BPS;
+            if ratio_above(new_supply, self.total_supply, upper_bps)?
+                || ratio_below(new_supply, self.total_supply, lower_bps)?
+            {
                 return Err(OVTError::InvalidSupplyChange.into());
             }
         }
         Ok(())
     }
 
+    /// NAV of one whole OVT in satoshis, at `OVT_DECIMALS` precision
+    pub fn nav_per_token_sats(&self) -> Result<u64, ProgramError> {
+        let one_token = 10u64.pow(OVT_DECIMALS as u32);
+        if self.total_supply == 0 {
+            return Ok(one_token);
+        }
+        mul_div(self.nav_sats, one_token, self.total_supply)
+    }
+
+    /// Satoshis owed for `amount` OVT base units at the current per-token NAV
+    pub fn sats_for_ovt(&self, amount: u64) -> Result<u64, ProgramError> {
+        if amount > self.total_supply {
+            return Err(OVTError::InsufficientFunds.into());
+        }
+        mul_div(amount, self.nav_sats, self.total_supply)
+    }
+
+    /// OVT base units worth `amount_sats` at the current per-token NAV.
+    /// Before any supply exists, one base unit is issued per satoshi.
+    pub fn ovt_for_sats(&self, amount_sats: u64) -> Result<u64, ProgramError> {
+        if self.total_supply == 0 {
+            return Ok(amount_sats);
+        }
+        if self.nav_sats == 0 {
+            return Err(OVTError::InvalidNAVUpdate.into());
+        }
+        mul_div(amount_sats, self.total_supply, self.nav_sats)
+    }
+
+    /// Satoshis owed for `amount` OVT base units at `nav_per_token_sats`
+    pub fn sats_for_ovt_at(&self, amount: u64, nav_per_token_sats: u64) -> Result<u64, ProgramError> {
+        if amount > self.total_supply {
+            return Err(OVTError::InsufficientFunds.into());
+        }
+        mul_div(amount, nav_per_token_sats, 10u64.pow(OVT_DECIMALS as u32))
+    }
+
+    /// OVT base units worth `amount_sats` at `nav_per_token_sats`
+    pub fn ovt_for_sats_at(&self, amount_sats: u64, nav_per_token_sats: u64) -> Result<u64, ProgramError> {
+        if nav_per_token_sats == 0 {
+            return Err(OVTError::InvalidNAVUpdate.into());
+        }
+        mul_div(amount_sats, 10u64.pow(OVT_DECIMALS as u32), nav_per_token_sats)
+    }
+
     pub fn validate_treasury(&self) -> ProgramResult {
         // Ensure treasury key is valid
         self.get_treasury_pubkey()?;
         Ok(())
     }
+
+    /// Treasury keys a payment verified at `now` may be sent to: the current
+    /// key, and the previous one until the rotation grace period ends
+    pub fn accepted_treasury_keys(&self, now: u64) -> Vec<[u8; 33]> {
+        let mut keys = vec![self.treasury_pubkey_bytes];
+        if self.treasury_rotated_at > 0
+            && now < self.treasury_rotated_at.saturating_add(TREASURY_ROTATION_GRACE_SECS)
+        {
+            keys.push(self.previous_treasury_pubkey_bytes);
+        }
+        keys
+    }
 }
 
 #[derive(BorshSerialize, BorshDeserialize)]
 pub enum OVTInstruction {
-    /// Initialize OVT state
+    /// Initialize OVT state and the admin set. `threshold` distinct admins
+    /// must sign every privileged instruction, and sensitive operations wait
+    /// out the `timelock` delay.
     Initialize {
         treasury_pubkey_bytes: [u8; 33],
+        oracle_program_id: Pubkey,
+        oracle_publisher: Pubkey,
+        circuit_breaker: CircuitBreakerParams,
+        admins: Vec<Pubkey>,
+        threshold: u8,
+        timelock: TimelockParams,
+        fees: FeeParams,
+        epoch: EpochParams,
     },
-    /// Calculate and update NAV
+    /// Update NAV from the NAV oracle feed. `btc_price_sats` is the NAV the
+    /// caller expects and must lie within the feed's confidence interval.
     UpdateNAV {
         btc_price_sats: u64,
     },
-    /// Execute buyback and burn
+    /// Execute buyback and burn, debiting an OVT account the authority owns
+    /// or is delegate of. Each payment output can back only one burn, mint
+    /// or settlement, and burns count against the epoch burn budget.
     BuybackBurn {
         payment_txid: String,
+        payment_vout: u32,
+        payment_amount_sats: u64,
+    },
+    /// Record a pre-TGE SAFE investment
+    AddSAFE {
+        safe_data: SAFEData,
+    },
+    /// Amend the terms of an unconverted SAFE
+    UpdateSAFE {
+        safe_id: u64,
+        new_data: SAFEData,
+    },
+    /// Convert an unlocked SAFE into OVT credited to an account of the
+    /// investor, at the lower of the discounted NAV and the valuation cap.
+    /// Issuance counts against the epoch mint budget. Fees due are accrued
+    /// first.
+    ConvertSAFE {
+        safe_id: u64,
+    },
+    /// Register a portfolio position of the fund whose state is `position.state`
+    AddPosition {
+        position: PositionData,
+    },
+    /// Update the holdings and mark price of a position
+    UpdatePosition {
+        amount: u64,
+        price_per_token: u64,
+        status: runes_client::PositionStatus,
+    },
+    /// Mark a position as exited
+    ExitPosition,
+    /// Recompute NAV from the marked value of every open position plus the
+    /// treasury BTC balance, minus liabilities. `prices` holds the mark price
+    /// per token for each position account, in account order.
+    RecomputeNAV {
+        prices: Vec<u64>,
+        treasury_balance_sats: u64,
+        liabilities_sats: u64,
+    },
+    /// Change the NAV circuit breaker bounds. Requires the admin quorum.
+    /// With `rebase` set, the current NAV becomes the new baseline.
+    SetCircuitBreaker {
+        params: CircuitBreakerParams,
+        rebase: bool,
+    },
+    /// Issue OVT at the current per-token NAV against a verified BTC payment
+    /// to the treasury, crediting an OVT account owned by `recipient`.
+    /// Issuance counts against the epoch mint budget. Fees due are accrued
+    /// first.
+    Mint {
+        payment_txid: String,
+        payment_vout: u32,
         payment_amount_sats: u64,
+        recipient: Pubkey,
+    },
+    /// Burn OVT from the holder's account at the current NAV and open a BTC
+    /// payout obligation to `btc_address`. Only the holder signs; the admin
+    /// quorum approves the payout at settlement. Fees due are accrued first,
+    /// and the burn counts against the epoch burn budget.
+    Redeem {
+        amount: u64,
+        btc_address: String,
+    },
+    /// Mark a payout obligation paid once the BTC payment is verified
+    SettleRedemption {
+        obligation: Pubkey,
+        payout_txid: String,
+        payout_vout: u32,
+    },
+    /// Add a key to the admin set. Requires the admin quorum.
+    AddAdmin {
+        admin: Pubkey,
+    },
+    /// Remove a key from the admin set. Refused if the threshold would then
+    /// exceed the admin count.
+    RemoveAdmin {
+        admin: Pubkey,
+    },
+    /// Swap a lost or compromised admin key for a new one
+    ReplaceAdmin {
+        old_admin: Pubkey,
+        new_admin: Pubkey,
+    },
+    /// Change the number of distinct admin signatures required
+    SetThreshold {
+        threshold: u8,
+    },
+    /// Open a proposal to run the serialized `instruction` over `accounts`
+    /// once the admin threshold approves. The proposer's approval is recorded.
+    Propose {
+        instruction: Vec<u8>,
+        accounts: Vec<Pubkey>,
+        lifetime_secs: u64,
+    },
+    /// Approve a proposal. `digest` must match the proposal's instruction and accounts.
+    Approve {
+        digest: [u8; 32],
+    },
+    /// Withdraw an unexecuted proposal
+    Cancel,
+    /// Run an approved proposal. Sensitive operations are queued by the first
+    /// execution and run by a later one once the timelock delay has passed.
+    Execute,
+    /// Change the timelock delay and guardian set
+    SetTimelock {
+        params: TimelockParams,
+    },
+    /// Cancel a proposal with the guardian quorum
+    Veto,
+    /// Replace the treasury key. Payments to the previous key are accepted
+    /// for `TREASURY_ROTATION_GRACE_SECS` afterwards.
+    RotateTreasury {
+        treasury_pubkey_bytes: [u8; 33],
+    },
+    /// Refuse the instruction `classes` (`pause::PAUSE_*` bits) until they are
+    /// unpaused. The guardian quorum or the admin quorum can pause, except
+    /// that only the guardians can pause redemptions.
+    Pause {
+        classes: u8,
+    },
+    /// Resume paused instruction classes. Requires the admin quorum.
+    Unpause {
+        classes: u8,
+    },
+    /// Upgrade the state account to the current layout. Accounts still in
+    /// the unversioned v1 layout need `v1_upgrade` for the fields v1 did not
+    /// record, signed by `migration::V1_UPGRADE_AUTHORITY`.
+    Migrate {
+        v1_upgrade: Option<migration::V1Upgrade>,
+    },
+    /// Create the NAV history account. Every later NAV update appends a
+    /// sample to it, and the account must be passed to NAV updates, mints
+    /// and redemptions from then on. Requires the admin quorum.
+    InitializeNavHistory {
+        twap_window_secs: u64,
+    },
+    /// Price mints and redemptions against the per-token NAV averaged over
+    /// `twap_window_secs`, or the spot NAV when zero. Requires the admin quorum.
+    SetTwapWindow {
+        twap_window_secs: u64,
+    },
+    /// Open an empty OVT account for `owner`
+    InitializeTokenAccount {
+        owner: Pubkey,
+    },
+    /// Move OVT between accounts, signed by the source owner or its delegate
+    Transfer {
+        amount: u64,
+    },
+    /// Let `delegate` move up to `amount` out of the signing owner's account.
+    /// Named apart from `Approve`, which approves proposals.
+    ApproveDelegate {
+        delegate: Pubkey,
+        amount: u64,
+    },
+    /// Withdraw the delegate of the signing owner's account
+    RevokeDelegate,
+    /// Close an empty OVT account and reclaim its lamports
+    CloseAccount,
+    /// Mint the management and performance fees due up to the last NAV
+    /// update to the fee recipient. Requires the admin quorum.
+    AccrueFees,
+    /// Change the fee rates or recipient, after accruing fees due at the old
+    /// rates. Raising a rate or changing the recipient is timelocked.
+    SetFeeParams {
+        fees: FeeParams,
+    },
+    /// Move an allocation from the authority's OVT account into escrow under
+    /// a new vesting account. Requires the admin quorum.
+    CreateVesting {
+        terms: vesting::VestingTerms,
+    },
+    /// Release the vested, unclaimed part of an allocation to the beneficiary
+    ClaimVested,
+    /// Stop a revocable allocation vesting and return the unvested remainder.
+    /// Requires the admin quorum.
+    RevokeVesting,
+    /// Turn restricted mode on or off and publish the Merkle root holders
+    /// register against. Restricting an open fund is timelocked.
+    SetAllowlist {
+        allowlist: AllowlistParams,
+    },
+    /// Write the allowlist entry of `entry.holder`, re-admitting the holder
+    /// if it was removed. Requires the admin quorum.
+    SetAllowlistEntry {
+        entry: allowlist::AllowlistEntry,
+    },
+    /// Mark an allowlist entry removed, leaving a tombstone the holder cannot
+    /// register over. Requires the admin quorum.
+    RemoveAllowlistEntry,
+    /// Create an allowlist entry proven to be in the published Merkle root.
+    /// Refused if the holder has, or had, an entry.
+    RegisterAllowlistEntry {
+        entry: allowlist::AllowlistEntry,
+        proof: Vec<[u8; 32]>,
+    },
+    /// Change the epoch length and the per-epoch mint and burn budgets.
+    /// Widening a budget or shortening the epoch is timelocked.
+    SetEpochParams {
+        epoch: EpochParams,
+    },
+    /// Share `amount_sats` of the proceeds of an exited position pro rata
+    /// over an off-chain snapshot of `total_supply` OVT. `merkle_root`
+    /// commits to each holder's balance and entitlement, which together come
+    /// to `distributed_sats`. Requires the admin quorum.
+    Distribute {
+        amount_sats: u64,
+        total_supply: u64,
+        distributed_sats: u64,
+        merkle_root: [u8; 32],
+    },
+    /// Claim the signing holder's entitlement to a distribution as a payout
+    /// obligation to `btc_address`, settled like a redemption
+    ClaimDistribution {
+        balance: u64,
+        entitlement_sats: u64,
+        proof: Vec<[u8; 32]>,
+        btc_address: String,
+    },
+    /// Change the key the NAV oracle feed must be signed by. Timelocked.
+    SetOraclePublisher {
+        publisher: Pubkey,
     },
 }
 
 impl Program for OVTProgram {
     fn process_instruction(ctx: &ProgramContext, data: &[u8]) -> ProgramResult {
         let instruction = OVTInstruction::try_from_slice(data)?;
-        
+
+        // Sensitive operations only run through Execute once the timelock has passed
+        if let Some(state) = ctx.accounts.first().and_then(|info| info.get_data::<OVTState>().ok()) {
+            if timelock::requires_timelock(&instruction, &state) {
+                return Err(OVTError::TimelockRequired.into());
+            }
+        }
+
+        Self::dispatch(ctx, instruction)
+    }
+}
+
+impl OVTProgram {
+    /// Run a decoded instruction. Proposals call this directly once their
+    /// approvals and timelock have been checked.
+    pub(crate) fn dispatch(ctx: &ProgramContext, instruction: OVTInstruction) -> ProgramResult {
+        // Paused classes are refused however they arrive, including through proposals
+        if let Some(state) = ctx.accounts.first().and_then(|info| info.get_data::<OVTState>().ok()) {
+            if state.paused & pause::pause_class(&instruction) != 0 {
+                msg!("Refusing paused instruction class {:#06b}", pause::pause_class(&instruction));
+                return Err(OVTError::InstructionPaused.into());
+            }
+        }
+
         match instruction {
-            OVTInstruction::Initialize { treasury_pubkey_bytes } => {
-                Self::process_initialize(ctx, treasury_pubkey_bytes)
+            OVTInstruction::Initialize {
+                treasury_pubkey_bytes,
+                oracle_program_id,
+                oracle_publisher,
+                circuit_breaker,
+                admins,
+                threshold,
+                timelock,
+                fees,
+                epoch,
+            } => {
+                let admin_config = AdminConfig { admins, threshold, timelock };
+                Self::process_initialize(
+                    ctx,
+                    treasury_pubkey_bytes,
+                    oracle_program_id,
+                    oracle_publisher,
+                    circuit_breaker,
+                    admin_config,
+                    fees,
+                    epoch,
+                )
             }
             OVTInstruction::UpdateNAV { btc_price_sats } => {
                 Self::process_update_nav(ctx, btc_price_sats)
             }
-            OVTInstruction::BuybackBurn { payment_txid, payment_amount_sats } => {
-                Self::process_buyback_burn(ctx, &payment_txid, payment_amount_sats)
+            OVTInstruction::BuybackBurn { payment_txid, payment_vout, payment_amount_sats } => {
+                Self::process_buyback_burn(ctx, &payment_txid, payment_vout, payment_amount_sats)
+            }
+            OVTInstruction::AddSAFE { safe_data } => {
+                Self::process_add_safe(ctx, safe_data)
+            }
+            OVTInstruction::UpdateSAFE { safe_id, new_data } => {
+                Self::process_update_safe(ctx, safe_id, new_data)
+            }
+            OVTInstruction::ConvertSAFE { safe_id } => {
+                Self::process_convert_safe(ctx, safe_id)
+            }
+            OVTInstruction::AddPosition { position } => {
+                Self::process_add_position(ctx, position)
+            }
+            OVTInstruction::UpdatePosition { amount, price_per_token, status } => {
+                Self::process_update_position(ctx, amount, price_per_token, status)
+            }
+            OVTInstruction::ExitPosition => {
+                Self::process_exit_position(ctx)
+            }
+            OVTInstruction::RecomputeNAV { prices, treasury_balance_sats, liabilities_sats } => {
+                Self::process_recompute_nav(ctx, &prices, treasury_balance_sats, liabilities_sats)
+            }
+            OVTInstruction::SetCircuitBreaker { params, rebase } => {
+                Self::process_set_circuit_breaker(ctx, params, rebase)
+            }
+            OVTInstruction::Mint { payment_txid, payment_vout, payment_amount_sats, recipient } => {
+                Self::process_mint(ctx, &payment_txid, payment_vout, payment_amount_sats, recipient)
+            }
+            OVTInstruction::Redeem { amount, btc_address } => {
+                Self::process_redeem(ctx, amount, btc_address)
+            }
+            OVTInstruction::SettleRedemption { obligation, payout_txid, payout_vout } => {
+                Self::process_settle_redemption(ctx, obligation, payout_txid, payout_vout)
+            }
+            OVTInstruction::AddAdmin { admin } => {
+                Self::process_change_admins(ctx, |config| config.add(admin))
+            }
+            OVTInstruction::RemoveAdmin { admin } => {
+                Self::process_change_admins(ctx, |config| config.remove(&admin))
+            }
+            OVTInstruction::ReplaceAdmin { old_admin, new_admin } => {
+                Self::process_change_admins(ctx, |config| config.replace(&old_admin, new_admin))
+            }
+            OVTInstruction::SetThreshold { threshold } => {
+                Self::process_change_admins(ctx, |config| {
+                    config.threshold = threshold;
+                    Ok(())
+                })
+            }
+            OVTInstruction::Propose { instruction, accounts, lifetime_secs } => {
+                Self::process_propose(ctx, instruction, accounts, lifetime_secs)
+            }
+            OVTInstruction::Approve { digest } => {
+                Self::process_approve(ctx, digest)
+            }
+            OVTInstruction::Cancel => {
+                Self::process_cancel(ctx)
+            }
+            OVTInstruction::Execute => {
+                Self::process_execute(ctx)
+            }
+            OVTInstruction::SetTimelock { params } => {
+                Self::process_change_admins(ctx, |config| {
+                    config.timelock = params;
+                    Ok(())
+                })
+            }
+            OVTInstruction::Veto => {
+                Self::process_veto(ctx)
+            }
+            OVTInstruction::RotateTreasury { treasury_pubkey_bytes } => {
+                Self::process_rotate_treasury(ctx, treasury_pubkey_bytes)
+            }
+            OVTInstruction::Pause { classes } => {
+                Self::process_pause(ctx, classes)
+            }
+            OVTInstruction::Unpause { classes } => {
+                Self::process_unpause(ctx, classes)
+            }
+            OVTInstruction::Migrate { v1_upgrade } => {
+                Self::process_migrate(ctx, v1_upgrade)
+            }
+            OVTInstruction::InitializeNavHistory { twap_window_secs } => {
+                Self::process_initialize_nav_history(ctx, twap_window_secs)
+            }
+            OVTInstruction::SetTwapWindow { twap_window_secs } => {
+                Self::process_set_twap_window(ctx, twap_window_secs)
+            }
+            OVTInstruction::InitializeTokenAccount { owner } => {
+                Self::process_initialize_token_account(ctx, owner)
+            }
+            OVTInstruction::Transfer { amount } => {
+                Self::process_transfer(ctx, amount)
+            }
+            OVTInstruction::ApproveDelegate { delegate, amount } => {
+                Self::process_approve_delegate(ctx, delegate, amount)
+            }
+            OVTInstruction::RevokeDelegate => {
+                Self::process_revoke_delegate(ctx)
+            }
+            OVTInstruction::CloseAccount => {
+                Self::process_close_account(ctx)
+            }
+            OVTInstruction::AccrueFees => {
+                Self::process_accrue_fees(ctx)
+            }
+            OVTInstruction::SetFeeParams { fees } => {
+                Self::process_set_fee_params(ctx, fees)
+            }
+            OVTInstruction::CreateVesting { terms } => {
+                Self::process_create_vesting(ctx, terms)
+            }
+            OVTInstruction::ClaimVested => {
+                Self::process_claim_vested(ctx)
+            }
+            OVTInstruction::RevokeVesting => {
+                Self::process_revoke_vesting(ctx)
+            }
+            OVTInstruction::SetAllowlist { allowlist } => {
+                Self::process_set_allowlist(ctx, allowlist)
+            }
+            OVTInstruction::SetAllowlistEntry { entry } => {
+                Self::process_set_allowlist_entry(ctx, entry)
+            }
+            OVTInstruction::RemoveAllowlistEntry => {
+                Self::process_remove_allowlist_entry(ctx)
+            }
+            OVTInstruction::RegisterAllowlistEntry { entry, proof } => {
+                Self::process_register_allowlist_entry(ctx, entry, proof)
+            }
+            OVTInstruction::SetEpochParams { epoch } => {
+                Self::process_set_epoch_params(ctx, epoch)
+            }
+            OVTInstruction::Distribute { amount_sats, total_supply, distributed_sats, merkle_root } => {
+                Self::process_distribute(ctx, amount_sats, total_supply, distributed_sats, merkle_root)
+            }
+            OVTInstruction::ClaimDistribution { balance, entitlement_sats, proof, btc_address } => {
+                Self::process_claim_distribution(ctx, balance, entitlement_sats, proof, btc_address)
+            }
+            OVTInstruction::SetOraclePublisher { publisher } => {
+                Self::process_set_oracle_publisher(ctx, publisher)
             }
         }
     }
-}
 
-impl OVTProgram {
-    fn process_initialize(ctx: &ProgramContext, treasury_pubkey_bytes: [u8; 33]) -> ProgramResult {
+    #[allow(clippy::too_many_arguments)]
+    fn process_initialize(
+        ctx: &ProgramContext,
+        treasury_pubkey_bytes: [u8; 33],
+        oracle_program_id: Pubkey,
+        oracle_publisher: Pubkey,
+        circuit_breaker: CircuitBreakerParams,
+        admin_config: AdminConfig,
+        fees: FeeParams,
+        epoch: EpochParams,
+    ) -> ProgramResult {
         let state_info = ctx.get(0)?;
         let authority_info = ctx.get(1)?;
         let system_program = ctx.get(2)?;
+        let admin_config_info = ctx.get(3)?;
 
         if !authority_info.is_signer {
             return Err(ProgramError::MissingRequiredSignature);
         }
-
-        // Create and initialize state account
-        create_program_account(
-            &ctx.program_id,
-            state_info,
-            authority_info,
-            std::mem::size_of::<OVTState>() as u64,
-            system_program,
-        )?;
+        circuit_breaker.validate()?;
+        admin_config.validate()?;
+        fees.validate()?;
+        epoch.validate()?;
+        if !admin_config.is_admin(&authority_info.key) {
+            return Err(OVTError::InvalidAuthority.into());
+        }
+        if oracle_publisher == Pubkey::new() {
+            return Err(OVTError::InvalidOracleAccount.into());
+        }
 
         // Initialize new state
         let state = OVTState {
@@ -149,113 +732,297 @@ impl OVTProgram {
             treasury_pubkey_bytes,
             total_supply: 0,
             last_nav_update: 0,
+            position_count: 0,
+            oracle_program_id,
+            circuit_breaker,
+            baseline_nav_sats: 0,
+            admin_config: admin_config_info.key,
+            previous_treasury_pubkey_bytes: [0u8; 33],
+            treasury_rotated_at: 0,
+            paused: 0,
+            nav_history: Pubkey::new(),
+            fees,
+            high_water_mark_sats: 0,
+            last_fee_accrual: 0,
+            allowlist: AllowlistParams::default(),
+            epoch,
+            epoch_start: 0,
+            epoch_start_supply: 0,
+            epoch_minted: 0,
+            epoch_burned: 0,
+            oracle_publisher,
         };
 
+        // Create and initialize state account, sized for its serialized form
+        create_program_account(
+            &ctx.program_id,
+            state_info,
+            authority_info,
+            borsh::object_length(&state)? as u64,
+            system_program,
+        )?;
+
         initialize_account(&ctx.program_id, state_info, &state)?;
-        Ok(())
+
+        // Sized for the largest admin set so the set can change in place
+        create_program_account(
+            &ctx.program_id,
+            admin_config_info,
+            authority_info,
+            AdminConfig::MAX_SPACE as u64,
+            system_program,
+        )?;
+        initialize_account(&ctx.program_id, admin_config_info, &admin_config)?;
+
+        ctx.emit(&OVTEvent::Initialized {
+            admin_config: admin_config_info.key,
+            admins: admin_config.admins,
+            threshold: admin_config.threshold,
+        })
     }
 
     fn process_update_nav(ctx: &ProgramContext, btc_price_sats: u64) -> ProgramResult {
         let state_info = ctx.get(0)?;
         let authority_info = ctx.get(1)?;
+        let oracle_info = ctx.get(2)?;
 
         if !authority_info.is_signer {
             return Err(ProgramError::MissingRequiredSignature);
         }
 
-        msg!("Starting NAV update process...");
-        msg!("Attempting to read current state...");
         let mut state: OVTState = state_info.get_data()?;
-        msg!("State before update: {:?}", state);
-        msg!("Current NAV: {}", state.nav_sats);
+        Self::verify_admin_quorum(ctx, &state)?;
+
+        let now = ctx.clock()?.unix_timestamp;
+        state.validate_nav_timestamp(now)?;
+
+        // Read the NAV from the oracle feed
+        let oracle = OracleAccount::load(
+            oracle_info,
+            &state.oracle_program_id,
+            &state.oracle_publisher,
+            &NAV_ASSET_ID,
+            now,
+        )?;
+        if btc_price_sats.abs_diff(oracle.price_sats) > oracle.confidence_sats {
+            msg!("Expected NAV {} outside oracle interval {} +/- {}", btc_price_sats, oracle.price_sats, oracle.confidence_sats);
+            return Err(OVTError::InvalidNAVUpdate.into());
+        }
         
         // Validate the NAV update
-        msg!("Validating NAV update to {} sats...", btc_price_sats);
-        state.validate_nav_update(btc_price_sats)?;
-        
-        // Update state
-        state.nav_sats = btc_price_sats;
-        state.last_nav_update = 1000; // Mock timestamp for testing
-        msg!("Setting new NAV to: {}", state.nav_sats);
-        msg!("State after update (before writing): {:?}", state);
-
-        let result = state_info.set_data(&state);
-        msg!("set_data result: {:?}", result);
-        
-        // Verify the write succeeded by reading back
-        if result.is_ok() {
-            match state_info.get_data::<OVTState>() {
-                Ok(final_state) => msg!("Final state verification: {:?}", final_state),
-                Err(e) => msg!("Failed to verify final state: {:?}", e),
-            }
+        state.validate_nav_update(oracle.price_sats)?;
+
+        let previous_nav_sats = state.nav_sats;
+        state.apply_nav(oracle.price_sats, now);
+        Self::record_nav(ctx, &state)?;
+        ctx.emit(&OVTEvent::NavUpdated {
+            previous_nav_sats,
+            nav_sats: state.nav_sats,
+            timestamp: now,
+        })?;
+        state_info.set_data(&state)
+    }
+
+    fn process_set_circuit_breaker(
+        ctx: &ProgramContext,
+        params: CircuitBreakerParams,
+        rebase: bool,
+    ) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+
+        let mut state: OVTState = state_info.get_data()?;
+        Self::verify_admin_quorum(ctx, &state)?;
+        params.validate()?;
+
+        state.circuit_breaker = params;
+        if rebase {
+            state.baseline_nav_sats = state.nav_sats;
         }
-        
-        result
+
+        ctx.emit(&OVTEvent::CircuitBreakerUpdated {
+            params,
+            baseline_nav_sats: state.baseline_nav_sats,
+        })?;
+        state_info.set_data(&state)
     }
 
-    fn process_buyback_burn(
+    fn process_recompute_nav(
         ctx: &ProgramContext,
-        _payment_txid: &str,
-        payment_amount_sats: u64,
+        prices: &[u64],
+        treasury_balance_sats: u64,
+        liabilities_sats: u64,
     ) -> ProgramResult {
         let state_info = ctx.get(0)?;
-        let authority_info = ctx.get(1)?;
+        // One position account per price follows the authority; the admin
+        // config and co-signers come after them
+        let position_infos = ctx.accounts.get(2..2 + prices.len())
+            .ok_or(ProgramError::AccountNotFound)?;
 
-        // Verify admin signature
-        if !authority_info.is_signer {
-            return Err(ProgramError::MissingRequiredSignature);
+        let mut state: OVTState = state_info.get_data()?;
+        Self::verify_admin_quorum(ctx, &state)?;
+        let now = ctx.clock()?.unix_timestamp;
+        state.validate_nav_timestamp(now)?;
+
+        // Every open position must be marked, exactly once
+        if position_infos.len() as u64 != state.position_count {
+            return Err(OVTError::InvalidPosition.into());
+        }
+        for (i, position_info) in position_infos.iter().enumerate() {
+            if position_infos[..i].iter().any(|other| other.key == position_info.key) {
+                return Err(OVTError::InvalidPosition.into());
+            }
         }
 
-        // Verify admin status
-        #[cfg(test)]
-        if !ctx.is_admin(&authority_info.key) {
-            return Err(ProgramError::Custom("Not an admin account".to_string()));
+        // Record the marks on the positions so the NAV can be reproduced
+        let mut positions = Vec::with_capacity(position_infos.len());
+        for (position_info, price) in position_infos.iter().zip(prices) {
+            let mut position = PositionData::load(&ctx.program_id, &state_info.key, position_info)?;
+            if position.status == runes_client::PositionStatus::Exited {
+                return Err(OVTError::PositionAlreadyExited.into());
+            }
+            position.price_per_token = *price;
+            position_info.set_data(&position)?;
+            positions.push(position);
         }
 
+        let nav_sats = position::compute_nav(&positions, treasury_balance_sats, liabilities_sats)?;
+        state.validate_nav_update(nav_sats)?;
+        let previous_nav_sats = state.nav_sats;
+        state.apply_nav(nav_sats, now);
+        Self::record_nav(ctx, &state)?;
+
+        msg!("Recomputed NAV from {} positions", positions.len());
+        ctx.emit(&OVTEvent::NavUpdated {
+            previous_nav_sats,
+            nav_sats,
+            timestamp: now,
+        })?;
+        state_info.set_data(&state)
+    }
+
+    fn process_buyback_burn(
+        ctx: &ProgramContext,
+        payment_txid: &str,
+        payment_vout: u32,
+        payment_amount_sats: u64,
+    ) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+        let authority_info = ctx.get(1)?;
+        let marker_info = ctx.get(2)?;
+        let system_program = ctx.get(3)?;
+        let source_info = ctx.get(4)?;
+
         let state: OVTState = state_info.get_data()?;
+
+        // Verify the admin quorum signed
+        Self::verify_admin_quorum(ctx, &state)?;
         
         // Validate treasury
         state.validate_treasury()?;
-        
+        Self::consume_payment(ctx, marker_info, authority_info, system_program, payment_txid, payment_vout)?;
+
         // Calculate OVT amount to burn based on current NAV
         let ovt_to_burn = if state.nav_sats > 0 {
-            (payment_amount_sats as u128)
-                .checked_mul(state.total_supply as u128)
-                .and_then(|product| product.checked_div(state.nav_sats as u128))
-                .and_then(|result| if result <= u64::MAX as u128 { Some(result as u64) } else { None })
-                .ok_or(OVTError::InvalidSupplyChange)?
+            mul_div(payment_amount_sats, state.total_supply, state.nav_sats)?
         } else {
             return Err(OVTError::InvalidNAVUpdate.into());
         };
 
-        // Calculate new supply and validate
+        // Calculate new supply and count the burn against the epoch budget
         let new_supply = state.total_supply
             .checked_sub(ovt_to_burn)
             .ok_or(OVTError::InvalidSupplyChange)?;
-            
-        // Validate supply change
         let mut new_state = state;
-        new_state.validate_supply_change(new_supply)?;
+        new_state.record_burn(ovt_to_burn, ctx.clock()?.unix_timestamp)?;
         new_state.total_supply = new_supply;
 
+        // The payment leaves the treasury. The burn is rounded down, so the
+        // per-token NAV is unchanged or rises.
+        new_state.nav_sats = new_state.nav_sats
+            .checked_sub(payment_amount_sats)
+            .ok_or(OVTError::ArithmeticOverflow)?;
+
+        // The bought-back OVT leaves the account it was delivered to
+        let mut source = TokenAccount::load(&ctx.program_id, &state_info.key, source_info)?;
+        source.debit(authority_info, ovt_to_burn)?;
+        source_info.set_data(&source)?;
+
+        ctx.emit(&OVTEvent::SupplyBurned {
+            payment_txid: payment_txid.to_string(),
+            payment_vout,
+            payment_amount_sats,
+            amount: ovt_to_burn,
+            total_supply: new_supply,
+        })?;
         state_info.set_data(&new_state)?;
         Ok(())
     }
+
+    fn process_rotate_treasury(ctx: &ProgramContext, treasury_pubkey_bytes: [u8; 33]) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+
+        let mut state: OVTState = state_info.get_data()?;
+        Self::verify_admin_quorum(ctx, &state)?;
+
+        if treasury_pubkey_bytes == state.treasury_pubkey_bytes {
+            return Err(OVTError::InvalidTreasuryKey.into());
+        }
+        let previous = state.treasury_pubkey_bytes;
+        state.treasury_pubkey_bytes = treasury_pubkey_bytes;
+        state.validate_treasury()?;
+
+        state.previous_treasury_pubkey_bytes = previous;
+        state.treasury_rotated_at = ctx.clock()?.unix_timestamp;
+
+        ctx.emit(&OVTEvent::TreasuryRotated {
+            previous_treasury_pubkey_bytes: previous,
+            treasury_pubkey_bytes,
+        })?;
+        state_info.set_data(&state)
+    }
+}
+
+// Define the entrypoint directly
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let context = ProgramContext::new(
+        *program_id,
+        accounts.to_vec(),
+    );
+    OVTProgram::process_instruction(&context, instruction_data)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use mock_sdk::{test_utils::TestClient, AccountMeta};
+    use test_fixtures::Fixture;
     use bitcoin::secp256k1::Secp256k1;
     use rand::thread_rng;
     use std::cell::RefCell;
 
+    /// Publish a fresh NAV price to `oracle_key`, signed by `publisher`
+    fn publish_nav(client: &TestClient, oracle_key: &Pubkey, publisher: &Pubkey, nav_sats: u64) {
+        client.set_account_data(oracle_key, &OracleAccount {
+            asset_id: NAV_ASSET_ID,
+            price_sats: nav_sats,
+            publish_time: client.clock().unix_timestamp,
+            publisher: *publisher,
+            confidence_sats: 0,
+        }).unwrap();
+    }
+
     #[test]
     fn test_nav_validation() {
         let mut client = TestClient::new();
         let program_id = Pubkey::new_unique();
         let system_program = Pubkey::new_unique();
+        let oracle_program = Pubkey::new_unique();
+        let oracle_publisher = Pubkey::new_unique();
+        let oracle_account = client.create_account(oracle_program).unwrap();
 
         // Create multiple admin accounts (3 out of 5 required)
         let mut admin_accounts = Vec::new();
@@ -263,8 +1030,10 @@ mod tests {
             admin_accounts.push(client.create_admin_account(program_id).unwrap());
         }
         
-        // Create state account
+        // Create state and admin config accounts
         let state_account = client.create_account(program_id).unwrap();
+        let admin_config = client.create_account(program_id).unwrap();
+        let admin_keys: Vec<Pubkey> = admin_accounts.iter().map(|admin| admin.key).collect();
 
         // Create system program account
         {
@@ -284,10 +1053,10 @@ mod tests {
         let init_description = "Initialize OVT program state".to_string();
         
         // First 3 admins sign the initialization
-        for i in 0..3 {
+        for (i, admin) in admin_accounts.iter().enumerate().take(3) {
             let signature = format!("init_sig_{}", i);
             client.sign_action(
-                &admin_accounts[i].key,
+                &admin.key,
                 init_action_type.clone(),
                 init_description.clone(),
                 signature,
@@ -299,6 +1068,14 @@ mod tests {
 
         let instruction = OVTInstruction::Initialize {
             treasury_pubkey_bytes: [0u8; 33],
+            oracle_program_id: oracle_program,
+            oracle_publisher,
+            circuit_breaker: CircuitBreakerParams::default(),
+            admins: admin_keys.clone(),
+            threshold: 3,
+            timelock: TimelockParams::default(),
+            fees: FeeParams::default(),
+            epoch: EpochParams::default(),
         };
 
         client.process_transaction(
@@ -307,6 +1084,7 @@ mod tests {
                 AccountMeta::new(state_account.key, true),
                 AccountMeta::new_readonly(admin_accounts[0].key, true),
                 AccountMeta::new_readonly(system_program, false),
+                AccountMeta::new(admin_config.key, true),
             ],
             borsh::to_vec(&instruction).unwrap(),
         ).unwrap();
@@ -317,6 +1095,25 @@ mod tests {
             treasury_pubkey_bytes: [0u8; 33],
             total_supply: 1_000_000,
             last_nav_update: 0,
+            position_count: 0,
+            oracle_program_id: oracle_program,
+            circuit_breaker: CircuitBreakerParams::default(),
+            baseline_nav_sats: 1_000_000,
+            admin_config: admin_config.key,
+            previous_treasury_pubkey_bytes: [0u8; 33],
+            treasury_rotated_at: 0,
+            paused: 0,
+            nav_history: Pubkey::new(),
+            fees: FeeParams::default(),
+            high_water_mark_sats: 0,
+            last_fee_accrual: 0,
+            allowlist: AllowlistParams::default(),
+            epoch: EpochParams::default(),
+            epoch_start: 0,
+            epoch_start_supply: 0,
+            epoch_minted: 0,
+            epoch_burned: 0,
+            oracle_publisher,
         };
 
         {
@@ -333,10 +1130,10 @@ mod tests {
         let description = "Update NAV by 2000%".to_string();
         
         // First 3 admins sign the action
-        for i in 0..3 {
+        for (i, admin) in admin_accounts.iter().enumerate().take(3) {
             let signature = format!("sig_{}", i);
             client.sign_action(
-                &admin_accounts[i].key,
+                &admin.key,
                 action_type.clone(),
                 description.clone(),
                 signature,
@@ -347,11 +1144,18 @@ mod tests {
         let signatures: Vec<String> = (0..3).map(|i| format!("sig_{}", i)).collect();
         assert!(client.verify_action(&action_type, &signatures).unwrap());
 
+        publish_nav(&client, &oracle_account.key, &oracle_publisher, valid_nav);
         let instruction = OVTInstruction::UpdateNAV { btc_price_sats: valid_nav };
-        let accounts = vec![
+        let signers = |accounts: &mut Vec<AccountMeta>| {
+            accounts.push(AccountMeta::new_readonly(admin_config.key, false));
+            accounts.extend(admin_keys[..3].iter().map(|key| AccountMeta::new_readonly(*key, true)));
+        };
+        let mut accounts = vec![
             AccountMeta::new(state_account.key, true),
             AccountMeta::new_readonly(admin_accounts[0].key, true),
+            AccountMeta::new_readonly(oracle_account.key, false),
         ];
+        signers(&mut accounts);
 
         assert!(client.process_transaction(
             program_id,
@@ -367,10 +1171,10 @@ mod tests {
         let description = "Update NAV by 4100%".to_string();
         
         // First 3 admins sign the action
-        for i in 0..3 {
+        for (i, admin) in admin_accounts.iter().enumerate().take(3) {
             let signature = format!("sig_{}", i);
             client.sign_action(
-                &admin_accounts[i].key,
+                &admin.key,
                 action_type.clone(),
                 description.clone(),
                 signature,
@@ -381,13 +1185,94 @@ mod tests {
         let signatures: Vec<String> = (0..3).map(|i| format!("sig_{}", i)).collect();
         assert!(client.verify_action(&action_type, &signatures).unwrap());
 
+        client.advance_clock(MIN_NAV_UPDATE_INTERVAL_SECS);
+        publish_nav(&client, &oracle_account.key, &oracle_publisher, invalid_nav);
         let instruction = OVTInstruction::UpdateNAV { btc_price_sats: invalid_nav };
         
         assert!(client.process_transaction(
             program_id,
-            accounts,
+            accounts.clone(),
+            borsh::to_vec(&instruction).unwrap(),
+        ).is_err());
+
+        // The expected NAV must match the feed
+        publish_nav(&client, &oracle_account.key, &oracle_publisher, 22_000_000);
+        let instruction = OVTInstruction::UpdateNAV { btc_price_sats: 23_000_000 };
+        assert!(client.process_transaction(
+            program_id,
+            accounts.clone(),
             borsh::to_vec(&instruction).unwrap(),
         ).is_err());
+
+        // Oracle accounts owned by another program are rejected
+        let rogue_oracle = client.create_account(Pubkey::new_unique()).unwrap();
+        publish_nav(&client, &rogue_oracle.key, &oracle_publisher, 22_000_000);
+        let instruction = OVTInstruction::UpdateNAV { btc_price_sats: 22_000_000 };
+        let mut rogue_accounts = vec![
+            AccountMeta::new(state_account.key, true),
+            AccountMeta::new_readonly(admin_accounts[0].key, true),
+            AccountMeta::new_readonly(rogue_oracle.key, false),
+        ];
+        signers(&mut rogue_accounts);
+        let err = client.process_transaction(
+            program_id,
+            rogue_accounts,
+            borsh::to_vec(&instruction).unwrap(),
+        ).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidOracleAccount");
+
+        // A single admin cannot move the NAV
+        publish_nav(&client, &oracle_account.key, &oracle_publisher, 22_000_000);
+        let err = client.process_transaction(
+            program_id,
+            vec![
+                AccountMeta::new(state_account.key, true),
+                AccountMeta::new_readonly(admin_accounts[0].key, true),
+                AccountMeta::new_readonly(oracle_account.key, false),
+                AccountMeta::new_readonly(admin_config.key, false),
+            ],
+            borsh::to_vec(&instruction).unwrap(),
+        ).unwrap_err();
+        assert_eq!(err.to_string(), "Missing required signature");
+
+        let state: OVTState = client.get_account_data(&state_account.key).unwrap();
+        assert_eq!(state.nav_sats, valid_nav);
+        assert_eq!(state.last_nav_update, TestClient::GENESIS_TIMESTAMP);
+    }
+
+    #[test]
+    fn test_nav_update_timestamps() {
+        let mut fixture = Fixture::new();
+        let oracle_account = fixture.client.create_account(fixture.oracle_program).unwrap();
+
+        let accounts = vec![
+            AccountMeta::new(fixture.state, true),
+            AccountMeta::new_readonly(fixture.authority(), true),
+            AccountMeta::new_readonly(oracle_account.key, false),
+        ];
+        let update = |fixture: &mut Fixture, nav_sats| {
+            publish_nav(&fixture.client, &oracle_account.key, &fixture.oracle_publisher, nav_sats);
+            fixture.process(accounts.clone(), &OVTInstruction::UpdateNAV { btc_price_sats: nav_sats })
+        };
+
+        update(&mut fixture, 1_000_000).unwrap();
+        assert_eq!(fixture.state().last_nav_update, fixture.client.clock().unix_timestamp);
+
+        // Too soon after the previous update
+        fixture.client.advance_clock(MIN_NAV_UPDATE_INTERVAL_SECS - 1);
+        let err = update(&mut fixture, 1_100_000).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidTimestamp");
+
+        fixture.client.advance_clock(1);
+        update(&mut fixture, 1_100_000).unwrap();
+        let state = fixture.state();
+        assert_eq!(state.nav_sats, 1_100_000);
+        assert_eq!(state.last_nav_update, fixture.client.clock().unix_timestamp);
+
+        // A clock that went backwards is rejected
+        fixture.client.clock.lock().unwrap().unix_timestamp -= 1;
+        let err = update(&mut fixture, 1_100_000).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidTimestamp");
     }
 
     #[test]
@@ -402,8 +1287,10 @@ mod tests {
             admin_accounts.push(client.create_admin_account(program_id).unwrap());
         }
         
-        // Create state account
+        // Create state and admin config accounts
         let state_account = client.create_account(program_id).unwrap();
+        let admin_config = client.create_account(program_id).unwrap();
+        let admin_keys: Vec<Pubkey> = admin_accounts.iter().map(|admin| admin.key).collect();
 
         // Create system program account
         {
@@ -423,10 +1310,10 @@ mod tests {
         let init_description = "Initialize OVT program state".to_string();
         
         // First 3 admins sign the initialization
-        for i in 0..3 {
+        for (i, admin) in admin_accounts.iter().enumerate().take(3) {
             let signature = format!("init_sig_{}", i);
             client.sign_action(
-                &admin_accounts[i].key,
+                &admin.key,
                 init_action_type.clone(),
                 init_description.clone(),
                 signature,
@@ -443,6 +1330,14 @@ mod tests {
         
         let instruction = OVTInstruction::Initialize {
             treasury_pubkey_bytes: pubkey.serialize(),
+            oracle_program_id: Pubkey::new_unique(),
+            oracle_publisher: Pubkey::new_unique(),
+            circuit_breaker: CircuitBreakerParams::default(),
+            admins: admin_keys.clone(),
+            threshold: 3,
+            timelock: TimelockParams::default(),
+            fees: FeeParams::default(),
+            epoch: EpochParams::default(),
         };
 
         client.process_transaction(
@@ -451,6 +1346,7 @@ mod tests {
                 AccountMeta::new(state_account.key, true),
                 AccountMeta::new_readonly(admin_accounts[0].key, true),
                 AccountMeta::new_readonly(system_program, false),
+                AccountMeta::new(admin_config.key, true),
             ],
             borsh::to_vec(&instruction).unwrap(),
         ).unwrap();
@@ -461,6 +1357,25 @@ mod tests {
             treasury_pubkey_bytes: pubkey.serialize(),
             total_supply: 1_000_000, // 1M OVT supply
             last_nav_update: 0,
+            position_count: 0,
+            oracle_program_id: Pubkey::new_unique(),
+            circuit_breaker: CircuitBreakerParams::default(),
+            baseline_nav_sats: 1_000_000,
+            admin_config: admin_config.key,
+            previous_treasury_pubkey_bytes: [0u8; 33],
+            treasury_rotated_at: 0,
+            paused: 0,
+            nav_history: Pubkey::new(),
+            fees: FeeParams::default(),
+            high_water_mark_sats: 0,
+            last_fee_accrual: 0,
+            allowlist: AllowlistParams::default(),
+            epoch: EpochParams::default(),
+            epoch_start: 0,
+            epoch_start_supply: 0,
+            epoch_minted: 0,
+            epoch_burned: 0,
+            oracle_publisher: Pubkey::new(),
         };
 
         {
@@ -474,10 +1389,10 @@ mod tests {
         let description = "Burn 100k sats worth of OVT".to_string();
         
         // First 3 admins sign the action
-        for i in 0..3 {
+        for (i, admin) in admin_accounts.iter().enumerate().take(3) {
             let signature = format!("sig_{}", i);
             client.sign_action(
-                &admin_accounts[i].key,
+                &admin.key,
                 action_type.clone(),
                 description.clone(),
                 signature,
@@ -490,27 +1405,66 @@ mod tests {
 
         // Test buyback burn with admin account
         let payment_amount = 100_000; // 100k sats
+        let payment_txid = test_fixtures::Fixture::PAYMENT_TXID;
         let instruction = OVTInstruction::BuybackBurn {
-            payment_txid: "test_txid".to_string(),
+            payment_txid: payment_txid.to_string(),
+            payment_vout: 0,
             payment_amount_sats: payment_amount,
         };
+        let marker = payment::payment_marker_address(&program_id, &payment::parse_txid(payment_txid).unwrap(), 0);
+        client.create_system_account(marker).unwrap();
 
-        let accounts = vec![
+        // The whole supply sits in the first admin's account
+        let token_account = client.create_account(program_id).unwrap();
+        let mut holding = TokenAccount::new(state_account.key, admin_accounts[0].key);
+        holding.amount = 1_000_000;
+        client.set_account_data(&token_account.key, &holding).unwrap();
+
+        let mut accounts = vec![
             AccountMeta::new(state_account.key, true),
             AccountMeta::new_readonly(admin_accounts[0].key, true), // Using first admin account
+            AccountMeta::new(marker, true),
+            AccountMeta::new_readonly(system_program, false),
+            AccountMeta::new(token_account.key, true),
+            AccountMeta::new_readonly(admin_config.key, false),
         ];
+        accounts.extend(admin_keys[1..3].iter().map(|key| AccountMeta::new_readonly(*key, true)));
 
+        let nav_per_token = state.nav_per_token_sats().unwrap();
         assert!(client.process_transaction(
             program_id,
             accounts.clone(),
             borsh::to_vec(&instruction).unwrap(),
         ).is_ok());
+        let state: OVTState = client.get_account_data(&state_account.key).unwrap();
+        let holding: TokenAccount = client.get_account_data(&token_account.key).unwrap();
+        assert_eq!(state.total_supply, 900_000);
+        assert_eq!(holding.amount, state.total_supply);
+
+        // The payment is paid out of the NAV, leaving remaining holders'
+        // per-token NAV where it was
+        assert_eq!(state.nav_sats, 900_000);
+        assert_eq!(state.nav_per_token_sats().unwrap(), nav_per_token);
+
+        // The same payment cannot be burned against twice, even once the
+        // epoch budget the first burn used up has been refilled
+        client.advance_clock(EpochParams::default().epoch_secs);
+        let err = client.process_transaction(
+            program_id,
+            accounts,
+            borsh::to_vec(&instruction).unwrap(),
+        ).unwrap_err();
+        assert_eq!(err.to_string(), "PaymentAlreadyProcessed");
 
         // Test buyback burn with non-admin account should fail
         let non_admin = client.create_account(program_id).unwrap();
         let accounts = vec![
             AccountMeta::new(state_account.key, true),
             AccountMeta::new_readonly(non_admin.key, true),
+            AccountMeta::new(marker, true),
+            AccountMeta::new_readonly(system_program, false),
+            AccountMeta::new(token_account.key, true),
+            AccountMeta::new_readonly(admin_config.key, false),
         ];
 
         assert!(client.process_transaction(
@@ -519,17 +1473,231 @@ mod tests {
             borsh::to_vec(&instruction).unwrap(),
         ).is_err());
     }
-}
 
-// Define the entrypoint directly
-pub fn process_instruction(
-    program_id: &Pubkey,
-    accounts: &[AccountInfo],
-    instruction_data: &[u8],
-) -> ProgramResult {
-    let context = ProgramContext::new(
-        *program_id,
-        accounts.to_vec(),
-    );
-    OVTProgram::process_instruction(&context, instruction_data)
-} 
\ No newline at end of file
+    #[test]
+    fn test_recompute_nav() {
+        let mut fixture = Fixture::new();
+
+        // Register two positions
+        let mut position_accounts = Vec::new();
+        for (name, amount) in [("Alpha", 1_000_000), ("Beta", 20_000)] {
+            let position_account = fixture.create_system_account();
+            let instruction = OVTInstruction::AddPosition {
+                position: PositionData {
+                    state: fixture.state,
+                    name: name.to_string(),
+                    amount,
+                    price_per_token: 1,
+                    currency_spent: amount,
+                    position_type: runes_client::PositionType::PostTGE,
+                    status: runes_client::PositionStatus::Active,
+                    entry_timestamp: 1677649200,
+                },
+            };
+            fixture.process(
+                vec![
+                    AccountMeta::new(fixture.state, true),
+                    AccountMeta::new(position_account, true),
+                    AccountMeta::new_readonly(fixture.authority(), true),
+                    AccountMeta::new_readonly(fixture.system_program, false),
+                ],
+                &instruction,
+            ).unwrap();
+            position_accounts.push(position_account);
+        }
+
+        let instruction = OVTInstruction::RecomputeNAV {
+            prices: vec![3, 50],
+            treasury_balance_sats: 500_000,
+            liabilities_sats: 100_000,
+        };
+        let mut accounts = vec![
+            AccountMeta::new(fixture.state, true),
+            AccountMeta::new_readonly(fixture.authority(), true),
+        ];
+        accounts.extend(position_accounts.iter().map(|key| AccountMeta::new(*key, true)));
+
+        // A position of another fund cannot stand in for one of this fund's
+        let foreign_account = fixture.create_account();
+        let mut foreign: PositionData = fixture.client.get_account_data(&position_accounts[1]).unwrap();
+        foreign.state = Fixture::new().state;
+        fixture.client.set_account_data(&foreign_account, &foreign).unwrap();
+        let mut foreign_accounts = accounts.clone();
+        foreign_accounts[3] = AccountMeta::new(foreign_account, true);
+        let err = fixture.process(foreign_accounts, &instruction).unwrap_err();
+        assert_eq!(err.to_string(), "PositionNotFound");
+
+        fixture.process(accounts.clone(), &instruction).unwrap();
+
+        // 1M * 3 + 20k * 50 + 500k - 100k
+        assert_eq!(fixture.state().nav_sats, 4_400_000);
+        let beta: PositionData = fixture.client.get_account_data(&position_accounts[1]).unwrap();
+        assert_eq!(beta.price_per_token, 50);
+
+        // Omitting a registered position is rejected
+        accounts.pop();
+        let instruction = OVTInstruction::RecomputeNAV {
+            prices: vec![3],
+            treasury_balance_sats: 500_000,
+            liabilities_sats: 100_000,
+        };
+        assert!(fixture.process(accounts.clone(), &instruction).is_err());
+
+        // Passing the same position twice is rejected
+        accounts.push(AccountMeta::new(position_accounts[0], true));
+        let instruction = OVTInstruction::RecomputeNAV {
+            prices: vec![3, 3],
+            treasury_balance_sats: 500_000,
+            liabilities_sats: 100_000,
+        };
+        assert!(fixture.process(accounts, &instruction).is_err());
+    }
+
+    #[test]
+    fn test_set_circuit_breaker() {
+        let mut fixture = Fixture::new();
+        let oracle_account = fixture.client.create_account(fixture.oracle_program).unwrap();
+
+        // The first NAV becomes the baseline
+        publish_nav(&fixture.client, &oracle_account.key, &fixture.oracle_publisher, 1_000_000);
+        let nav_accounts = vec![
+            AccountMeta::new(fixture.state, true),
+            AccountMeta::new_readonly(fixture.authority(), true),
+            AccountMeta::new_readonly(oracle_account.key, false),
+        ];
+        fixture.process(nav_accounts.clone(), &OVTInstruction::UpdateNAV { btc_price_sats: 1_000_000 }).unwrap();
+        assert_eq!(fixture.state().baseline_nav_sats, 1_000_000);
+
+        let tight = CircuitBreakerParams {
+            max_cumulative_bps: 15_000,
+            ..CircuitBreakerParams::default()
+        };
+        let instruction = OVTInstruction::SetCircuitBreaker { params: tight, rebase: false };
+        let state_accounts = vec![AccountMeta::new(fixture.state, true)];
+
+        // Two admins are not enough
+        assert!(fixture.process_with_signers(state_accounts.clone(), 2, &instruction).is_err());
+
+        // The same admin listed three times does not make a quorum
+        let mut repeated = state_accounts.clone();
+        repeated.extend(fixture.signers(1));
+        repeated.push(AccountMeta::new_readonly(fixture.admins[0], true));
+        repeated.push(AccountMeta::new_readonly(fixture.admins[0], true));
+        assert!(fixture.process_raw(repeated, &instruction).is_err());
+
+        // Signers that are not admins do not count
+        let outsider = fixture.create_signer();
+        let mut outsiders = state_accounts.clone();
+        outsiders.push(AccountMeta::new_readonly(outsider, true));
+        assert!(fixture.process_with_signers(outsiders, 2, &instruction).is_err());
+
+        // An admin config account other than the one recorded at Initialize is ignored
+        let rogue_config = fixture.create_account();
+        fixture.client.set_account_data(&rogue_config, &AdminConfig {
+            admins: vec![outsider],
+            threshold: 1,
+            timelock: TimelockParams::default(),
+        }).unwrap();
+        let rogue = vec![
+            AccountMeta::new(fixture.state, true),
+            AccountMeta::new_readonly(rogue_config, false),
+            AccountMeta::new_readonly(outsider, true),
+        ];
+        let err = fixture.process_raw(rogue, &instruction).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidAdminConfig");
+
+        fixture.process(state_accounts.clone(), &instruction).unwrap();
+        assert_eq!(fixture.state().circuit_breaker, tight);
+
+        // 1.6x the baseline now trips the breaker
+        fixture.client.advance_clock(MIN_NAV_UPDATE_INTERVAL_SECS);
+        publish_nav(&fixture.client, &oracle_account.key, &fixture.oracle_publisher, 1_600_000);
+        assert!(fixture.process(nav_accounts.clone(), &OVTInstruction::UpdateNAV { btc_price_sats: 1_600_000 }).is_err());
+
+        publish_nav(&fixture.client, &oracle_account.key, &fixture.oracle_publisher, 1_400_000);
+        fixture.process(nav_accounts, &OVTInstruction::UpdateNAV { btc_price_sats: 1_400_000 }).unwrap();
+
+        // Rebasing measures future updates from the current NAV
+        let instruction = OVTInstruction::SetCircuitBreaker { params: tight, rebase: true };
+        let mut rebase_accounts = state_accounts;
+        rebase_accounts.push(AccountMeta::new_readonly(fixture.admin_config, false));
+        let approvers = fixture.admins[..3].to_vec();
+        fixture.process_timelocked(rebase_accounts, &instruction, &approvers).unwrap();
+        assert_eq!(fixture.state().baseline_nav_sats, 1_400_000);
+    }
+
+    #[test]
+    fn test_supply_change_boundaries() {
+        let state = OVTState {
+            nav_sats: 1_000_000,
+            treasury_pubkey_bytes: [0u8; 33],
+            total_supply: 1_000_000,
+            last_nav_update: 0,
+            position_count: 0,
+            oracle_program_id: Pubkey::new(),
+            circuit_breaker: CircuitBreakerParams::default(),
+            baseline_nav_sats: 1_000_000,
+            admin_config: Pubkey::new(),
+            previous_treasury_pubkey_bytes: [0u8; 33],
+            treasury_rotated_at: 0,
+            paused: 0,
+            nav_history: Pubkey::new(),
+            fees: FeeParams::default(),
+            high_water_mark_sats: 0,
+            last_fee_accrual: 0,
+            allowlist: AllowlistParams::default(),
+            epoch: EpochParams::default(),
+            epoch_start: 0,
+            epoch_start_supply: 0,
+            epoch_minted: 0,
+            epoch_burned: 0,
+            oracle_publisher: Pubkey::new(),
+        };
+
+        // Exactly 10% either way is allowed
+        assert!(state.validate_supply_change(1_100_000).is_ok());
+        assert!(state.validate_supply_change(900_000).is_ok());
+        assert!(state.validate_supply_change(1_100_001).is_err());
+        assert!(state.validate_supply_change(899_999).is_err());
+
+        // Exactly 41x the baseline is allowed
+        assert!(state.validate_nav_update(41_000_000).is_ok());
+        assert!(state.validate_nav_update(41_000_001).is_err());
+    }
+
+    #[test]
+    fn test_rotate_treasury() {
+        let mut fixture = Fixture::new();
+        let original = fixture.state().treasury_pubkey_bytes;
+        let (_, new_treasury) = Secp256k1::new().generate_keypair(&mut thread_rng());
+        let rotate = |treasury_pubkey_bytes| OVTInstruction::RotateTreasury { treasury_pubkey_bytes };
+        let accounts = vec![
+            AccountMeta::new(fixture.state, true),
+            AccountMeta::new_readonly(fixture.admin_config, false),
+        ];
+        let approvers = fixture.admins[..3].to_vec();
+
+        // Rotation is sensitive and goes through the timelock
+        let err = fixture.process(accounts.clone(), &rotate(new_treasury.serialize())).unwrap_err();
+        assert_eq!(err.to_string(), "TimelockRequired");
+
+        // The new key must be a valid public key other than the current one
+        let err = fixture.process_timelocked(accounts.clone(), &rotate([0u8; 33]), &approvers).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidTreasuryKey");
+        let err = fixture.process_timelocked(accounts.clone(), &rotate(original), &approvers).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidTreasuryKey");
+
+        fixture.process_timelocked(accounts, &rotate(new_treasury.serialize()), &approvers).unwrap();
+        let state = fixture.state();
+        let rotated_at = fixture.client.clock().unix_timestamp;
+        assert_eq!(state.treasury_pubkey_bytes, new_treasury.serialize());
+        assert_eq!(state.previous_treasury_pubkey_bytes, original);
+        assert_eq!(state.treasury_rotated_at, rotated_at);
+
+        // Payments to the old key are accepted until the grace period ends
+        assert_eq!(state.accepted_treasury_keys(rotated_at), vec![new_treasury.serialize(), original]);
+        let grace_end = rotated_at + TREASURY_ROTATION_GRACE_SECS;
+        assert_eq!(state.accepted_treasury_keys(grace_end - 1).len(), 2);
+        assert_eq!(state.accepted_treasury_keys(grace_end), vec![new_treasury.serialize()]);
+    }
+}
@@ -0,0 +1,88 @@
+use crate::{
+    error::OVTError,
+    mock_sdk::ProgramError,
+};
+
+/// One in basis points
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// `a * b / denominator`, rounded down, computed in u128
+pub fn mul_div(a: u64, b: u64, denominator: u64) -> Result<u64, ProgramError> {
+    if denominator == 0 {
+        return Err(OVTError::ArithmeticOverflow.into());
+    }
+    let result = (a as u128)
+        .checked_mul(b as u128)
+        .ok_or(OVTError::ArithmeticOverflow)?
+        / denominator as u128;
+    u64::try_from(result).map_err(|_| OVTError::ArithmeticOverflow.into())
+}
+
+/// Whether `numerator / denominator` is strictly above `bound_bps`. Compared
+/// by cross-multiplication, so the result is exact at the boundary.
+pub fn ratio_above(numerator: u64, denominator: u64, bound_bps: u64) -> Result<bool, ProgramError> {
+    let (lhs, rhs) = cross_multiply(numerator, denominator, bound_bps)?;
+    Ok(lhs > rhs)
+}
+
+/// Whether `numerator / denominator` is strictly below `bound_bps`
+pub fn ratio_below(numerator: u64, denominator: u64, bound_bps: u64) -> Result<bool, ProgramError> {
+    let (lhs, rhs) = cross_multiply(numerator, denominator, bound_bps)?;
+    Ok(lhs < rhs)
+}
+
+fn cross_multiply(numerator: u64, denominator: u64, bound_bps: u64) -> Result<(u128, u128), ProgramError> {
+    if denominator == 0 {
+        return Err(OVTError::ArithmeticOverflow.into());
+    }
+    let lhs = (numerator as u128)
+        .checked_mul(BPS_DENOMINATOR as u128)
+        .ok_or(OVTError::ArithmeticOverflow)?;
+    let rhs = (bound_bps as u128)
+        .checked_mul(denominator as u128)
+        .ok_or(OVTError::ArithmeticOverflow)?;
+    Ok((lhs, rhs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mul_div() {
+        assert_eq!(mul_div(100_000, 1_000_000, 1_000_000).unwrap(), 100_000);
+        assert_eq!(mul_div(u64::MAX, u64::MAX, u64::MAX).unwrap(), u64::MAX);
+        assert_eq!(mul_div(7, 3, 2).unwrap(), 10);
+
+        let err = mul_div(u64::MAX, 2, 1).unwrap_err();
+        assert_eq!(err.to_string(), "ArithmeticOverflow");
+        let err = mul_div(1, 1, 0).unwrap_err();
+        assert_eq!(err.to_string(), "ArithmeticOverflow");
+    }
+
+    #[test]
+    fn test_ratio_boundaries() {
+        // Exactly 10% either side is within bounds, one unit more is not
+        assert!(!ratio_above(1_100_000, 1_000_000, 11_000).unwrap());
+        assert!(ratio_above(1_100_001, 1_000_000, 11_000).unwrap());
+        assert!(!ratio_below(900_000, 1_000_000, 9_000).unwrap());
+        assert!(ratio_below(899_999, 1_000_000, 9_000).unwrap());
+
+        // Exactly 2x and 0.5x
+        assert!(!ratio_above(2_000_000, 1_000_000, 20_000).unwrap());
+        assert!(ratio_above(2_000_001, 1_000_000, 20_000).unwrap());
+        assert!(!ratio_below(500_000, 1_000_000, 5_000).unwrap());
+        assert!(ratio_below(499_999, 1_000_000, 5_000).unwrap());
+
+        // Exactly 41x, including denominators that do not divide evenly
+        assert!(!ratio_above(41_000_000, 1_000_000, 410_000).unwrap());
+        assert!(ratio_above(41_000_001, 1_000_000, 410_000).unwrap());
+        assert!(!ratio_above(123, 3, 410_000).unwrap());
+        assert!(ratio_above(124, 3, 410_000).unwrap());
+
+        // Full u64 range does not overflow the comparison
+        assert!(!ratio_above(u64::MAX, u64::MAX, BPS_DENOMINATOR).unwrap());
+        assert!(!ratio_below(u64::MAX, u64::MAX, BPS_DENOMINATOR).unwrap());
+        assert!(ratio_above(1, 0, BPS_DENOMINATOR).is_err());
+    }
+}
@@ -0,0 +1,96 @@
+//! Merkle trees over sha256, used to commit to large holder lists with a
+//! single root. Leaves and inner nodes are hashed with distinct prefixes so
+//! a node cannot pass for a leaf, and each pair is hashed in sorted order so
+//! proofs need no left/right flags.
+
+use bitcoin::hashes::{sha256, Hash};
+
+const LEAF_PREFIX: u8 = 0;
+const NODE_PREFIX: u8 = 1;
+
+/// Hash of a leaf holding `data`
+pub fn leaf_hash(data: &[u8]) -> [u8; 32] {
+    let mut preimage = Vec::with_capacity(data.len() + 1);
+    preimage.push(LEAF_PREFIX);
+    preimage.extend_from_slice(data);
+    sha256::Hash::hash(&preimage).to_byte_array()
+}
+
+fn node_hash(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    let mut preimage = [0u8; 65];
+    preimage[0] = NODE_PREFIX;
+    preimage[1..33].copy_from_slice(first);
+    preimage[33..].copy_from_slice(second);
+    sha256::Hash::hash(&preimage).to_byte_array()
+}
+
+/// Whether `proof` links `leaf` to `root`
+pub fn verify(root: &[u8; 32], leaf: &[u8; 32], proof: &[[u8; 32]]) -> bool {
+    let computed = proof.iter().fold(*leaf, |hash, sibling| node_hash(&hash, sibling));
+    computed == *root
+}
+
+/// Root of the tree over `leaves`, all zero for an empty tree. A node
+/// without a sibling is carried up to the next level unchanged.
+pub fn root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    let mut level = leaves.to_vec();
+    if level.is_empty() {
+        return [0u8; 32];
+    }
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level[0]
+}
+
+/// Proof for the leaf at `index`, for building trees off-chain
+pub fn proof(leaves: &[[u8; 32]], mut index: usize) -> Vec<[u8; 32]> {
+    let mut proof = Vec::new();
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        if let Some(sibling) = level.get(index ^ 1) {
+            proof.push(*sibling);
+        }
+        level = next_level(&level);
+        index /= 2;
+    }
+    proof
+}
+
+fn next_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level.chunks(2)
+        .map(|pair| match pair {
+            [a, b] => node_hash(a, b),
+            [a] => *a,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proofs() {
+        let leaves: Vec<[u8; 32]> = (0u8..5).map(|i| leaf_hash(&[i])).collect();
+        let root = root(&leaves);
+        for (index, leaf) in leaves.iter().enumerate() {
+            assert!(verify(&root, leaf, &proof(&leaves, index)));
+        }
+
+        // Proofs do not carry over to other leaves, data or roots
+        assert!(!verify(&root, &leaves[0], &proof(&leaves, 1)));
+        assert!(!verify(&root, &leaf_hash(&[9]), &proof(&leaves, 0)));
+        assert!(!verify(&super::root(&leaves[..4]), &leaves[4], &proof(&leaves, 4)));
+
+        // An inner node is not accepted as a leaf
+        let inner = node_hash(&leaves[0], &leaves[1]);
+        assert!(!verify(&root, &leaf_hash(&inner), &proof(&leaves, 0)[1..]));
+
+        // A single leaf is its own root
+        assert_eq!(super::root(&leaves[..1]), leaves[0]);
+        assert!(proof(&leaves[..1], 0).is_empty());
+    }
+}
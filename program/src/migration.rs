@@ -0,0 +1,753 @@
+//! Versioned layout of the program state account.
+//!
+//! The state is stored as an 8-byte discriminator, a one-byte layout version
+//! and the borsh fields of `OVTState`. The first release stored the bare v1
+//! fields with no envelope; those accounts are recognised by the missing
+//! discriminator and upgraded in place with `Migrate`, as are accounts in
+//! any older versioned layout.
+
+use crate::mock_sdk::{
+    AccountInfo,
+    Pubkey,
+    ProgramContext,
+    ProgramResult,
+    ProgramError,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use std::io::{self, Read, Write};
+use crate::{
+    allowlist::AllowlistParams,
+    circuit_breaker::CircuitBreakerParams,
+    epoch::EpochParams,
+    error::OVTError,
+    events::OVTEvent,
+    fees::FeeParams,
+    msg,
+    system::{create_program_account, initialize_account, resize_program_account, verify_uninitialized},
+    timelock::TimelockParams,
+    AdminConfig,
+    OVTProgram,
+    OVTState,
+};
+
+/// Marks an account as holding versioned OVT state. Read as the leading
+/// `nav_sats` of a v1 account it would be over 10^18 sats, so the two cannot
+/// be confused.
+pub const STATE_DISCRIMINATOR: [u8; 8] = *b"OVTSTATE";
+/// Layout version written by this program
+pub const STATE_VERSION: u8 = 7;
+/// Key that approves the admin set of a v1 fund. v1 state records no
+/// authority, so the upgrade key is fixed at build time.
+pub const V1_UPGRADE_AUTHORITY: Pubkey = Pubkey(*b"OVTv1UpgradeAuthority11111111111");
+
+/// Unversioned state layout of the first release
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct OVTStateV1 {
+    pub nav_sats: u64,
+    pub treasury_pubkey_bytes: [u8; 33],
+    pub total_supply: u64,
+    pub last_nav_update: u64,
+}
+
+/// Versioned layout 2, before the NAV history account was recorded. Stored
+/// after the discriminator and version byte.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct OVTStateV2 {
+    pub nav_sats: u64,
+    pub treasury_pubkey_bytes: [u8; 33],
+    pub total_supply: u64,
+    pub last_nav_update: u64,
+    pub position_count: u64,
+    pub oracle_program_id: Pubkey,
+    pub circuit_breaker: CircuitBreakerParams,
+    pub baseline_nav_sats: u64,
+    pub admin_config: Pubkey,
+    pub previous_treasury_pubkey_bytes: [u8; 33],
+    pub treasury_rotated_at: u64,
+    pub paused: u8,
+}
+
+impl From<OVTStateV2> for OVTStateV3 {
+    fn from(legacy: OVTStateV2) -> Self {
+        Self {
+            nav_sats: legacy.nav_sats,
+            treasury_pubkey_bytes: legacy.treasury_pubkey_bytes,
+            total_supply: legacy.total_supply,
+            last_nav_update: legacy.last_nav_update,
+            position_count: legacy.position_count,
+            oracle_program_id: legacy.oracle_program_id,
+            circuit_breaker: legacy.circuit_breaker,
+            baseline_nav_sats: legacy.baseline_nav_sats,
+            admin_config: legacy.admin_config,
+            previous_treasury_pubkey_bytes: legacy.previous_treasury_pubkey_bytes,
+            treasury_rotated_at: legacy.treasury_rotated_at,
+            paused: legacy.paused,
+            nav_history: Pubkey::new(),
+        }
+    }
+}
+
+/// Versioned layout 3, before fees were charged
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct OVTStateV3 {
+    pub nav_sats: u64,
+    pub treasury_pubkey_bytes: [u8; 33],
+    pub total_supply: u64,
+    pub last_nav_update: u64,
+    pub position_count: u64,
+    pub oracle_program_id: Pubkey,
+    pub circuit_breaker: CircuitBreakerParams,
+    pub baseline_nav_sats: u64,
+    pub admin_config: Pubkey,
+    pub previous_treasury_pubkey_bytes: [u8; 33],
+    pub treasury_rotated_at: u64,
+    pub paused: u8,
+    pub nav_history: Pubkey,
+}
+
+/// Migrated funds charge no fees until the admins set them
+impl From<OVTStateV3> for OVTStateV4 {
+    fn from(legacy: OVTStateV3) -> Self {
+        Self {
+            nav_sats: legacy.nav_sats,
+            treasury_pubkey_bytes: legacy.treasury_pubkey_bytes,
+            total_supply: legacy.total_supply,
+            last_nav_update: legacy.last_nav_update,
+            position_count: legacy.position_count,
+            oracle_program_id: legacy.oracle_program_id,
+            circuit_breaker: legacy.circuit_breaker,
+            baseline_nav_sats: legacy.baseline_nav_sats,
+            admin_config: legacy.admin_config,
+            previous_treasury_pubkey_bytes: legacy.previous_treasury_pubkey_bytes,
+            treasury_rotated_at: legacy.treasury_rotated_at,
+            paused: legacy.paused,
+            nav_history: legacy.nav_history,
+            fees: FeeParams::default(),
+            high_water_mark_sats: 0,
+            last_fee_accrual: 0,
+        }
+    }
+}
+
+/// Versioned layout 4, before the investor allowlist
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct OVTStateV4 {
+    pub nav_sats: u64,
+    pub treasury_pubkey_bytes: [u8; 33],
+    pub total_supply: u64,
+    pub last_nav_update: u64,
+    pub position_count: u64,
+    pub oracle_program_id: Pubkey,
+    pub circuit_breaker: CircuitBreakerParams,
+    pub baseline_nav_sats: u64,
+    pub admin_config: Pubkey,
+    pub previous_treasury_pubkey_bytes: [u8; 33],
+    pub treasury_rotated_at: u64,
+    pub paused: u8,
+    pub nav_history: Pubkey,
+    pub fees: FeeParams,
+    pub high_water_mark_sats: u64,
+    pub last_fee_accrual: u64,
+}
+
+/// Migrated funds stay open until the admins restrict them
+impl From<OVTStateV4> for OVTStateV5 {
+    fn from(legacy: OVTStateV4) -> Self {
+        Self {
+            nav_sats: legacy.nav_sats,
+            treasury_pubkey_bytes: legacy.treasury_pubkey_bytes,
+            total_supply: legacy.total_supply,
+            last_nav_update: legacy.last_nav_update,
+            position_count: legacy.position_count,
+            oracle_program_id: legacy.oracle_program_id,
+            circuit_breaker: legacy.circuit_breaker,
+            baseline_nav_sats: legacy.baseline_nav_sats,
+            admin_config: legacy.admin_config,
+            previous_treasury_pubkey_bytes: legacy.previous_treasury_pubkey_bytes,
+            treasury_rotated_at: legacy.treasury_rotated_at,
+            paused: legacy.paused,
+            nav_history: legacy.nav_history,
+            fees: legacy.fees,
+            high_water_mark_sats: legacy.high_water_mark_sats,
+            last_fee_accrual: legacy.last_fee_accrual,
+            allowlist: AllowlistParams::default(),
+        }
+    }
+}
+
+/// Versioned layout 5, before per-epoch supply budgets
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct OVTStateV5 {
+    pub nav_sats: u64,
+    pub treasury_pubkey_bytes: [u8; 33],
+    pub total_supply: u64,
+    pub last_nav_update: u64,
+    pub position_count: u64,
+    pub oracle_program_id: Pubkey,
+    pub circuit_breaker: CircuitBreakerParams,
+    pub baseline_nav_sats: u64,
+    pub admin_config: Pubkey,
+    pub previous_treasury_pubkey_bytes: [u8; 33],
+    pub treasury_rotated_at: u64,
+    pub paused: u8,
+    pub nav_history: Pubkey,
+    pub fees: FeeParams,
+    pub high_water_mark_sats: u64,
+    pub last_fee_accrual: u64,
+    pub allowlist: AllowlistParams,
+}
+
+/// Migrated funds get the default budgets, the old per-instruction bound
+/// applied over a day. The first epoch starts with the next mint or burn.
+impl From<OVTStateV5> for OVTStateV6 {
+    fn from(legacy: OVTStateV5) -> Self {
+        Self {
+            nav_sats: legacy.nav_sats,
+            treasury_pubkey_bytes: legacy.treasury_pubkey_bytes,
+            total_supply: legacy.total_supply,
+            last_nav_update: legacy.last_nav_update,
+            position_count: legacy.position_count,
+            oracle_program_id: legacy.oracle_program_id,
+            circuit_breaker: legacy.circuit_breaker,
+            baseline_nav_sats: legacy.baseline_nav_sats,
+            admin_config: legacy.admin_config,
+            previous_treasury_pubkey_bytes: legacy.previous_treasury_pubkey_bytes,
+            treasury_rotated_at: legacy.treasury_rotated_at,
+            paused: legacy.paused,
+            nav_history: legacy.nav_history,
+            fees: legacy.fees,
+            high_water_mark_sats: legacy.high_water_mark_sats,
+            last_fee_accrual: legacy.last_fee_accrual,
+            allowlist: legacy.allowlist,
+            epoch: EpochParams::default(),
+            epoch_start: 0,
+            epoch_start_supply: 0,
+            epoch_minted: 0,
+            epoch_burned: 0,
+        }
+    }
+}
+
+/// Versioned layout 6, before the oracle publisher was recorded
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct OVTStateV6 {
+    pub nav_sats: u64,
+    pub treasury_pubkey_bytes: [u8; 33],
+    pub total_supply: u64,
+    pub last_nav_update: u64,
+    pub position_count: u64,
+    pub oracle_program_id: Pubkey,
+    pub circuit_breaker: CircuitBreakerParams,
+    pub baseline_nav_sats: u64,
+    pub admin_config: Pubkey,
+    pub previous_treasury_pubkey_bytes: [u8; 33],
+    pub treasury_rotated_at: u64,
+    pub paused: u8,
+    pub nav_history: Pubkey,
+    pub fees: FeeParams,
+    pub high_water_mark_sats: u64,
+    pub last_fee_accrual: u64,
+    pub allowlist: AllowlistParams,
+    pub epoch: EpochParams,
+    pub epoch_start: u64,
+    pub epoch_start_supply: u64,
+    pub epoch_minted: u64,
+    pub epoch_burned: u64,
+}
+
+/// Migrated funds accept no oracle price until the admins set the publisher
+impl From<OVTStateV6> for OVTState {
+    fn from(legacy: OVTStateV6) -> Self {
+        Self {
+            nav_sats: legacy.nav_sats,
+            treasury_pubkey_bytes: legacy.treasury_pubkey_bytes,
+            total_supply: legacy.total_supply,
+            last_nav_update: legacy.last_nav_update,
+            position_count: legacy.position_count,
+            oracle_program_id: legacy.oracle_program_id,
+            circuit_breaker: legacy.circuit_breaker,
+            baseline_nav_sats: legacy.baseline_nav_sats,
+            admin_config: legacy.admin_config,
+            previous_treasury_pubkey_bytes: legacy.previous_treasury_pubkey_bytes,
+            treasury_rotated_at: legacy.treasury_rotated_at,
+            paused: legacy.paused,
+            nav_history: legacy.nav_history,
+            fees: legacy.fees,
+            high_water_mark_sats: legacy.high_water_mark_sats,
+            last_fee_accrual: legacy.last_fee_accrual,
+            allowlist: legacy.allowlist,
+            epoch: legacy.epoch,
+            epoch_start: legacy.epoch_start,
+            epoch_start_supply: legacy.epoch_start_supply,
+            epoch_minted: legacy.epoch_minted,
+            epoch_burned: legacy.epoch_burned,
+            oracle_publisher: Pubkey::new(),
+        }
+    }
+}
+
+/// Settings v1 state did not record, supplied when migrating a v1 account
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct V1Upgrade {
+    pub oracle_program_id: Pubkey,
+    pub oracle_publisher: Pubkey,
+    pub circuit_breaker: CircuitBreakerParams,
+    pub admins: Vec<Pubkey>,
+    pub threshold: u8,
+    pub timelock: TimelockParams,
+}
+
+/// Layout version of raw state account data, 1 for the unversioned layout
+pub fn state_version(data: &[u8]) -> Result<u8, ProgramError> {
+    match data.strip_prefix(&STATE_DISCRIMINATOR) {
+        Some(rest) => rest.first().copied().ok_or(ProgramError::InvalidAccountData),
+        None if data.is_empty() => Err(ProgramError::InvalidAccountData),
+        None => Ok(1),
+    }
+}
+
+impl BorshSerialize for OVTState {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        STATE_DISCRIMINATOR.serialize(writer)?;
+        STATE_VERSION.serialize(writer)?;
+        self.nav_sats.serialize(writer)?;
+        self.treasury_pubkey_bytes.serialize(writer)?;
+        self.total_supply.serialize(writer)?;
+        self.last_nav_update.serialize(writer)?;
+        self.position_count.serialize(writer)?;
+        self.oracle_program_id.serialize(writer)?;
+        self.circuit_breaker.serialize(writer)?;
+        self.baseline_nav_sats.serialize(writer)?;
+        self.admin_config.serialize(writer)?;
+        self.previous_treasury_pubkey_bytes.serialize(writer)?;
+        self.treasury_rotated_at.serialize(writer)?;
+        self.paused.serialize(writer)?;
+        self.nav_history.serialize(writer)?;
+        self.fees.serialize(writer)?;
+        self.high_water_mark_sats.serialize(writer)?;
+        self.last_fee_accrual.serialize(writer)?;
+        self.allowlist.serialize(writer)?;
+        self.epoch.serialize(writer)?;
+        self.epoch_start.serialize(writer)?;
+        self.epoch_start_supply.serialize(writer)?;
+        self.epoch_minted.serialize(writer)?;
+        self.epoch_burned.serialize(writer)?;
+        self.oracle_publisher.serialize(writer)
+    }
+}
+
+impl BorshDeserialize for OVTState {
+    /// Only the current layout decodes. Older accounts must be migrated first.
+    fn deserialize_reader<R: Read>(reader: &mut R) -> io::Result<Self> {
+        if <[u8; 8]>::deserialize_reader(reader)? != STATE_DISCRIMINATOR {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "missing OVT state discriminator"));
+        }
+        let version = u8::deserialize_reader(reader)?;
+        if version != STATE_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("OVT state version {} must be migrated to {}", version, STATE_VERSION),
+            ));
+        }
+        Ok(Self {
+            nav_sats: BorshDeserialize::deserialize_reader(reader)?,
+            treasury_pubkey_bytes: BorshDeserialize::deserialize_reader(reader)?,
+            total_supply: BorshDeserialize::deserialize_reader(reader)?,
+            last_nav_update: BorshDeserialize::deserialize_reader(reader)?,
+            position_count: BorshDeserialize::deserialize_reader(reader)?,
+            oracle_program_id: BorshDeserialize::deserialize_reader(reader)?,
+            circuit_breaker: BorshDeserialize::deserialize_reader(reader)?,
+            baseline_nav_sats: BorshDeserialize::deserialize_reader(reader)?,
+            admin_config: BorshDeserialize::deserialize_reader(reader)?,
+            previous_treasury_pubkey_bytes: BorshDeserialize::deserialize_reader(reader)?,
+            treasury_rotated_at: BorshDeserialize::deserialize_reader(reader)?,
+            paused: BorshDeserialize::deserialize_reader(reader)?,
+            nav_history: BorshDeserialize::deserialize_reader(reader)?,
+            fees: BorshDeserialize::deserialize_reader(reader)?,
+            high_water_mark_sats: BorshDeserialize::deserialize_reader(reader)?,
+            last_fee_accrual: BorshDeserialize::deserialize_reader(reader)?,
+            allowlist: BorshDeserialize::deserialize_reader(reader)?,
+            epoch: BorshDeserialize::deserialize_reader(reader)?,
+            epoch_start: BorshDeserialize::deserialize_reader(reader)?,
+            epoch_start_supply: BorshDeserialize::deserialize_reader(reader)?,
+            epoch_minted: BorshDeserialize::deserialize_reader(reader)?,
+            epoch_burned: BorshDeserialize::deserialize_reader(reader)?,
+            oracle_publisher: BorshDeserialize::deserialize_reader(reader)?,
+        })
+    }
+}
+
+impl OVTProgram {
+    /// Upgrade the state account to `STATE_VERSION`, growing it if the new
+    /// layout is larger. Accounts already at the current version are left
+    /// untouched.
+    pub(crate) fn process_migrate(ctx: &ProgramContext, v1_upgrade: Option<V1Upgrade>) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+        let authority_info = ctx.get(1)?;
+        let system_program = ctx.get(2)?;
+
+        if *state_info.owner.borrow() != ctx.program_id {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let version = state_version(&state_info.data.borrow())?;
+        let state = match version {
+            STATE_VERSION => {
+                msg!("State already at version {}", STATE_VERSION);
+                return Ok(());
+            }
+            1 => {
+                let upgrade = v1_upgrade.ok_or(ProgramError::InvalidArgument)?;
+                Self::migrate_v1(ctx, state_info, upgrade)?
+            }
+            2..=6 => {
+                let data = state_info.data.borrow();
+                let mut fields = &data[STATE_DISCRIMINATOR.len() + 1..];
+                let legacy = match version {
+                    2 => OVTStateV2::deserialize(&mut fields)
+                        .map(OVTStateV3::from)
+                        .map(OVTStateV4::from)
+                        .map(OVTStateV5::from)
+                        .map(OVTStateV6::from),
+                    3 => OVTStateV3::deserialize(&mut fields)
+                        .map(OVTStateV4::from)
+                        .map(OVTStateV5::from)
+                        .map(OVTStateV6::from),
+                    4 => OVTStateV4::deserialize(&mut fields).map(OVTStateV5::from).map(OVTStateV6::from),
+                    5 => OVTStateV5::deserialize(&mut fields).map(OVTStateV6::from),
+                    _ => OVTStateV6::deserialize(&mut fields),
+                };
+                let state = OVTState::from(legacy.map_err(|_| ProgramError::InvalidAccountData)?);
+                Self::verify_admin_quorum(ctx, &state)?;
+                state
+            }
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        resize_program_account(state_info, authority_info, borsh::object_length(&state)? as u64, system_program)?;
+        ctx.emit(&OVTEvent::StateMigrated { from_version: version, to_version: STATE_VERSION })?;
+        state_info.set_data(&state)
+    }
+
+    /// Build current state from a v1 account and create the admin config it
+    /// lacked. `V1_UPGRADE_AUTHORITY` must sign for the new admin set, and
+    /// the threshold of that set must sign too, counted as the admin quorum
+    /// is counted afterwards.
+    fn migrate_v1(ctx: &ProgramContext, state_info: &AccountInfo, upgrade: V1Upgrade) -> Result<OVTState, ProgramError> {
+        let authority_info = ctx.get(1)?;
+        let system_program = ctx.get(2)?;
+        let admin_config_info = ctx.get(3)?;
+
+        let legacy: OVTStateV1 = OVTStateV1::deserialize(&mut &state_info.data.borrow()[..])
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+
+        let admin_config = AdminConfig {
+            admins: upgrade.admins,
+            threshold: upgrade.threshold,
+            timelock: upgrade.timelock,
+        };
+        upgrade.circuit_breaker.validate()?;
+        admin_config.validate()?;
+        if upgrade.oracle_publisher == Pubkey::new() {
+            return Err(OVTError::InvalidOracleAccount.into());
+        }
+        if authority_info.key != V1_UPGRADE_AUTHORITY {
+            return Err(OVTError::InvalidAuthority.into());
+        }
+        if !authority_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        let signers = admin_config.count_signers(&ctx.accounts);
+        if signers < admin_config.threshold as usize {
+            msg!("Only {} of {} required admin signatures", signers, admin_config.threshold);
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        verify_uninitialized(admin_config_info)?;
+
+        create_program_account(
+            &ctx.program_id,
+            admin_config_info,
+            authority_info,
+            AdminConfig::MAX_SPACE as u64,
+            system_program,
+        )?;
+        initialize_account(&ctx.program_id, admin_config_info, &admin_config)?;
+
+        // The NAV at migration becomes the circuit breaker baseline
+        Ok(OVTState {
+            nav_sats: legacy.nav_sats,
+            treasury_pubkey_bytes: legacy.treasury_pubkey_bytes,
+            total_supply: legacy.total_supply,
+            last_nav_update: legacy.last_nav_update,
+            position_count: 0,
+            oracle_program_id: upgrade.oracle_program_id,
+            circuit_breaker: upgrade.circuit_breaker,
+            baseline_nav_sats: legacy.nav_sats,
+            admin_config: admin_config_info.key,
+            previous_treasury_pubkey_bytes: [0u8; 33],
+            treasury_rotated_at: 0,
+            paused: 0,
+            nav_history: Pubkey::new(),
+            fees: FeeParams::default(),
+            high_water_mark_sats: 0,
+            last_fee_accrual: 0,
+            allowlist: AllowlistParams::default(),
+            epoch: EpochParams::default(),
+            epoch_start: 0,
+            epoch_start_supply: 0,
+            epoch_minted: 0,
+            epoch_burned: 0,
+            oracle_publisher: upgrade.oracle_publisher,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock_sdk::AccountMeta,
+        test_fixtures::Fixture,
+        OVTInstruction,
+    };
+
+    fn v1_fixture() -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&1_000_000u64.to_le_bytes());
+        data.extend_from_slice(&[2u8; 33]);
+        data.extend_from_slice(&500_000u64.to_le_bytes());
+        data.extend_from_slice(&1_700_000_000u64.to_le_bytes());
+        data
+    }
+
+    fn v2_fixture(admin_config: &Pubkey) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(b"OVTSTATE");
+        data.push(2);
+        data.extend_from_slice(&1_000_000u64.to_le_bytes());
+        data.extend_from_slice(&[2u8; 33]);
+        data.extend_from_slice(&500_000u64.to_le_bytes());
+        data.extend_from_slice(&1_700_000_000u64.to_le_bytes());
+        data.extend_from_slice(&3u64.to_le_bytes());
+        data.extend_from_slice(&[7u8; 32]);
+        for bps in [410_000u64, 500, 20_000, 5_000] {
+            data.extend_from_slice(&bps.to_le_bytes());
+        }
+        data.extend_from_slice(&900_000u64.to_le_bytes());
+        data.extend_from_slice(&admin_config.0);
+        data.extend_from_slice(&[3u8; 33]);
+        data.extend_from_slice(&1_700_000_600u64.to_le_bytes());
+        data.push(0b0101);
+        data
+    }
+
+    #[test]
+    fn test_decode_historical_layouts() {
+        let v1 = v1_fixture();
+        assert_eq!(state_version(&v1).unwrap(), 1);
+        assert_eq!(OVTStateV1::try_from_slice(&v1).unwrap(), OVTStateV1 {
+            nav_sats: 1_000_000,
+            treasury_pubkey_bytes: [2u8; 33],
+            total_supply: 500_000,
+            last_nav_update: 1_700_000_000,
+        });
+        // v1 accounts do not decode as current state
+        assert!(OVTState::try_from_slice(&v1).is_err());
+
+        let admin_config = Pubkey::new_unique();
+        let v2 = v2_fixture(&admin_config);
+        assert_eq!(state_version(&v2).unwrap(), 2);
+        assert!(OVTState::try_from_slice(&v2).is_err());
+        let legacy = OVTStateV2::try_from_slice(&v2[9..]).unwrap();
+        assert_eq!(legacy.position_count, 3);
+        assert_eq!(legacy.paused, 0b0101);
+
+        let nav_history = Pubkey::new_unique();
+        let mut v3 = v2.clone();
+        v3[8] = 3;
+        v3.extend_from_slice(&nav_history.0);
+        assert!(OVTState::try_from_slice(&v3).is_err());
+        assert_eq!(OVTStateV3::try_from_slice(&v3[9..]).unwrap(), OVTStateV3 {
+            nav_history,
+            ..OVTStateV3::from(legacy)
+        });
+
+        let fee_recipient = Pubkey::new_unique();
+        let mut v4 = v3.clone();
+        v4[8] = 4;
+        for value in [200u64, 2_000] {
+            v4.extend_from_slice(&value.to_le_bytes());
+        }
+        v4.extend_from_slice(&fee_recipient.0);
+        v4.extend_from_slice(&110_000_000u64.to_le_bytes());
+        v4.extend_from_slice(&1_700_000_300u64.to_le_bytes());
+        assert!(OVTState::try_from_slice(&v4).is_err());
+        let legacy = OVTStateV4::try_from_slice(&v4[9..]).unwrap();
+        assert_eq!(legacy.fees.fee_recipient, fee_recipient);
+        assert_eq!(OVTStateV5::from(legacy).allowlist, AllowlistParams::default());
+
+        let mut v5 = v4.clone();
+        v5[8] = 5;
+        v5.push(1);
+        v5.extend_from_slice(&[9u8; 32]);
+        assert!(OVTState::try_from_slice(&v5).is_err());
+        let legacy = OVTStateV5::try_from_slice(&v5[9..]).unwrap();
+        assert!(legacy.allowlist.restricted);
+        assert_eq!(OVTStateV6::from(legacy).epoch, EpochParams::default());
+
+        let mut v6 = v5.clone();
+        v6[8] = 6;
+        for value in [3_600u64, 500, 200, 1_700_000_000, 400_000, 1_000, 2_000] {
+            v6.extend_from_slice(&value.to_le_bytes());
+        }
+        assert!(OVTState::try_from_slice(&v6).is_err());
+        let legacy = OVTStateV6::try_from_slice(&v6[9..]).unwrap();
+        assert_eq!(legacy.epoch_burned, 2_000);
+        assert_eq!(OVTState::from(legacy).oracle_publisher, Pubkey::new());
+
+        let oracle_publisher = Pubkey::new_unique();
+        let mut v7 = v6.clone();
+        v7[8] = 7;
+        v7.extend_from_slice(&oracle_publisher.0);
+        let state = OVTState::try_from_slice(&v7).unwrap();
+        assert_eq!(state.nav_sats, 1_000_000);
+        assert_eq!(state.total_supply, 500_000);
+        assert_eq!(state.position_count, 3);
+        assert_eq!(state.oracle_program_id, Pubkey([7u8; 32]));
+        assert_eq!(state.circuit_breaker, CircuitBreakerParams::default());
+        assert_eq!(state.baseline_nav_sats, 900_000);
+        assert_eq!(state.admin_config, admin_config);
+        assert_eq!(state.previous_treasury_pubkey_bytes, [3u8; 33]);
+        assert_eq!(state.treasury_rotated_at, 1_700_000_600);
+        assert_eq!(state.paused, 0b0101);
+        assert_eq!(state.nav_history, nav_history);
+        assert_eq!(state.fees, FeeParams { management_fee_bps: 200, performance_fee_bps: 2_000, fee_recipient });
+        assert_eq!(state.high_water_mark_sats, 110_000_000);
+        assert_eq!(state.last_fee_accrual, 1_700_000_300);
+        assert_eq!(state.allowlist, AllowlistParams { restricted: true, merkle_root: [9u8; 32] });
+        assert_eq!(state.epoch, EpochParams { epoch_secs: 3_600, max_mint_bps: 500, max_burn_bps: 200 });
+        assert_eq!(state.epoch_start, 1_700_000_000);
+        assert_eq!(state.epoch_start_supply, 400_000);
+        assert_eq!((state.epoch_minted, state.epoch_burned), (1_000, 2_000));
+        assert_eq!(state.oracle_publisher, oracle_publisher);
+        assert_eq!(borsh::to_vec(&state).unwrap(), v7);
+
+        // Unknown versions and empty accounts are refused
+        let mut future = v7.clone();
+        future[8] = STATE_VERSION + 1;
+        assert!(OVTState::try_from_slice(&future).is_err());
+        assert!(state_version(&[]).is_err());
+    }
+
+    fn migrate_v1(
+        fixture: &mut Fixture,
+        admin_config: Pubkey,
+        authority: AccountMeta,
+        upgrade: Option<V1Upgrade>,
+        signers: &[Pubkey],
+    ) -> Result<Vec<OVTEvent>, ProgramError> {
+        fixture.client.create_system_account(V1_UPGRADE_AUTHORITY)?;
+        let mut accounts = vec![
+            AccountMeta::new(fixture.state, true),
+            authority,
+            AccountMeta::new_readonly(fixture.system_program, false),
+            AccountMeta::new(admin_config, true),
+        ];
+        accounts.extend(signers.iter().map(|signer| AccountMeta::new_readonly(*signer, true)));
+        fixture.process_raw(accounts, &OVTInstruction::Migrate { v1_upgrade: upgrade })
+    }
+
+    fn v1_upgrade(fixture: &Fixture, admins: Vec<Pubkey>) -> V1Upgrade {
+        V1Upgrade {
+            oracle_program_id: fixture.oracle_program,
+            oracle_publisher: fixture.oracle_publisher,
+            circuit_breaker: CircuitBreakerParams::default(),
+            admins,
+            threshold: Fixture::THRESHOLD,
+            timelock: TimelockParams::default(),
+        }
+    }
+
+    #[test]
+    fn test_migrate_v1() {
+        let mut fixture = Fixture::new();
+        fixture.client.accounts.lock().unwrap()[&fixture.state].data.replace(v1_fixture());
+        let admin_config = fixture.create_system_account();
+        let upgrade = v1_upgrade(&fixture, fixture.admins.clone());
+        let authority = AccountMeta::new_readonly(V1_UPGRADE_AUTHORITY, true);
+        let admins = fixture.admins.clone();
+
+        // Nothing reads a v1 account until it is migrated
+        let err = fixture.process(vec![AccountMeta::new(fixture.state, true)], &OVTInstruction::Pause { classes: 1 })
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Invalid account data");
+
+        assert!(migrate_v1(&mut fixture, admin_config, authority.clone(), None, &admins[..3]).is_err());
+        let err = migrate_v1(&mut fixture, admin_config, authority.clone(), Some(upgrade.clone()), &admins[..2])
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Missing required signature");
+
+        migrate_v1(&mut fixture, admin_config, authority.clone(), Some(upgrade.clone()), &admins[..3]).unwrap();
+        let state = fixture.state();
+        assert_eq!(state.nav_sats, 1_000_000);
+        assert_eq!(state.baseline_nav_sats, 1_000_000);
+        assert_eq!(state.total_supply, 500_000);
+        assert_eq!(state.admin_config, admin_config);
+        let stored: AdminConfig = fixture.client.get_account_data(&admin_config).unwrap();
+        assert_eq!(stored.admins, fixture.admins);
+        let data_len = fixture.client.accounts.lock().unwrap()[&fixture.state].data.borrow().len();
+        assert_eq!(data_len, borsh::object_length(&state).unwrap());
+
+        // Migrating a current account changes nothing
+        migrate_v1(&mut fixture, admin_config, authority, Some(upgrade), &admins[..3]).unwrap();
+        assert_eq!(fixture.state().admin_config, admin_config);
+    }
+
+    #[test]
+    fn test_migrate_v1_refuses_foreign_admins() {
+        let mut fixture = Fixture::new();
+        fixture.client.accounts.lock().unwrap()[&fixture.state].data.replace(v1_fixture());
+        let admin_config = fixture.create_system_account();
+
+        // A stranger's admin set is refused however many of them sign
+        let strangers: Vec<Pubkey> = (0..3).map(|_| fixture.create_signer()).collect();
+        let upgrade = v1_upgrade(&fixture, strangers.clone());
+        let authority = AccountMeta::new_readonly(strangers[0], true);
+        let err = migrate_v1(&mut fixture, admin_config, authority, Some(upgrade.clone()), &strangers[1..])
+            .unwrap_err();
+        assert_eq!(err.to_string(), "InvalidAuthority");
+
+        // Naming the upgrade key is not enough without its signature
+        let authority = AccountMeta::new_readonly(V1_UPGRADE_AUTHORITY, false);
+        let err = migrate_v1(&mut fixture, admin_config, authority, Some(upgrade), &strangers).unwrap_err();
+        assert_eq!(err.to_string(), "Missing required signature");
+
+        assert_eq!(state_version(&fixture.client.accounts.lock().unwrap()[&fixture.state].data.borrow()).unwrap(), 1);
+        assert!(fixture.client.get_account_data::<AdminConfig>(&admin_config).is_err());
+    }
+
+    #[test]
+    fn test_migrate_v2() {
+        let mut fixture = Fixture::new();
+        fixture.client.accounts.lock().unwrap()[&fixture.state].data.replace(v2_fixture(&fixture.admin_config));
+        let mut accounts = vec![
+            AccountMeta::new(fixture.state, true),
+            AccountMeta::new_readonly(fixture.authority(), true),
+            AccountMeta::new_readonly(fixture.system_program, false),
+        ];
+
+        // The admin quorum recorded in the v2 state must sign
+        let err = fixture.process_with_signers(accounts.clone(), 2, &OVTInstruction::Migrate { v1_upgrade: None })
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Missing required signature");
+
+        accounts.extend(fixture.signers(Fixture::THRESHOLD as usize));
+        let events = fixture.process_raw(accounts, &OVTInstruction::Migrate { v1_upgrade: None }).unwrap();
+        assert_eq!(events, vec![OVTEvent::StateMigrated { from_version: 2, to_version: STATE_VERSION }]);
+        let state = fixture.state();
+        assert_eq!(state.position_count, 3);
+        assert_eq!(state.treasury_rotated_at, 1_700_000_600);
+        assert_eq!(state.paused, 0b0101);
+        assert_eq!(state.nav_history, Pubkey::new());
+        assert_eq!(state.oracle_publisher, Pubkey::new());
+    }
+}
@@ -10,6 +10,8 @@ use std::sync::{Arc, Mutex};
 pub use account_info::AccountInfo;
 pub use pubkey::Pubkey;
 pub use program::{Program, ProgramContext, AccountMeta};
+pub use sysvar::clock::Clock;
+pub use log::LogSink;
 
 // Define ProgramResult at the root level
 pub type ProgramResult = Result<(), ProgramError>;
@@ -21,6 +23,12 @@ pub mod pubkey {
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
     pub struct Pubkey(pub [u8; 32]);
 
+    impl Default for Pubkey {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
     impl Pubkey {
         pub fn new() -> Self {
             Self([0; 32])
@@ -32,6 +40,21 @@ pub mod pubkey {
             rand::thread_rng().fill_bytes(&mut bytes);
             Self(bytes)
         }
+
+        /// Address derived from `seeds` and `program_id`, with its bump seed.
+        /// The mock hashes the inputs and always uses bump 255.
+        pub fn find_program_address(seeds: &[&[u8]], program_id: &Pubkey) -> (Self, u8) {
+            use bitcoin::hashes::{sha256, Hash};
+            let bump = u8::MAX;
+            let mut preimage = Vec::new();
+            for seed in seeds {
+                preimage.extend_from_slice(seed);
+            }
+            preimage.push(bump);
+            preimage.extend_from_slice(&program_id.0);
+            preimage.extend_from_slice(b"ProgramDerivedAddress");
+            (Self(sha256::Hash::hash(&preimage).to_byte_array()), bump)
+        }
     }
 
     impl BorshSerialize for Pubkey {
@@ -123,6 +146,81 @@ pub mod account_info {
     }
 }
 
+pub mod sysvar {
+    pub mod clock {
+        use super::super::*;
+        use super::super::account_info::AccountInfo;
+        use super::super::pubkey::Pubkey;
+
+        /// Address of the clock sysvar account
+        pub const ID: Pubkey = Pubkey(*b"SysvarC1ock111111111111111111111");
+
+        /// Cluster time as seen by the runtime
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+        pub struct Clock {
+            pub slot: u64,
+            pub unix_timestamp: u64,
+        }
+
+        impl Clock {
+            pub fn from_account_info(account_info: &AccountInfo) -> Result<Self, ProgramError> {
+                if account_info.key != ID {
+                    return Err(ProgramError::InvalidArgument);
+                }
+                account_info.get_data()
+            }
+        }
+    }
+}
+
+pub mod log {
+    use super::*;
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use std::rc::Rc;
+
+    /// Prefix of log lines carrying base64-encoded borsh event data
+    pub const DATA_LOG_PREFIX: &str = "Program data: ";
+
+    /// Collects the events a transaction emits, in order. Clones share the
+    /// same buffer, so nested invocations log into their caller's sink.
+    #[derive(Clone, Debug, Default)]
+    pub struct LogSink {
+        entries: Rc<RefCell<Vec<Vec<u8>>>>,
+    }
+
+    impl LogSink {
+        /// Record `event` and print it as a data log line
+        pub fn emit<T: BorshSerialize>(&self, event: &T) -> Result<(), ProgramError> {
+            let data = borsh::to_vec(event).map_err(|_| ProgramError::InvalidArgument)?;
+            println!("{}", encode_log_line(&data));
+            self.entries.borrow_mut().push(data);
+            Ok(())
+        }
+
+        /// Every event emitted so far, decoded as `T`
+        pub fn events<T: BorshDeserialize>(&self) -> Result<Vec<T>, ProgramError> {
+            self.entries.borrow().iter()
+                .map(|data| T::try_from_slice(data).map_err(|_| ProgramError::InvalidAccountData))
+                .collect()
+        }
+
+        /// Every event emitted so far as base64 data log lines
+        pub fn log_lines(&self) -> Vec<String> {
+            self.entries.borrow().iter().map(|data| encode_log_line(data)).collect()
+        }
+    }
+
+    pub fn encode_log_line(data: &[u8]) -> String {
+        format!("{}{}", DATA_LOG_PREFIX, STANDARD.encode(data))
+    }
+
+    /// Decode a data log line back into an event, `None` for other lines
+    pub fn decode_log_line<T: BorshDeserialize>(line: &str) -> Option<T> {
+        let data = STANDARD.decode(line.strip_prefix(DATA_LOG_PREFIX)?).ok()?;
+        T::try_from_slice(&data).ok()
+    }
+}
+
 pub mod program {
     use super::*;
     use super::account_info::AccountInfo;
@@ -162,6 +260,8 @@ pub mod program {
         pub program_id: Pubkey,
         pub accounts: Vec<AccountInfo>,
         pub test_client: Option<super::test_utils::TestClient>,
+        /// Events emitted by the instruction
+        pub log: super::LogSink,
     }
 
     impl ProgramContext {
@@ -170,6 +270,7 @@ pub mod program {
                 program_id,
                 accounts,
                 test_client: None,
+                log: super::LogSink::default(),
             }
         }
 
@@ -182,6 +283,7 @@ pub mod program {
                 program_id,
                 accounts,
                 test_client: Some(test_client),
+                log: super::LogSink::default(),
             }
         }
 
@@ -189,6 +291,48 @@ pub mod program {
             self.accounts.get(index).ok_or(ProgramError::AccountNotFound)
         }
 
+        /// Run `f` in a context over `accounts`, as a cross-program invocation
+        /// would, then copy what it wrote to writable accounts back into this
+        /// context
+        pub fn invoke(
+            &self,
+            accounts: Vec<AccountInfo>,
+            f: impl FnOnce(&ProgramContext) -> ProgramResult,
+        ) -> ProgramResult {
+            let nested = ProgramContext {
+                program_id: self.program_id,
+                accounts,
+                test_client: self.test_client.clone(),
+                log: self.log.clone(),
+            };
+            f(&nested)?;
+
+            for inner in nested.accounts.iter().filter(|account| account.is_writable) {
+                for outer in self.accounts.iter().filter(|account| account.key == inner.key) {
+                    *outer.lamports.borrow_mut() = *inner.lamports.borrow();
+                    outer.data.borrow_mut().clone_from(&inner.data.borrow());
+                    *outer.owner.borrow_mut() = *inner.owner.borrow();
+                }
+            }
+            Ok(())
+        }
+
+        /// Current clock, read from the clock sysvar account if it was passed
+        /// in, otherwise from the test client's runtime
+        pub fn clock(&self) -> Result<super::Clock, ProgramError> {
+            if let Some(clock_info) = self.accounts.iter().find(|a| a.key == super::sysvar::clock::ID) {
+                return super::Clock::from_account_info(clock_info);
+            }
+            self.test_client.as_ref()
+                .map(|client| client.clock())
+                .ok_or(ProgramError::AccountNotFound)
+        }
+
+        /// Emit a program event to the transaction's log
+        pub fn emit<T: BorshSerialize>(&self, event: &T) -> ProgramResult {
+            self.log.emit(event)
+        }
+
         pub fn is_admin(&self, pubkey: &Pubkey) -> bool {
             self.test_client.as_ref()
                 .map(|client| client.is_admin(pubkey))
@@ -234,7 +378,7 @@ impl From<io::Error> for ProgramError {
     }
 }
 
-#[derive(Debug, BorshSerialize, BorshDeserialize)]
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
 pub struct TokenAccount {
     pub mint: pubkey::Pubkey,
     pub owner: pubkey::Pubkey,
@@ -272,18 +416,38 @@ pub mod test_utils {
         pub accounts: Arc<Mutex<HashMap<Pubkey, AccountInfo>>>,
         pub admin_accounts: Arc<Mutex<HashMap<Pubkey, bool>>>,
         pub pending_actions: Arc<Mutex<Vec<AdminAction>>>,
+        pub clock: Arc<Mutex<Clock>>,
         pub required_signatures: usize,
         pub total_admins: usize,
     }
 
+    impl Default for TestClient {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
     impl TestClient {
+        /// Unix timestamp the test clock starts at
+        pub const GENESIS_TIMESTAMP: u64 = 1_700_000_000;
+
         pub fn new() -> Self {
+            Self::with_multisig(3, 5)
+        }
+
+        /// Test client whose off-chain actions need `required_signatures` of
+        /// at most `total_admins` admin accounts
+        pub fn with_multisig(required_signatures: usize, total_admins: usize) -> Self {
             Self {
                 accounts: Arc::new(Mutex::new(HashMap::new())),
                 admin_accounts: Arc::new(Mutex::new(HashMap::new())),
                 pending_actions: Arc::new(Mutex::new(Vec::new())),
-                required_signatures: 3, // 3 out of 5 required
-                total_admins: 5,
+                clock: Arc::new(Mutex::new(Clock {
+                    slot: 1,
+                    unix_timestamp: Self::GENESIS_TIMESTAMP,
+                })),
+                required_signatures,
+                total_admins,
             }
         }
 
@@ -305,6 +469,18 @@ pub mod test_utils {
             })
         }
 
+        /// Register an empty, system-owned account at `key` for the program
+        /// to create, such as a derived address
+        pub fn create_system_account(&mut self, key: Pubkey) -> Result<AccountHandle, ProgramError> {
+            let account = AccountInfo::new(key, false, true);
+            self.accounts.lock().unwrap().insert(key, account);
+            Ok(AccountHandle {
+                key,
+                is_signer: false,
+                is_writable: true,
+            })
+        }
+
         pub fn create_admin_account(&mut self, _program_id: Pubkey) -> Result<AccountHandle, ProgramError> {
             if self.admin_accounts.lock().unwrap().len() >= self.total_admins {
                 return Err(ProgramError::Custom("Maximum number of admins reached".to_string()));
@@ -328,6 +504,36 @@ pub mod test_utils {
             })
         }
 
+        pub fn clock(&self) -> Clock {
+            *self.clock.lock().unwrap()
+        }
+
+        /// Move the clock forward by `seconds`, advancing one slot
+        pub fn advance_clock(&self, seconds: u64) {
+            let mut clock = self.clock.lock().unwrap();
+            clock.slot += 1;
+            clock.unix_timestamp += seconds;
+        }
+
+        /// Register a mock system program account used when creating program accounts
+        pub fn create_system_program(&mut self, program_id: Pubkey) -> Result<AccountHandle, ProgramError> {
+            let key = Pubkey::new_unique();
+            let account = AccountInfo {
+                key,
+                is_signer: false,
+                is_writable: false,
+                lamports: RefCell::new(1),
+                data: RefCell::new(Vec::new()),
+                owner: RefCell::new(program_id),
+            };
+            self.accounts.lock().unwrap().insert(key, account);
+            Ok(AccountHandle {
+                key,
+                is_signer: false,
+                is_writable: false,
+            })
+        }
+
         pub fn is_admin(&self, pubkey: &Pubkey) -> bool {
             self.admin_accounts.lock().unwrap().get(pubkey).copied().unwrap_or(false)
         }
@@ -397,26 +603,22 @@ pub mod test_utils {
             }
         }
 
+        /// Run an instruction and return the events it emitted. Writable
+        /// accounts are only updated if it succeeds.
         pub fn process_transaction(
             &mut self,
             program_id: Pubkey,
             accounts: Vec<AccountMeta>,
             instruction_data: Vec<u8>,
-        ) -> ProgramResult {
+        ) -> Result<Vec<crate::events::OVTEvent>, ProgramError> {
             let mut ctx_accounts = Vec::new();
-            let mut original_keys = Vec::new();
             
             // First, collect the accounts and their metadata
-            let mut account_map = self.accounts.lock().unwrap();
+            let account_map = self.accounts.lock().unwrap();
             for meta in accounts {
                 let account = account_map.get(&meta.pubkey)
                     .ok_or(ProgramError::AccountNotFound)?;
                 
-                // Store original key for writable accounts
-                if meta.is_writable {
-                    original_keys.push(meta.pubkey);
-                }
-                
                 // Create new AccountInfo with same data
                 let account_info = AccountInfo {
                     key: account.key,
@@ -434,25 +636,20 @@ pub mod test_utils {
             drop(account_map);
 
             let ctx = ProgramContext::with_test_client(program_id, ctx_accounts, self.clone());
-            let result = crate::OVTProgram::process_instruction(&ctx, &instruction_data);
-
-            // If instruction succeeded, update writable accounts in the map
-            if result.is_ok() {
-                let mut account_map = self.accounts.lock().unwrap();
-                for (account_info, original_key) in ctx.accounts.into_iter()
-                    .zip(original_keys.iter())
-                    .filter(|(acc, _)| acc.is_writable)
-                {
-                    if let Some(account) = account_map.get_mut(original_key) {
-                        // Update the contents of the RefCells instead of replacing them
-                        *account.lamports.borrow_mut() = *account_info.lamports.borrow();
-                        account.data.borrow_mut().clone_from(&account_info.data.borrow());
-                        *account.owner.borrow_mut() = *account_info.owner.borrow();
-                    }
+            crate::OVTProgram::process_instruction(&ctx, &instruction_data)?;
+
+            // The instruction succeeded, so update writable accounts in the map
+            let mut account_map = self.accounts.lock().unwrap();
+            for account_info in ctx.accounts.iter().filter(|acc| acc.is_writable) {
+                if let Some(account) = account_map.get_mut(&account_info.key) {
+                    // Update the contents of the RefCells instead of replacing them
+                    *account.lamports.borrow_mut() = *account_info.lamports.borrow();
+                    account.data.borrow_mut().clone_from(&account_info.data.borrow());
+                    *account.owner.borrow_mut() = *account_info.owner.borrow();
                 }
             }
 
-            result
+            ctx.log.events()
         }
 
         pub fn get_account_data<T: BorshDeserialize>(&self, pubkey: &Pubkey) -> Result<T, ProgramError> {
@@ -460,6 +657,12 @@ pub mod test_utils {
                 .ok_or(ProgramError::AccountNotFound)?
                 .get_data()
         }
+
+        pub fn set_account_data<T: BorshSerialize>(&self, pubkey: &Pubkey, data: &T) -> ProgramResult {
+            self.accounts.lock().unwrap().get(pubkey)
+                .ok_or(ProgramError::AccountNotFound)?
+                .set_data(data)
+        }
     }
 }
 
@@ -467,7 +670,7 @@ pub mod test_utils {
 #[macro_export]
 macro_rules! msg {
     ($($arg:tt)*) => {
-        println!($($arg)*);
+        println!($($arg)*)
     };
 }
 
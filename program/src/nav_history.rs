@@ -0,0 +1,474 @@
+//! Recent NAV samples, kept so mints and redemptions can be priced against a
+//! time-weighted average rather than the NAV of a single update.
+
+use crate::mock_sdk::{
+    AccountInfo,
+    Pubkey,
+    ProgramContext,
+    ProgramResult,
+    ProgramError,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use crate::{
+    error::OVTError,
+    events::OVTEvent,
+    math::{mul_div, BPS_DENOMINATOR},
+    system::{create_program_account, initialize_account, verify_uninitialized},
+    OVTProgram,
+    OVTState,
+    OVT_DECIMALS,
+};
+
+/// Samples kept before the oldest is overwritten
+pub const NAV_HISTORY_CAPACITY: usize = 256;
+/// Longest TWAP window accepted. At the minimum update interval the history
+/// covers a little over 21 hours, so a day-long window is always mostly in range.
+pub const MAX_TWAP_WINDOW_SECS: u64 = 24 * 3_600;
+
+/// NAV and supply as of one NAV update
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct NavSample {
+    pub timestamp: u64,
+    pub nav_sats: u64,
+    pub total_supply: u64,
+}
+
+impl NavSample {
+    /// The NAV and supply of `state` as of its last NAV update
+    pub fn of(state: &OVTState) -> Self {
+        Self {
+            timestamp: state.last_nav_update,
+            nav_sats: state.nav_sats,
+            total_supply: state.total_supply,
+        }
+    }
+
+    /// NAV of one whole OVT in satoshis, as `OVTState::nav_per_token_sats`
+    pub fn nav_per_token_sats(&self) -> Result<u64, ProgramError> {
+        let one_token = 10u64.pow(OVT_DECIMALS as u32);
+        if self.total_supply == 0 {
+            return Ok(one_token);
+        }
+        mul_div(self.nav_sats, one_token, self.total_supply)
+    }
+}
+
+/// Fixed-capacity ring buffer of NAV samples, oldest overwritten first
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct NavHistory {
+    /// Window mints and redemptions are priced over, zero for spot pricing
+    pub twap_window_secs: u64,
+    /// Slot the next sample overwrites once the buffer is full
+    pub next: u32,
+    /// Samples in slot order, at most `NAV_HISTORY_CAPACITY`
+    pub samples: Vec<NavSample>,
+}
+
+impl NavHistory {
+    /// Account space for a full buffer
+    pub const MAX_SPACE: usize = 8 + 4 + 4 + 24 * NAV_HISTORY_CAPACITY;
+
+    pub fn new(twap_window_secs: u64) -> Result<Self, ProgramError> {
+        validate_twap_window(twap_window_secs)?;
+        Ok(Self {
+            twap_window_secs,
+            next: 0,
+            samples: Vec::with_capacity(NAV_HISTORY_CAPACITY),
+        })
+    }
+
+    pub fn push(&mut self, sample: NavSample) {
+        if self.samples.len() < NAV_HISTORY_CAPACITY {
+            self.samples.push(sample);
+        } else {
+            self.samples[self.next as usize] = sample;
+            self.next = ((self.next as usize + 1) % NAV_HISTORY_CAPACITY) as u32;
+        }
+    }
+
+    /// Samples from oldest to newest
+    pub fn iter(&self) -> impl Iterator<Item = &NavSample> {
+        let (newer, older) = self.samples.split_at(self.next as usize);
+        older.iter().chain(newer)
+    }
+
+    pub fn latest(&self) -> Option<&NavSample> {
+        self.iter().last()
+    }
+
+    /// The sample in effect at `timestamp`, the last one taken at or before it
+    pub fn sample_at(&self, timestamp: u64) -> Option<&NavSample> {
+        self.iter().take_while(|sample| sample.timestamp <= timestamp).last()
+    }
+
+    /// Time-weighted average NAV over the `window_secs` before `now`
+    pub fn twap_nav_sats(&self, now: u64, window_secs: u64) -> Result<Option<u64>, ProgramError> {
+        self.time_weighted(now, window_secs, |sample| Ok(sample.nav_sats))
+    }
+
+    /// Time-weighted average per-token NAV over the `window_secs` before `now`
+    pub fn twap_nav_per_token_sats(&self, now: u64, window_secs: u64) -> Result<Option<u64>, ProgramError> {
+        self.time_weighted(now, window_secs, NavSample::nav_per_token_sats)
+    }
+
+    /// Change in per-token NAV from `start` to `end` in basis points, or
+    /// `None` if the history does not reach back to `start`
+    pub fn return_bps(&self, start: u64, end: u64) -> Result<Option<i64>, ProgramError> {
+        let (Some(from), Some(to)) = (self.sample_at(start), self.sample_at(end)) else {
+            return Ok(None);
+        };
+        let from = from.nav_per_token_sats()? as i128;
+        let to = to.nav_per_token_sats()? as i128;
+        if from == 0 {
+            return Ok(None);
+        }
+        let bps = (to - from) * BPS_DENOMINATOR as i128 / from;
+        i64::try_from(bps).map(Some).map_err(|_| OVTError::ArithmeticOverflow.into())
+    }
+
+    /// Returns over the `periods` consecutive periods of `period_secs` ending
+    /// at `end`, oldest first
+    pub fn period_returns_bps(&self, end: u64, period_secs: u64, periods: u32) -> Result<Vec<Option<i64>>, ProgramError> {
+        if period_secs == 0 {
+            return Err(ProgramError::InvalidArgument);
+        }
+        (0..periods as u64).rev()
+            .map(|k| {
+                let period_end = end.saturating_sub(k.saturating_mul(period_secs));
+                self.return_bps(period_end.saturating_sub(period_secs), period_end)
+            })
+            .collect()
+    }
+
+    /// Each sample holds until the next one is taken. Only the part of the
+    /// window the history covers is averaged; a history whose only sample is
+    /// at `now` averages to that sample.
+    fn time_weighted(
+        &self,
+        now: u64,
+        window_secs: u64,
+        value: impl Fn(&NavSample) -> Result<u64, ProgramError>,
+    ) -> Result<Option<u64>, ProgramError> {
+        let samples: Vec<&NavSample> = self.iter().filter(|sample| sample.timestamp <= now).collect();
+        let Some(latest) = samples.last() else {
+            return Ok(None);
+        };
+        let start = now.saturating_sub(window_secs);
+
+        let mut weighted: u128 = 0;
+        let mut elapsed: u64 = 0;
+        for (i, sample) in samples.iter().enumerate() {
+            let from = sample.timestamp.max(start);
+            let until = samples.get(i + 1).map_or(now, |next| next.timestamp);
+            if until <= from {
+                continue;
+            }
+            weighted = weighted
+                .checked_add(value(sample)? as u128 * (until - from) as u128)
+                .ok_or(OVTError::ArithmeticOverflow)?;
+            elapsed += until - from;
+        }
+        if elapsed == 0 {
+            return value(latest).map(Some);
+        }
+        Ok(Some((weighted / elapsed as u128) as u64))
+    }
+
+    /// Load the history stored in `history_info`, checking it belongs to this program
+    pub fn load(program_id: &Pubkey, history_info: &AccountInfo) -> Result<Self, ProgramError> {
+        if *history_info.owner.borrow() != *program_id {
+            return Err(OVTError::InvalidNavHistory.into());
+        }
+        history_info.get_data()
+            .map_err(|_| OVTError::InvalidNavHistory.into())
+    }
+}
+
+fn validate_twap_window(twap_window_secs: u64) -> ProgramResult {
+    if twap_window_secs > MAX_TWAP_WINDOW_SECS {
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(())
+}
+
+impl OVTProgram {
+    /// Create the NAV history account and record it in the state. The
+    /// current NAV, if any, becomes the first sample.
+    pub(crate) fn process_initialize_nav_history(ctx: &ProgramContext, twap_window_secs: u64) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+        let history_info = ctx.get(1)?;
+        let authority_info = ctx.get(2)?;
+        let system_program = ctx.get(3)?;
+
+        let mut state: OVTState = state_info.get_data()?;
+        Self::verify_admin_quorum(ctx, &state)?;
+        if state.nav_history != Pubkey::new() {
+            return Err(OVTError::InvalidNavHistory.into());
+        }
+        verify_uninitialized(history_info)?;
+
+        let mut history = NavHistory::new(twap_window_secs)?;
+        if state.last_nav_update > 0 {
+            history.push(NavSample::of(&state));
+        }
+        create_program_account(
+            &ctx.program_id,
+            history_info,
+            authority_info,
+            NavHistory::MAX_SPACE as u64,
+            system_program,
+        )?;
+        initialize_account(&ctx.program_id, history_info, &history)?;
+
+        state.nav_history = history_info.key;
+        ctx.emit(&OVTEvent::NavHistoryInitialized {
+            nav_history: history_info.key,
+            twap_window_secs,
+        })?;
+        state_info.set_data(&state)
+    }
+
+    pub(crate) fn process_set_twap_window(ctx: &ProgramContext, twap_window_secs: u64) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+
+        let state: OVTState = state_info.get_data()?;
+        Self::verify_admin_quorum(ctx, &state)?;
+        validate_twap_window(twap_window_secs)?;
+        let (history_info, mut history) = Self::load_nav_history(ctx, &state)?
+            .ok_or(OVTError::InvalidNavHistory)?;
+
+        history.twap_window_secs = twap_window_secs;
+        ctx.emit(&OVTEvent::TwapWindowUpdated { twap_window_secs })?;
+        history_info.set_data(&history)
+    }
+
+    /// Find the NAV history recorded in `state` among the instruction
+    /// accounts. Once a state has a history it must always be passed.
+    pub(crate) fn load_nav_history<'a>(
+        ctx: &'a ProgramContext,
+        state: &OVTState,
+    ) -> Result<Option<(&'a AccountInfo, NavHistory)>, ProgramError> {
+        if state.nav_history == Pubkey::new() {
+            return Ok(None);
+        }
+        let history_info = ctx.accounts.iter()
+            .find(|info| info.key == state.nav_history)
+            .ok_or(OVTError::InvalidNavHistory)?;
+        let history = NavHistory::load(&ctx.program_id, history_info)?;
+        Ok(Some((history_info, history)))
+    }
+
+    /// Append the NAV just applied to `state` to its history, if it has one
+    pub(crate) fn record_nav(ctx: &ProgramContext, state: &OVTState) -> ProgramResult {
+        let Some((history_info, mut history)) = Self::load_nav_history(ctx, state)? else {
+            return Ok(());
+        };
+        history.push(NavSample::of(state));
+        history_info.set_data(&history)
+    }
+
+    /// Per-token NAV averaged over the history's TWAP window, or `None` when
+    /// mints and redemptions are priced at the spot NAV
+    pub(crate) fn twap_nav_per_token(ctx: &ProgramContext, state: &OVTState, now: u64) -> Result<Option<u64>, ProgramError> {
+        match Self::load_nav_history(ctx, state)? {
+            Some((_, history)) if history.twap_window_secs > 0 => {
+                history.twap_nav_per_token_sats(now, history.twap_window_secs)
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock_sdk::AccountMeta,
+        oracle::{OracleAccount, NAV_ASSET_ID},
+        test_fixtures::Fixture,
+        OVTInstruction,
+        MIN_NAV_UPDATE_INTERVAL_SECS,
+    };
+
+    fn sample(timestamp: u64, nav_sats: u64) -> NavSample {
+        NavSample { timestamp, nav_sats, total_supply: 1_000_000 }
+    }
+
+    #[test]
+    fn test_ring_buffer_and_twap() {
+        let mut history = NavHistory::new(3_600).unwrap();
+        assert!(NavHistory::new(MAX_TWAP_WINDOW_SECS + 1).is_err());
+        assert_eq!(history.twap_nav_sats(1_000, 3_600).unwrap(), None);
+
+        // A lone sample at `now` is its own average
+        history.push(sample(1_000, 1_000_000));
+        assert_eq!(history.twap_nav_sats(1_000, 3_600).unwrap(), Some(1_000_000));
+
+        // 1M for 600s then 2M for 1800s, inside a one-hour window
+        history.push(sample(1_600, 2_000_000));
+        assert_eq!(history.twap_nav_sats(3_400, 3_600).unwrap(), Some(1_750_000));
+        // A shorter window only sees the later sample
+        assert_eq!(history.twap_nav_sats(3_400, 1_800).unwrap(), Some(2_000_000));
+        // A sample taken right now has not held yet
+        history.push(sample(3_400, 4_000_000));
+        assert_eq!(history.twap_nav_sats(3_400, 3_600).unwrap(), Some(1_750_000));
+        assert_eq!(history.twap_nav_per_token_sats(3_400, 3_600).unwrap(), Some(175_000_000));
+
+        // Returns measure the per-token NAV in effect at each end
+        assert_eq!(history.return_bps(1_000, 1_600).unwrap(), Some(10_000));
+        assert_eq!(history.return_bps(1_600, 1_000).unwrap(), Some(-5_000));
+        assert_eq!(history.return_bps(999, 3_400).unwrap(), None);
+        assert_eq!(
+            history.period_returns_bps(3_400, 1_200, 3).unwrap(),
+            vec![None, Some(10_000), Some(10_000)],
+        );
+        assert!(history.period_returns_bps(3_400, 0, 1).is_err());
+
+        // Once full, the oldest samples are overwritten in order
+        for i in 0..NAV_HISTORY_CAPACITY as u64 {
+            history.push(sample(10_000 + i, 1_000 + i));
+        }
+        assert_eq!(history.samples.len(), NAV_HISTORY_CAPACITY);
+        assert_eq!(history.iter().next(), Some(&sample(10_000, 1_000)));
+        let latest = 10_000 + NAV_HISTORY_CAPACITY as u64 - 1;
+        assert_eq!(history.latest(), Some(&sample(latest, 1_000 + latest - 10_000)));
+        assert!(history.iter().zip(history.iter().skip(1)).all(|(a, b)| a.timestamp < b.timestamp));
+        assert!(borsh::object_length(&history).unwrap() <= NavHistory::MAX_SPACE);
+    }
+
+    #[test]
+    fn test_nav_history_pricing() {
+        let mut fixture = Fixture::new();
+        let history = fixture.create_system_account();
+        let oracle_account = fixture.client.create_account(fixture.oracle_program).unwrap().key;
+        let utxo_account = fixture.create_account();
+        let holder = fixture.create_signer();
+        let holder_account = fixture.token_account(holder);
+
+        let update_nav = |fixture: &mut Fixture, nav_sats, with_history: bool| {
+            fixture.client.set_account_data(&oracle_account, &OracleAccount {
+                asset_id: NAV_ASSET_ID,
+                price_sats: nav_sats,
+                publish_time: fixture.client.clock().unix_timestamp,
+                publisher: fixture.oracle_publisher,
+                confidence_sats: 0,
+            }).unwrap();
+            let mut accounts = vec![
+                AccountMeta::new(fixture.state, true),
+                AccountMeta::new_readonly(fixture.authority(), true),
+                AccountMeta::new_readonly(oracle_account, false),
+            ];
+            if with_history {
+                accounts.push(AccountMeta::new(history, true));
+            }
+            fixture.process(accounts, &OVTInstruction::UpdateNAV { btc_price_sats: nav_sats })
+        };
+        let mint = |fixture: &mut Fixture, payment_vout| {
+            let accounts = vec![
+                AccountMeta::new(fixture.state, true),
+                AccountMeta::new_readonly(fixture.authority(), true),
+                AccountMeta::new_readonly(utxo_account, false),
+                AccountMeta::new(fixture.payment_marker(Fixture::PAYMENT_TXID, payment_vout), true),
+                AccountMeta::new_readonly(fixture.system_program, false),
+                AccountMeta::new(holder_account, true),
+                AccountMeta::new_readonly(history, false),
+            ];
+            fixture.process(accounts, &OVTInstruction::Mint {
+                payment_txid: Fixture::PAYMENT_TXID.to_string(),
+                payment_vout,
+                payment_amount_sats: 10_000,
+                recipient: holder,
+            })
+        };
+
+        let mut state = fixture.state();
+        state.total_supply = 1_000_000;
+        fixture.set_state(&state);
+        fixture.set_balance(&holder_account, 1_000_000);
+        update_nav(&mut fixture, 1_000_000, false).unwrap();
+
+        let init_accounts = vec![
+            AccountMeta::new(fixture.state, true),
+            AccountMeta::new(history, true),
+            AccountMeta::new_readonly(fixture.authority(), true),
+            AccountMeta::new_readonly(fixture.system_program, false),
+        ];
+        let init = OVTInstruction::InitializeNavHistory { twap_window_secs: 3_600 };
+        let err = fixture.process_with_signers(init_accounts.clone(), 2, &init).unwrap_err();
+        assert_eq!(err.to_string(), "Missing required signature");
+
+        // The history goes into a fresh account, not over an existing one
+        let mut taken_accounts = init_accounts.clone();
+        taken_accounts[1] = AccountMeta::new(holder_account, true);
+        let err = fixture.process(taken_accounts, &init).unwrap_err();
+        assert_eq!(err.to_string(), "AccountAlreadyInitialized");
+        assert_eq!(fixture.balance(&holder_account), 1_000_000);
+
+        fixture.process(init_accounts.clone(), &init).unwrap();
+        assert_eq!(fixture.state().nav_history, history);
+        let err = fixture.process(init_accounts, &init).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidNavHistory");
+
+        // The current NAV seeds the history, and every update appends to it
+        fixture.client.advance_clock(1_800);
+        let err = update_nav(&mut fixture, 1_200_000, false).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidNavHistory");
+        update_nav(&mut fixture, 1_200_000, true).unwrap();
+        let recorded: NavHistory = fixture.client.get_account_data(&history).unwrap();
+        let now = fixture.client.clock().unix_timestamp;
+        assert_eq!(recorded.iter().copied().collect::<Vec<_>>(), vec![
+            NavSample { timestamp: now - 1_800, nav_sats: 1_000_000, total_supply: 1_000_000 },
+            NavSample { timestamp: now, nav_sats: 1_200_000, total_supply: 1_000_000 },
+        ]);
+
+        // Just after the mark-up, mints pay the spot NAV rather than the lower average
+        fixture.client.advance_clock(MIN_NAV_UPDATE_INTERVAL_SECS);
+        mint(&mut fixture, 0).unwrap();
+        assert_eq!(fixture.state().total_supply, 1_000_000 + 8_333);
+
+        // ...while redemptions pay the lower average
+        let obligation = fixture.create_system_account();
+        let redeem_accounts = vec![
+            AccountMeta::new(fixture.state, true),
+            AccountMeta::new(obligation, true),
+            AccountMeta::new_readonly(holder, true),
+            AccountMeta::new_readonly(fixture.authority(), true),
+            AccountMeta::new_readonly(fixture.system_program, false),
+            AccountMeta::new(holder_account, true),
+            AccountMeta::new_readonly(history, false),
+        ];
+        let twap = recorded_twap(&fixture, history);
+        assert!(twap < fixture.state().nav_per_token_sats().unwrap());
+        let events = fixture.process(redeem_accounts, &OVTInstruction::Redeem {
+            amount: 10_000,
+            btc_address: "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4".to_string(),
+        }).unwrap();
+        let expected = 10_000 * twap / 100_000_000;
+        assert!(matches!(events.as_slice(), [OVTEvent::Redeemed { payout_sats, .. }] if *payout_sats == expected));
+
+        // After a mark-down, mints pay the higher average
+        update_nav(&mut fixture, 1_000_000, true).unwrap();
+        fixture.client.advance_clock(MIN_NAV_UPDATE_INTERVAL_SECS);
+        let state = fixture.state();
+        let twap = recorded_twap(&fixture, history);
+        assert!(twap > state.nav_per_token_sats().unwrap());
+        mint(&mut fixture, 1).unwrap();
+        assert_eq!(fixture.state().total_supply, state.total_supply + 10_000 * 100_000_000 / twap);
+
+        // Spot pricing again once the window is cleared
+        let window_accounts = vec![
+            AccountMeta::new(fixture.state, true),
+            AccountMeta::new(history, true),
+        ];
+        fixture.process(window_accounts, &OVTInstruction::SetTwapWindow { twap_window_secs: 0 }).unwrap();
+        let state = fixture.state();
+        mint(&mut fixture, 2).unwrap();
+        assert_eq!(fixture.state().total_supply, state.total_supply + state.ovt_for_sats(10_000).unwrap());
+    }
+
+    fn recorded_twap(fixture: &Fixture, history: Pubkey) -> u64 {
+        let recorded: NavHistory = fixture.client.get_account_data(&history).unwrap();
+        let now = fixture.client.clock().unix_timestamp;
+        recorded.twap_nav_per_token_sats(now, recorded.twap_window_secs).unwrap().unwrap()
+    }
+}
@@ -0,0 +1,192 @@
+use crate::mock_sdk::{
+    AccountInfo,
+    Pubkey,
+    ProgramContext,
+    ProgramResult,
+    ProgramError,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use crate::{
+    error::OVTError,
+    events::OVTEvent,
+    msg,
+    OVTProgram,
+    OVTState,
+};
+
+/// Asset id of the feed publishing the fund NAV in satoshis
+pub const NAV_ASSET_ID: [u8; 32] = *b"OVT/NAV\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0";
+/// Oldest price accepted, in seconds
+pub const MAX_PRICE_AGE_SECS: u64 = 3_600;
+/// Widest confidence interval accepted, in basis points of the price
+pub const MAX_CONFIDENCE_BPS: u64 = 200;
+
+/// Price published by the oracle program for a single asset
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct OracleAccount {
+    /// Asset the price refers to
+    pub asset_id: [u8; 32],
+    /// Price in satoshis
+    pub price_sats: u64,
+    /// Unix timestamp the price was published
+    pub publish_time: u64,
+    /// Key of the publisher that signed the price
+    pub publisher: Pubkey,
+    /// Half-width of the confidence interval in satoshis
+    pub confidence_sats: u64,
+}
+
+impl OracleAccount {
+    /// Read a price for `asset_id` from `oracle_info`, rejecting accounts not
+    /// owned by `oracle_program_id`, prices not signed by `publisher`, stale
+    /// prices and wide confidence intervals
+    pub fn load(
+        oracle_info: &AccountInfo,
+        oracle_program_id: &Pubkey,
+        publisher: &Pubkey,
+        asset_id: &[u8; 32],
+        now: u64,
+    ) -> Result<Self, ProgramError> {
+        if *oracle_info.owner.borrow() != *oracle_program_id {
+            return Err(OVTError::InvalidOracleAccount.into());
+        }
+        let oracle: OracleAccount = oracle_info.get_data()
+            .map_err(|_| ProgramError::from(OVTError::InvalidOracleAccount))?;
+        if oracle.asset_id != *asset_id {
+            return Err(OVTError::InvalidOracleAccount.into());
+        }
+        // A migrated fund has no publisher until the admins set one
+        if *publisher == Pubkey::new() || oracle.publisher != *publisher {
+            msg!("Oracle price published by {:?}, expected {:?}", oracle.publisher, publisher);
+            return Err(OVTError::InvalidOracleAccount.into());
+        }
+        oracle.validate(now)?;
+        Ok(oracle)
+    }
+
+    pub fn validate(&self, now: u64) -> Result<(), ProgramError> {
+        if self.price_sats == 0 {
+            return Err(OVTError::InvalidOracleAccount.into());
+        }
+        // Prices from the future are as untrustworthy as stale ones
+        if self.publish_time > now || now - self.publish_time > MAX_PRICE_AGE_SECS {
+            return Err(OVTError::InvalidOracleAccount.into());
+        }
+        if (self.confidence_sats as u128) * 10_000 > (self.price_sats as u128) * (MAX_CONFIDENCE_BPS as u128) {
+            return Err(OVTError::InvalidOracleAccount.into());
+        }
+        Ok(())
+    }
+}
+
+impl OVTProgram {
+    /// Change the key NAV prices must be signed by. Requires the admin quorum
+    /// and the timelock.
+    pub(crate) fn process_set_oracle_publisher(ctx: &ProgramContext, publisher: Pubkey) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+
+        let mut state: OVTState = state_info.get_data()?;
+        Self::verify_admin_quorum(ctx, &state)?;
+        if publisher == Pubkey::new() {
+            return Err(OVTError::InvalidOracleAccount.into());
+        }
+
+        let previous_publisher = state.oracle_publisher;
+        state.oracle_publisher = publisher;
+        ctx.emit(&OVTEvent::OraclePublisherSet { previous_publisher, publisher })?;
+        state_info.set_data(&state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock_sdk::AccountMeta, test_fixtures::Fixture, OVTInstruction};
+
+    const PUBLISHER: Pubkey = Pubkey([5u8; 32]);
+
+    fn price(publish_time: u64, confidence_sats: u64) -> OracleAccount {
+        OracleAccount {
+            asset_id: NAV_ASSET_ID,
+            price_sats: 1_000_000,
+            publish_time,
+            publisher: PUBLISHER,
+            confidence_sats,
+        }
+    }
+
+    #[test]
+    fn test_oracle_validation() {
+        let now = 1_700_000_000;
+        assert!(price(now, 0).validate(now).is_ok());
+        assert!(price(now - MAX_PRICE_AGE_SECS, 20_000).validate(now).is_ok());
+
+        // Stale, future-dated and wide-confidence prices
+        assert!(price(now - MAX_PRICE_AGE_SECS - 1, 0).validate(now).is_err());
+        assert!(price(now + 1, 0).validate(now).is_err());
+        assert!(price(now, 20_001).validate(now).is_err());
+    }
+
+    #[test]
+    fn test_oracle_ownership() {
+        let oracle_program = Pubkey::new_unique();
+        let now = 1_700_000_000;
+        let oracle_info = AccountInfo::new(Pubkey::new_unique(), false, true);
+        oracle_info.set_data(&price(now, 0)).unwrap();
+
+        // Owned by the wrong program
+        let err = OracleAccount::load(&oracle_info, &oracle_program, &PUBLISHER, &NAV_ASSET_ID, now).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidOracleAccount");
+
+        *oracle_info.owner.borrow_mut() = oracle_program;
+        assert!(OracleAccount::load(&oracle_info, &oracle_program, &PUBLISHER, &NAV_ASSET_ID, now).is_ok());
+        assert!(OracleAccount::load(&oracle_info, &oracle_program, &PUBLISHER, &[0u8; 32], now).is_err());
+
+        // Signed by someone else, or checked against no publisher at all
+        let err = OracleAccount::load(&oracle_info, &oracle_program, &Pubkey::new_unique(), &NAV_ASSET_ID, now)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "InvalidOracleAccount");
+        oracle_info.set_data(&OracleAccount { publisher: Pubkey::new(), ..price(now, 0) }).unwrap();
+        let err = OracleAccount::load(&oracle_info, &oracle_program, &Pubkey::new(), &NAV_ASSET_ID, now).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidOracleAccount");
+    }
+
+    #[test]
+    fn test_set_oracle_publisher() {
+        let mut fixture = Fixture::new();
+        let oracle_account = fixture.client.create_account(fixture.oracle_program).unwrap().key;
+        let update_nav = |fixture: &mut Fixture, publisher: Pubkey| {
+            let now = fixture.client.clock().unix_timestamp;
+            fixture.client.set_account_data(&oracle_account, &OracleAccount { publisher, ..price(now, 0) }).unwrap();
+            let accounts = vec![
+                AccountMeta::new(fixture.state, true),
+                AccountMeta::new_readonly(fixture.authority(), true),
+                AccountMeta::new_readonly(oracle_account, false),
+            ];
+            fixture.process(accounts, &OVTInstruction::UpdateNAV { btc_price_sats: 1_000_000 })
+        };
+
+        // Only the publisher the fund was initialized with is trusted
+        let err = update_nav(&mut fixture, PUBLISHER).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidOracleAccount");
+
+        // Moving to another publisher waits out the timelock
+        let mut accounts = vec![AccountMeta::new(fixture.state, true)];
+        let instruction = OVTInstruction::SetOraclePublisher { publisher: PUBLISHER };
+        let err = fixture.process(accounts.clone(), &instruction).unwrap_err();
+        assert_eq!(err.to_string(), "TimelockRequired");
+        accounts.extend(fixture.signers(Fixture::THRESHOLD as usize));
+        let approvers = fixture.admins[..3].to_vec();
+        let events = fixture.process_timelocked(accounts, &instruction, &approvers).unwrap();
+        assert_eq!(events[0], OVTEvent::OraclePublisherSet {
+            previous_publisher: fixture.oracle_publisher,
+            publisher: PUBLISHER,
+        });
+
+        let previous_publisher = fixture.oracle_publisher;
+        let err = update_nav(&mut fixture, previous_publisher).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidOracleAccount");
+        update_nav(&mut fixture, PUBLISHER).unwrap();
+        assert_eq!(fixture.state().nav_sats, 1_000_000);
+    }
+}
@@ -0,0 +1,166 @@
+use crate::mock_sdk::{
+    ProgramContext,
+    ProgramResult,
+    ProgramError,
+};
+use crate::{
+    events::OVTEvent,
+    msg,
+    OVTInstruction,
+    OVTProgram,
+    OVTState,
+};
+
+/// Instruction classes that can be paused, as bits of `OVTState::paused`
+pub const PAUSE_MINT: u8 = 1 << 0;
+pub const PAUSE_BURN: u8 = 1 << 1;
+pub const PAUSE_REDEEM: u8 = 1 << 2;
+pub const PAUSE_NAV: u8 = 1 << 3;
+pub const PAUSE_ALL: u8 = PAUSE_MINT | PAUSE_BURN | PAUSE_REDEEM | PAUSE_NAV;
+
+/// Pause class of `instruction`, zero for instructions that cannot be paused
+pub fn pause_class(instruction: &OVTInstruction) -> u8 {
+    match instruction {
+        // Fee accrual mints to the fee recipient, and changing the fees accrues
+        OVTInstruction::Mint { .. }
+        | OVTInstruction::ConvertSAFE { .. }
+        | OVTInstruction::AccrueFees
+        | OVTInstruction::SetFeeParams { .. } => PAUSE_MINT,
+        OVTInstruction::BuybackBurn { .. } => PAUSE_BURN,
+        OVTInstruction::Redeem { .. } | OVTInstruction::ClaimDistribution { .. } => PAUSE_REDEEM,
+        OVTInstruction::UpdateNAV { .. } | OVTInstruction::RecomputeNAV { .. } => PAUSE_NAV,
+        _ => 0,
+    }
+}
+
+fn validate_classes(classes: u8) -> ProgramResult {
+    if classes == 0 || classes & !PAUSE_ALL != 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(())
+}
+
+impl OVTProgram {
+    /// Pause `classes`. The guardian quorum is enough, so an incident can be
+    /// contained without waiting for the admins. Redemptions are the holders'
+    /// exit from the admins, so the admins alone cannot pause them.
+    pub(crate) fn process_pause(ctx: &ProgramContext, classes: u8) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+
+        let mut state: OVTState = state_info.get_data()?;
+        validate_classes(classes)?;
+
+        let (_, config) = Self::load_admin_config(ctx, &state)?;
+        if !config.timelock.has_guardian_quorum(&ctx.accounts) {
+            if classes & PAUSE_REDEEM != 0 {
+                msg!("Only the guardians can pause redemptions");
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            Self::verify_admin_quorum(ctx, &state)?;
+        }
+
+        state.paused |= classes;
+        ctx.emit(&OVTEvent::Paused { classes, paused: state.paused })?;
+        state_info.set_data(&state)
+    }
+
+    /// Resume `classes`. Requires the admin quorum.
+    pub(crate) fn process_unpause(ctx: &ProgramContext, classes: u8) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+
+        let mut state: OVTState = state_info.get_data()?;
+        validate_classes(classes)?;
+        Self::verify_admin_quorum(ctx, &state)?;
+
+        state.paused &= !classes;
+        ctx.emit(&OVTEvent::Unpaused { classes, paused: state.paused })?;
+        state_info.set_data(&state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock_sdk::{AccountMeta, Pubkey},
+        test_fixtures::Fixture,
+    };
+
+    #[test]
+    fn test_pause_and_unpause() {
+        let mut fixture = Fixture::new();
+        let utxo_account = fixture.create_account();
+        let recipient = Pubkey::new_unique();
+        let mint_accounts = vec![
+            AccountMeta::new(fixture.state, true),
+            AccountMeta::new_readonly(fixture.authority(), true),
+            AccountMeta::new_readonly(utxo_account, false),
+            AccountMeta::new(fixture.payment_marker(Fixture::PAYMENT_TXID, 0), true),
+            AccountMeta::new_readonly(fixture.system_program, false),
+            AccountMeta::new(fixture.token_account(recipient), true),
+        ];
+        let mint = OVTInstruction::Mint {
+            payment_txid: Fixture::PAYMENT_TXID.to_string(),
+            payment_vout: 0,
+            payment_amount_sats: 100_000,
+            recipient,
+        };
+        let with_signers = |fixture: &Fixture, signers: &[Pubkey]| {
+            let mut accounts = vec![
+                AccountMeta::new(fixture.state, true),
+                AccountMeta::new_readonly(fixture.admin_config, false),
+            ];
+            accounts.extend(signers.iter().map(|key| AccountMeta::new_readonly(*key, true)));
+            accounts
+        };
+        let guardians = fixture.guardians.clone();
+
+        // One guardian is not enough, two are
+        let accounts = with_signers(&fixture, &guardians[..1]);
+        let err = fixture.process_raw(accounts, &OVTInstruction::Pause { classes: PAUSE_MINT }).unwrap_err();
+        assert_eq!(err.to_string(), "Missing required signature");
+        let accounts = with_signers(&fixture, &guardians[..2]);
+        fixture.process_raw(accounts, &OVTInstruction::Pause { classes: PAUSE_MINT }).unwrap();
+        assert_eq!(fixture.state().paused, PAUSE_MINT);
+
+        let err = fixture.process(mint_accounts.clone(), &mint).unwrap_err();
+        assert_eq!(err.to_string(), "InstructionPaused");
+
+        // Paused classes cannot be reached through a proposal either
+        let mut proposal_accounts = mint_accounts.clone();
+        proposal_accounts.push(AccountMeta::new_readonly(fixture.admin_config, false));
+        let approvers = fixture.admins[..3].to_vec();
+        let err = fixture.process_timelocked(proposal_accounts, &mint, &approvers).unwrap_err();
+        assert_eq!(err.to_string(), "InstructionPaused");
+
+        // The admin quorum can pause too, and unknown classes are refused
+        fixture.process(vec![AccountMeta::new(fixture.state, true)], &OVTInstruction::Pause { classes: PAUSE_NAV })
+            .unwrap();
+        assert_eq!(fixture.state().paused, PAUSE_MINT | PAUSE_NAV);
+        assert!(fixture.process(vec![AccountMeta::new(fixture.state, true)], &OVTInstruction::Pause { classes: 1 << 7 })
+            .is_err());
+        assert!(fixture.process(vec![AccountMeta::new(fixture.state, true)], &OVTInstruction::Pause { classes: 0 })
+            .is_err());
+
+        // but only the guardians can pause redemptions
+        let state_accounts = vec![AccountMeta::new(fixture.state, true)];
+        let err = fixture.process(state_accounts, &OVTInstruction::Pause { classes: PAUSE_ALL }).unwrap_err();
+        assert_eq!(err.to_string(), "Missing required signature");
+        assert_eq!(fixture.state().paused, PAUSE_MINT | PAUSE_NAV);
+        let accounts = with_signers(&fixture, &guardians[..2]);
+        fixture.process_raw(accounts, &OVTInstruction::Pause { classes: PAUSE_REDEEM }).unwrap();
+        assert_eq!(fixture.state().paused, PAUSE_MINT | PAUSE_NAV | PAUSE_REDEEM);
+        fixture.process(vec![AccountMeta::new(fixture.state, true)], &OVTInstruction::Unpause { classes: PAUSE_REDEEM })
+            .unwrap();
+
+        // Guardians cannot unpause
+        let accounts = with_signers(&fixture, &guardians);
+        let err = fixture.process_raw(accounts, &OVTInstruction::Unpause { classes: PAUSE_MINT }).unwrap_err();
+        assert_eq!(err.to_string(), "Missing required signature");
+
+        fixture.process(vec![AccountMeta::new(fixture.state, true)], &OVTInstruction::Unpause { classes: PAUSE_MINT })
+            .unwrap();
+        assert_eq!(fixture.state().paused, PAUSE_NAV);
+        fixture.process(mint_accounts, &mint).unwrap();
+    }
+}
@@ -0,0 +1,150 @@
+use crate::mock_sdk::{
+    AccountInfo,
+    Pubkey,
+    ProgramContext,
+    ProgramResult,
+    ProgramError,
+};
+use bitcoin::{hashes::Hash, Txid};
+use borsh::{BorshDeserialize, BorshSerialize};
+use std::str::FromStr;
+use crate::{
+    error::OVTError,
+    msg,
+    system::{create_program_account, initialize_account},
+    OVTProgram,
+};
+
+/// Seed prefix of payment marker addresses
+pub const PAYMENT_MARKER_SEED: &[u8] = b"payment";
+
+/// Records that a BTC output was consumed by a mint, burn or settlement, so
+/// it cannot back a second one. Lives at `payment_marker_address`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct PaymentMarker {
+    pub txid: [u8; 32],
+    pub vout: u32,
+    /// When the output was consumed
+    pub consumed_at: u64,
+}
+
+/// Parse a transaction id in its usual hex form
+pub fn parse_txid(txid: &str) -> Result<Txid, ProgramError> {
+    Txid::from_str(txid).map_err(|_| OVTError::InvalidBitcoinPayment.into())
+}
+
+/// Marker address for output `vout` of `txid`. Derived from the parsed txid,
+/// so differently written forms of the same id share one marker.
+pub fn payment_marker_address(program_id: &Pubkey, txid: &Txid, vout: u32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[PAYMENT_MARKER_SEED, txid.as_byte_array(), &vout.to_le_bytes()],
+        program_id,
+    ).0
+}
+
+impl OVTProgram {
+    /// Create the marker for output `vout` of `payment_txid`, refusing an
+    /// output that has already been consumed
+    pub(crate) fn consume_payment(
+        ctx: &ProgramContext,
+        marker_info: &AccountInfo,
+        payer_info: &AccountInfo,
+        system_program: &AccountInfo,
+        payment_txid: &str,
+        vout: u32,
+    ) -> ProgramResult {
+        let txid = parse_txid(payment_txid)?;
+        if marker_info.key != payment_marker_address(&ctx.program_id, &txid, vout) {
+            return Err(ProgramError::InvalidArgument);
+        }
+        if *marker_info.owner.borrow() == ctx.program_id && marker_info.get_data::<PaymentMarker>().is_ok() {
+            msg!("Payment {}:{} already processed", txid, vout);
+            return Err(OVTError::PaymentAlreadyProcessed.into());
+        }
+
+        let marker = PaymentMarker {
+            txid: txid.to_byte_array(),
+            vout,
+            consumed_at: ctx.clock()?.unix_timestamp,
+        };
+        create_program_account(
+            &ctx.program_id,
+            marker_info,
+            payer_info,
+            borsh::object_length(&marker)? as u64,
+            system_program,
+        )?;
+        initialize_account(&ctx.program_id, marker_info, &marker)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock_sdk::AccountMeta, test_fixtures::Fixture, OVTInstruction};
+
+    #[test]
+    fn test_payment_replay_protection() {
+        let mut fixture = Fixture::new();
+        let utxo_account = fixture.create_account();
+        let mut state = fixture.state();
+        state.total_supply = 1_000_000;
+        state.nav_sats = 1_000_000;
+        fixture.set_state(&state);
+        let authority = fixture.authority();
+        let holder_account = fixture.token_account(authority);
+        fixture.set_balance(&holder_account, 1_000_000);
+
+        let mint = |fixture: &mut Fixture, marker: Pubkey, payment_txid: &str, payment_vout| {
+            let accounts = vec![
+                AccountMeta::new(fixture.state, true),
+                AccountMeta::new_readonly(fixture.authority(), true),
+                AccountMeta::new_readonly(utxo_account, false),
+                AccountMeta::new(marker, true),
+                AccountMeta::new_readonly(fixture.system_program, false),
+                AccountMeta::new(holder_account, true),
+            ];
+            fixture.process(accounts, &OVTInstruction::Mint {
+                payment_txid: payment_txid.to_string(),
+                payment_vout,
+                payment_amount_sats: 10_000,
+                recipient: authority,
+            })
+        };
+
+        // Txids must parse, and the marker must be the one derived for the output
+        let marker = fixture.payment_marker(Fixture::PAYMENT_TXID, 0);
+        let err = mint(&mut fixture, marker, "test_txid", 0).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidBitcoinPayment");
+        let other = fixture.payment_marker(Fixture::PAYMENT_TXID, 1);
+        assert!(mint(&mut fixture, other, Fixture::PAYMENT_TXID, 0).is_err());
+
+        mint(&mut fixture, marker, Fixture::PAYMENT_TXID, 0).unwrap();
+        let recorded: PaymentMarker = fixture.client.get_account_data(&marker).unwrap();
+        assert_eq!(recorded.txid, parse_txid(Fixture::PAYMENT_TXID).unwrap().to_byte_array());
+        assert_eq!(recorded.vout, 0);
+        assert_eq!(recorded.consumed_at, fixture.client.clock().unix_timestamp);
+
+        // The same output is refused however the txid is written, and by
+        // the other payment instructions too
+        let err = mint(&mut fixture, marker, &Fixture::PAYMENT_TXID.to_uppercase(), 0).unwrap_err();
+        assert_eq!(err.to_string(), "PaymentAlreadyProcessed");
+        let mut burn_accounts = vec![
+            AccountMeta::new(fixture.state, true),
+            AccountMeta::new_readonly(fixture.authority(), true),
+            AccountMeta::new(marker, true),
+            AccountMeta::new_readonly(fixture.system_program, false),
+            AccountMeta::new(holder_account, true),
+        ];
+        burn_accounts.extend(fixture.signers(Fixture::THRESHOLD as usize));
+        let err = fixture.process_raw(burn_accounts, &OVTInstruction::BuybackBurn {
+            payment_txid: Fixture::PAYMENT_TXID.to_string(),
+            payment_vout: 0,
+            payment_amount_sats: 10_000,
+        }).unwrap_err();
+        assert_eq!(err.to_string(), "PaymentAlreadyProcessed");
+
+        // Other outputs of the same transaction are separate payments
+        mint(&mut fixture, other, Fixture::PAYMENT_TXID, 1).unwrap();
+    }
+}
@@ -0,0 +1,291 @@
+use crate::mock_sdk::{
+    AccountInfo,
+    Pubkey,
+    ProgramContext,
+    ProgramResult,
+    ProgramError,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use crate::{
+    error::OVTError,
+    events::OVTEvent,
+    runes_client::{PositionStatus, PositionType},
+    system::{create_program_account, initialize_account, verify_uninitialized},
+    OVTProgram,
+    OVTState,
+};
+
+/// Portfolio position held by the fund, stored in its own program account
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct PositionData {
+    /// State account of the fund holding the position
+    pub state: Pubkey,
+    /// Project or asset name
+    pub name: String,
+    /// Number of tokens held
+    pub amount: u64,
+    /// Price per token in satoshis
+    pub price_per_token: u64,
+    /// Satoshis spent acquiring the position
+    pub currency_spent: u64,
+    pub position_type: PositionType,
+    pub status: PositionStatus,
+    /// Unix timestamp the position was entered
+    pub entry_timestamp: u64,
+}
+
+impl PositionData {
+    /// Maximum length of a position name in bytes
+    pub const MAX_NAME_LEN: usize = 64;
+
+    pub fn validate(&self) -> ProgramResult {
+        if self.name.is_empty() || self.name.len() > Self::MAX_NAME_LEN {
+            return Err(OVTError::InvalidPosition.into());
+        }
+        Ok(())
+    }
+
+    /// Value of the position at its current mark price, in satoshis
+    pub fn market_value(&self) -> Result<u64, ProgramError> {
+        self.amount
+            .checked_mul(self.price_per_token)
+            .ok_or_else(|| OVTError::ArithmeticOverflow.into())
+    }
+
+    /// Load the position stored in `position_info`, checking it belongs to
+    /// this program and is held by the fund whose state is `state`
+    pub fn load(program_id: &Pubkey, state: &Pubkey, position_info: &AccountInfo) -> Result<Self, ProgramError> {
+        if *position_info.owner.borrow() != *program_id {
+            return Err(OVTError::PositionNotFound.into());
+        }
+        let position: Self = position_info.get_data()
+            .map_err(|_| OVTError::PositionNotFound)?;
+        if position.state != *state {
+            return Err(OVTError::PositionNotFound.into());
+        }
+        Ok(position)
+    }
+}
+
+/// Fund NAV: marked value of the open positions plus the treasury BTC
+/// balance, minus liabilities
+pub fn compute_nav(
+    positions: &[PositionData],
+    treasury_balance_sats: u64,
+    liabilities_sats: u64,
+) -> Result<u64, ProgramError> {
+    let mut gross = treasury_balance_sats;
+    for position in positions {
+        gross = gross
+            .checked_add(position.market_value()?)
+            .ok_or(OVTError::ArithmeticOverflow)?;
+    }
+    gross
+        .checked_sub(liabilities_sats)
+        .ok_or_else(|| OVTError::InvalidNAVUpdate.into())
+}
+
+impl OVTProgram {
+    pub(crate) fn process_add_position(ctx: &ProgramContext, position: PositionData) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+        let position_info = ctx.get(1)?;
+        let authority_info = ctx.get(2)?;
+        let system_program = ctx.get(3)?;
+
+        let mut state: OVTState = state_info.get_data()?;
+        Self::verify_admin_quorum(ctx, &state)?;
+        position.validate()?;
+        if position.state != state_info.key {
+            return Err(OVTError::InvalidPosition.into());
+        }
+
+        // Refuse to overwrite an existing position or any other account
+        verify_uninitialized(position_info)?;
+        if position.status == PositionStatus::Exited {
+            return Err(OVTError::PositionAlreadyExited.into());
+        }
+
+        create_program_account(
+            &ctx.program_id,
+            position_info,
+            authority_info,
+            borsh::object_length(&position)? as u64,
+            system_program,
+        )?;
+        initialize_account(&ctx.program_id, position_info, &position)?;
+
+        state.position_count = state.position_count
+            .checked_add(1)
+            .ok_or(OVTError::ArithmeticOverflow)?;
+        state_info.set_data(&state)?;
+
+        ctx.emit(&OVTEvent::PositionAdded {
+            position: position_info.key,
+            name: position.name,
+            amount: position.amount,
+        })
+    }
+
+    pub(crate) fn process_update_position(
+        ctx: &ProgramContext,
+        amount: u64,
+        price_per_token: u64,
+        status: PositionStatus,
+    ) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+        let position_info = ctx.get(1)?;
+
+        let state: OVTState = state_info.get_data()?;
+        Self::verify_admin_quorum(ctx, &state)?;
+
+        let mut position = PositionData::load(&ctx.program_id, &state_info.key, position_info)?;
+        if position.status == PositionStatus::Exited {
+            return Err(OVTError::PositionAlreadyExited.into());
+        }
+        // Exits go through ExitPosition
+        if status == PositionStatus::Exited {
+            return Err(OVTError::InvalidPosition.into());
+        }
+
+        position.amount = amount;
+        position.price_per_token = price_per_token;
+        position.status = status;
+        position_info.set_data(&position)?;
+
+        ctx.emit(&OVTEvent::PositionUpdated {
+            position: position_info.key,
+            amount,
+            price_per_token,
+            status,
+        })
+    }
+
+    pub(crate) fn process_exit_position(ctx: &ProgramContext) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+        let position_info = ctx.get(1)?;
+
+        let mut state: OVTState = state_info.get_data()?;
+        Self::verify_admin_quorum(ctx, &state)?;
+
+        let mut position = PositionData::load(&ctx.program_id, &state_info.key, position_info)?;
+        if position.status == PositionStatus::Exited {
+            return Err(OVTError::PositionAlreadyExited.into());
+        }
+
+        position.status = PositionStatus::Exited;
+        position_info.set_data(&position)?;
+
+        state.position_count = state.position_count
+            .checked_sub(1)
+            .ok_or(OVTError::ArithmeticOverflow)?;
+        state_info.set_data(&state)?;
+
+        ctx.emit(&OVTEvent::PositionExited {
+            position: position_info.key,
+            name: position.name,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock_sdk::AccountMeta, test_fixtures::Fixture, OVTInstruction};
+
+    #[test]
+    fn test_position_lifecycle() {
+        let mut fixture = Fixture::new();
+        let position_account = fixture.create_system_account();
+
+        let position = PositionData {
+            state: fixture.state,
+            name: "Test Project".to_string(),
+            amount: 1_000_000,
+            price_per_token: 100,
+            currency_spent: 100_000_000,
+            position_type: PositionType::PostTGE,
+            status: PositionStatus::Active,
+            entry_timestamp: 1677649200,
+        };
+
+        let instruction = OVTInstruction::AddPosition { position: position.clone() };
+        let (state, authority, system_program) = (fixture.state, fixture.authority(), fixture.system_program);
+        let add_accounts = move |position_account| vec![
+            AccountMeta::new(state, true),
+            AccountMeta::new(position_account, true),
+            AccountMeta::new_readonly(authority, true),
+            AccountMeta::new_readonly(system_program, false),
+        ];
+        fixture.process(add_accounts(position_account), &instruction).unwrap();
+        assert_eq!(fixture.state().position_count, 1);
+
+        // Neither the position nor another account of the program can be
+        // written over
+        let err = fixture.process(add_accounts(position_account), &instruction).unwrap_err();
+        assert_eq!(err.to_string(), "AccountAlreadyInitialized");
+        let token_account = fixture.token_account(Pubkey::new_unique());
+        let err = fixture.process(add_accounts(token_account), &instruction).unwrap_err();
+        assert_eq!(err.to_string(), "AccountAlreadyInitialized");
+
+        let accounts = vec![
+            AccountMeta::new_readonly(fixture.state, false),
+            AccountMeta::new(position_account, true),
+        ];
+
+        let instruction = OVTInstruction::UpdatePosition {
+            amount: 800_000,
+            price_per_token: 150,
+            status: PositionStatus::Active,
+        };
+        let events = fixture.process(accounts.clone(), &instruction).unwrap();
+        assert_eq!(events, vec![OVTEvent::PositionUpdated {
+            position: position_account,
+            amount: 800_000,
+            price_per_token: 150,
+            status: PositionStatus::Active,
+        }]);
+
+        let stored: PositionData = fixture.client.get_account_data(&position_account).unwrap();
+        assert_eq!(stored.amount, 800_000);
+        assert_eq!(stored.price_per_token, 150);
+        assert_eq!(stored.currency_spent, position.currency_spent);
+        assert_eq!(stored.market_value().unwrap(), 120_000_000);
+
+        let exit_accounts = vec![
+            AccountMeta::new(fixture.state, true),
+            AccountMeta::new(position_account, true),
+        ];
+        let err = fixture.process_with_signers(exit_accounts.clone(), 2, &OVTInstruction::ExitPosition).unwrap_err();
+        assert_eq!(err.to_string(), "Missing required signature");
+        fixture.process(exit_accounts, &OVTInstruction::ExitPosition).unwrap();
+
+        let stored: PositionData = fixture.client.get_account_data(&position_account).unwrap();
+        assert_eq!(stored.status, PositionStatus::Exited);
+        assert_eq!(fixture.state().position_count, 0);
+
+        // Exited positions are frozen
+        let err = fixture.process(accounts, &instruction).unwrap_err();
+        assert_eq!(err.to_string(), "PositionAlreadyExited");
+    }
+
+    #[test]
+    fn test_compute_nav() {
+        let position = |amount, price_per_token| PositionData {
+            state: Pubkey::default(),
+            name: "P".to_string(),
+            amount,
+            price_per_token,
+            currency_spent: 0,
+            position_type: PositionType::PostTGE,
+            status: PositionStatus::Active,
+            entry_timestamp: 0,
+        };
+        let positions = vec![position(1_000, 200), position(50, 10)];
+
+        assert_eq!(compute_nav(&positions, 1_000_000, 100_500).unwrap(), 1_100_000);
+        assert_eq!(compute_nav(&[], 0, 0).unwrap(), 0);
+        // Liabilities exceeding assets cannot produce a NAV
+        assert!(compute_nav(&positions, 0, 200_501).is_err());
+        assert!(compute_nav(&[position(u64::MAX, 2)], 0, 0).is_err());
+    }
+}
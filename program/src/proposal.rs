@@ -0,0 +1,423 @@
+use crate::mock_sdk::{
+    AccountInfo,
+    Pubkey,
+    ProgramContext,
+    ProgramResult,
+    ProgramError,
+};
+use bitcoin::hashes::{sha256, Hash};
+use borsh::{BorshDeserialize, BorshSerialize};
+use crate::{
+    admin::MAX_ADMINS,
+    error::OVTError,
+    events::OVTEvent,
+    msg,
+    system::{create_program_account, initialize_account, verify_uninitialized},
+    timelock::requires_timelock,
+    OVTInstruction,
+    OVTProgram,
+    OVTState,
+};
+
+/// Longest time a proposal may stay open, in seconds
+pub const MAX_PROPOSAL_LIFETIME_SECS: u64 = 7 * 24 * 3_600;
+
+/// Whether `instruction` runs on the admin quorum's signatures. Holder
+/// instructions are signed by the holder and are never proposed.
+pub fn requires_quorum(instruction: &OVTInstruction) -> bool {
+    matches!(
+        instruction,
+        OVTInstruction::UpdateNAV { .. }
+            | OVTInstruction::BuybackBurn { .. }
+            | OVTInstruction::AddSAFE { .. }
+            | OVTInstruction::UpdateSAFE { .. }
+            | OVTInstruction::ConvertSAFE { .. }
+            | OVTInstruction::AddPosition { .. }
+            | OVTInstruction::UpdatePosition { .. }
+            | OVTInstruction::ExitPosition
+            | OVTInstruction::RecomputeNAV { .. }
+            | OVTInstruction::SetCircuitBreaker { .. }
+            | OVTInstruction::Mint { .. }
+            | OVTInstruction::SettleRedemption { .. }
+            | OVTInstruction::AddAdmin { .. }
+            | OVTInstruction::RemoveAdmin { .. }
+            | OVTInstruction::ReplaceAdmin { .. }
+            | OVTInstruction::SetThreshold { .. }
+            | OVTInstruction::SetTimelock { .. }
+            | OVTInstruction::RotateTreasury { .. }
+            | OVTInstruction::Pause { .. }
+            | OVTInstruction::Unpause { .. }
+            | OVTInstruction::Migrate { .. }
+            | OVTInstruction::InitializeNavHistory { .. }
+            | OVTInstruction::SetTwapWindow { .. }
+            | OVTInstruction::AccrueFees
+            | OVTInstruction::SetFeeParams { .. }
+            | OVTInstruction::CreateVesting { .. }
+            | OVTInstruction::RevokeVesting
+            | OVTInstruction::SetAllowlist { .. }
+            | OVTInstruction::SetAllowlistEntry { .. }
+            | OVTInstruction::RemoveAllowlistEntry
+            | OVTInstruction::SetEpochParams { .. }
+            | OVTInstruction::Distribute { .. }
+            | OVTInstruction::SetOraclePublisher { .. }
+    )
+}
+
+/// A privileged instruction awaiting admin approval. Approvals commit to the
+/// digest of the instruction bytes and the accounts it runs over, so an
+/// approved proposal cannot be executed against different accounts.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct Proposal {
+    /// Admin that created the proposal
+    pub proposer: Pubkey,
+    /// Serialized `OVTInstruction` to execute
+    pub instruction: Vec<u8>,
+    /// Accounts the instruction runs over, starting with the state account
+    pub accounts: Vec<Pubkey>,
+    /// Admins that approved, including the proposer
+    pub approvals: Vec<Pubkey>,
+    /// Unix timestamp the proposal was created
+    pub created_at: u64,
+    /// Unix timestamp after which the proposal can no longer be approved or
+    /// queued
+    pub expires_at: u64,
+    /// Earliest execution time once queued in the timelock, zero before that
+    pub eta: u64,
+    pub executed: bool,
+    pub cancelled: bool,
+}
+
+impl Proposal {
+    /// Digest approvals are bound to
+    pub fn digest(&self) -> [u8; 32] {
+        Self::compute_digest(&self.instruction, &self.accounts)
+    }
+
+    pub fn compute_digest(instruction: &[u8], accounts: &[Pubkey]) -> [u8; 32] {
+        let mut preimage = instruction.to_vec();
+        for key in accounts {
+            preimage.extend_from_slice(&key.0);
+        }
+        sha256::Hash::hash(&preimage).to_byte_array()
+    }
+
+    pub fn is_queued(&self) -> bool {
+        self.eta != 0
+    }
+
+    /// Check the proposal can still be approved or executed at `now`. Queued
+    /// proposals no longer expire.
+    pub fn validate_open(&self, now: u64) -> ProgramResult {
+        if self.executed {
+            return Err(OVTError::ProposalAlreadyExecuted.into());
+        }
+        if self.cancelled {
+            return Err(OVTError::ProposalCancelled.into());
+        }
+        if !self.is_queued() && now > self.expires_at {
+            return Err(OVTError::ProposalExpired.into());
+        }
+        Ok(())
+    }
+
+    /// Load the proposal stored in `proposal_info`, checking it belongs to this
+    /// program and runs over the fund whose state is `state_key`
+    pub fn load(program_id: &Pubkey, proposal_info: &AccountInfo, state_key: &Pubkey) -> Result<Self, ProgramError> {
+        if *proposal_info.owner.borrow() != *program_id {
+            return Err(OVTError::InvalidProposal.into());
+        }
+        let proposal: Proposal = proposal_info.get_data()
+            .map_err(|_| ProgramError::from(OVTError::InvalidProposal))?;
+        if proposal.accounts.first() != Some(state_key) {
+            return Err(OVTError::InvalidProposal.into());
+        }
+        Ok(proposal)
+    }
+}
+
+impl OVTProgram {
+    pub(crate) fn process_propose(
+        ctx: &ProgramContext,
+        instruction: Vec<u8>,
+        accounts: Vec<Pubkey>,
+        lifetime_secs: u64,
+    ) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+        let proposal_info = ctx.get(1)?;
+        let proposer_info = ctx.get(2)?;
+        let system_program = ctx.get(3)?;
+
+        let state: OVTState = state_info.get_data()?;
+        let (_, config) = Self::load_admin_config(ctx, &state)?;
+        if !proposer_info.is_signer || !config.is_admin(&proposer_info.key) {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // Only privileged instructions over this fund can be proposed, which
+        // leaves out Initialize and the proposal instructions themselves
+        let proposed = OVTInstruction::try_from_slice(&instruction).map_err(|_| OVTError::InvalidProposal)?;
+        if !requires_quorum(&proposed) && !requires_timelock(&proposed, &state) {
+            msg!("Only privileged instructions can be proposed");
+            return Err(OVTError::InvalidProposal.into());
+        }
+        if accounts.first() != Some(&state_info.key) {
+            return Err(OVTError::InvalidProposal.into());
+        }
+        if lifetime_secs == 0 || lifetime_secs > MAX_PROPOSAL_LIFETIME_SECS {
+            return Err(OVTError::InvalidProposal.into());
+        }
+        verify_uninitialized(proposal_info)?;
+
+        let now = ctx.clock()?.unix_timestamp;
+        let proposal = Proposal {
+            proposer: proposer_info.key,
+            instruction,
+            accounts,
+            approvals: vec![proposer_info.key],
+            created_at: now,
+            expires_at: now + lifetime_secs,
+            eta: 0,
+            executed: false,
+            cancelled: false,
+        };
+
+        // Room for every admin to approve
+        let space = borsh::object_length(&proposal)? + 32 * (MAX_ADMINS - 1);
+        create_program_account(
+            &ctx.program_id,
+            proposal_info,
+            proposer_info,
+            space as u64,
+            system_program,
+        )?;
+        initialize_account(&ctx.program_id, proposal_info, &proposal)?;
+
+        ctx.emit(&OVTEvent::ProposalCreated {
+            proposal: proposal_info.key,
+            proposer: proposer_info.key,
+            expires_at: proposal.expires_at,
+        })
+    }
+
+    pub(crate) fn process_approve(ctx: &ProgramContext, digest: [u8; 32]) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+        let proposal_info = ctx.get(1)?;
+        let approver_info = ctx.get(2)?;
+
+        let state: OVTState = state_info.get_data()?;
+        let (_, config) = Self::load_admin_config(ctx, &state)?;
+        if !approver_info.is_signer || !config.is_admin(&approver_info.key) {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let mut proposal = Proposal::load(&ctx.program_id, proposal_info, &state_info.key)?;
+        proposal.validate_open(ctx.clock()?.unix_timestamp)?;
+        // Approvals close once the proposal is queued
+        if proposal.is_queued() {
+            return Err(OVTError::InvalidProposal.into());
+        }
+        if digest != proposal.digest() || proposal.approvals.contains(&approver_info.key) {
+            return Err(OVTError::InvalidProposal.into());
+        }
+
+        proposal.approvals.push(approver_info.key);
+        ctx.emit(&OVTEvent::ProposalApproved {
+            proposal: proposal_info.key,
+            approver: approver_info.key,
+            approvals: proposal.approvals.len() as u8,
+        })?;
+        proposal_info.set_data(&proposal)
+    }
+
+    /// Cancel a proposal. The proposer may cancel alone; anyone else needs the
+    /// admin quorum.
+    pub(crate) fn process_cancel(ctx: &ProgramContext) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+        let proposal_info = ctx.get(1)?;
+
+        let state: OVTState = state_info.get_data()?;
+        let mut proposal = Proposal::load(&ctx.program_id, proposal_info, &state_info.key)?;
+        let proposer_signed = ctx.accounts.iter()
+            .any(|info| info.is_signer && info.key == proposal.proposer);
+        if !proposer_signed {
+            Self::verify_admin_quorum(ctx, &state)?;
+        }
+        if proposal.executed {
+            return Err(OVTError::ProposalAlreadyExecuted.into());
+        }
+        if proposal.cancelled {
+            return Err(OVTError::ProposalCancelled.into());
+        }
+
+        proposal.cancelled = true;
+        ctx.emit(&OVTEvent::ProposalCancelled { proposal: proposal_info.key })?;
+        proposal_info.set_data(&proposal)
+    }
+
+    /// Run the proposed instruction once enough current admins have approved.
+    /// The accounts after the proposal must match those it was approved over;
+    /// the approvals stand in for the admin signatures. Sensitive instructions
+    /// are queued in the timelock by the first call and run by a later one.
+    pub(crate) fn process_execute(ctx: &ProgramContext) -> ProgramResult {
+        let proposal_info = ctx.get(0)?;
+        let state_info = ctx.get(1)?;
+        let instruction_infos = &ctx.accounts[1..];
+
+        let now = ctx.clock()?.unix_timestamp;
+        let mut proposal = Proposal::load(&ctx.program_id, proposal_info, &state_info.key)?;
+        proposal.validate_open(now)?;
+
+        if instruction_infos.len() != proposal.accounts.len()
+            || instruction_infos.iter().zip(&proposal.accounts).any(|(info, key)| info.key != *key)
+        {
+            return Err(OVTError::InvalidProposal.into());
+        }
+
+        let state: OVTState = state_info.get_data()?;
+        let (_, config) = Self::load_admin_config(ctx, &state)?;
+
+        // Approvals from keys that have since left the admin set do not count
+        let approvers: Vec<Pubkey> = proposal.approvals.iter()
+            .copied()
+            .filter(|key| config.is_admin(key))
+            .collect();
+        if approvers.len() < config.threshold as usize {
+            msg!("Proposal has {} of {} required approvals", approvers.len(), config.threshold);
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let instruction = OVTInstruction::try_from_slice(&proposal.instruction)?;
+        if requires_timelock(&instruction, &state)
+            && !Self::advance_timelock(ctx, &state, &proposal_info.key, &mut proposal, now)?
+        {
+            return proposal_info.set_data(&proposal);
+        }
+
+        // Mark the proposal executed before running it so it can only run once
+        proposal.executed = true;
+        proposal_info.set_data(&proposal)?;
+
+        let mut accounts = instruction_infos.to_vec();
+        accounts.extend(approvers.iter().map(|key| AccountInfo::new(*key, true, false)));
+        ctx.invoke(accounts, |nested| Self::dispatch(nested, instruction))?;
+
+        if proposal.is_queued() {
+            ctx.emit(&OVTEvent::TimelockExecuted { proposal: proposal_info.key })?;
+        }
+        ctx.emit(&OVTEvent::ProposalExecuted { proposal: proposal_info.key })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        circuit_breaker::CircuitBreakerParams,
+        mock_sdk::AccountMeta,
+        test_fixtures::Fixture,
+    };
+
+    #[test]
+    fn test_proposal_lifecycle() {
+        let mut fixture = Fixture::new();
+        let proposal = fixture.create_system_account();
+        let tight = CircuitBreakerParams {
+            max_cumulative_bps: 15_000,
+            ..CircuitBreakerParams::default()
+        };
+        let instruction = OVTInstruction::SetCircuitBreaker { params: tight, rebase: false };
+        let accounts = vec![
+            AccountMeta::new(fixture.state, true),
+            AccountMeta::new_readonly(fixture.admin_config, false),
+        ];
+
+        let admins = fixture.admins.clone();
+        fixture.propose(proposal, admins[0], &instruction, &accounts).unwrap();
+        let digest = fixture.proposal(&proposal).digest();
+        assert_eq!(fixture.proposal(&proposal).approvals, vec![admins[0]]);
+
+        // Not enough approvals yet
+        fixture.approve(proposal, admins[1], digest).unwrap();
+        let err = fixture.execute(proposal, &accounts).unwrap_err();
+        assert_eq!(err.to_string(), "Missing required signature");
+
+        // Approvals are bound to the proposed bytes and count once per admin
+        let err = fixture.approve(proposal, admins[2], [0u8; 32]).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidProposal");
+        assert!(fixture.approve(proposal, admins[1], digest).is_err());
+        let outsider = fixture.create_signer();
+        assert!(fixture.approve(proposal, outsider, digest).is_err());
+
+        fixture.approve(proposal, admins[2], digest).unwrap();
+
+        // Executing over different accounts is rejected
+        let err = fixture.execute(proposal, &accounts[..1]).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidProposal");
+
+        fixture.execute(proposal, &accounts).unwrap();
+        assert_eq!(fixture.state().circuit_breaker, tight);
+        assert!(fixture.proposal(&proposal).executed);
+
+        // Each proposal executes at most once
+        let err = fixture.execute(proposal, &accounts).unwrap_err();
+        assert_eq!(err.to_string(), "ProposalAlreadyExecuted");
+
+        // A proposal cannot be opened over an existing account, spent
+        // proposal or not
+        let err = fixture.propose(proposal, admins[0], &instruction, &accounts).unwrap_err();
+        assert_eq!(err.to_string(), "AccountAlreadyInitialized");
+        let program_account = fixture.create_account();
+        let err = fixture.propose(program_account, admins[0], &instruction, &accounts).unwrap_err();
+        assert_eq!(err.to_string(), "AccountAlreadyInitialized");
+    }
+
+    #[test]
+    fn test_proposal_expiry_and_cancel() {
+        let mut fixture = Fixture::new();
+        let admins = fixture.admins.clone();
+        let instruction = OVTInstruction::SetThreshold { threshold: 2 };
+        let accounts = vec![
+            AccountMeta::new_readonly(fixture.state, false),
+            AccountMeta::new(fixture.admin_config, true),
+        ];
+
+        // Proposals cannot nest
+        let nested = fixture.create_system_account();
+        let err = fixture.propose(nested, admins[0], &OVTInstruction::Execute, &accounts).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidProposal");
+
+        // Holder instructions are signed by the holder, not proposed
+        for holder_instruction in [OVTInstruction::Transfer { amount: 1 }, OVTInstruction::ClaimVested] {
+            let err = fixture.propose(nested, admins[0], &holder_instruction, &accounts).unwrap_err();
+            assert_eq!(err.to_string(), "InvalidProposal");
+        }
+
+        let expiring = fixture.create_system_account();
+        fixture.propose(expiring, admins[0], &instruction, &accounts).unwrap();
+        let digest = fixture.proposal(&expiring).digest();
+        fixture.client.advance_clock(Fixture::PROPOSAL_LIFETIME_SECS + 1);
+        let err = fixture.approve(expiring, admins[1], digest).unwrap_err();
+        assert_eq!(err.to_string(), "ProposalExpired");
+
+        let cancelled = fixture.create_system_account();
+        fixture.propose_and_approve(cancelled, &instruction, &accounts, &admins[..3]).unwrap();
+
+        // Another admin alone cannot cancel, the proposer can
+        let cancel = |fixture: &mut Fixture, signer: Pubkey| {
+            fixture.process_raw(
+                vec![
+                    AccountMeta::new_readonly(fixture.state, false),
+                    AccountMeta::new(cancelled, true),
+                    AccountMeta::new_readonly(signer, true),
+                    AccountMeta::new_readonly(fixture.admin_config, false),
+                ],
+                &OVTInstruction::Cancel,
+            )
+        };
+        assert!(cancel(&mut fixture, admins[3]).is_err());
+        cancel(&mut fixture, admins[0]).unwrap();
+
+        let err = fixture.execute(cancelled, &accounts).unwrap_err();
+        assert_eq!(err.to_string(), "ProposalCancelled");
+    }
+}
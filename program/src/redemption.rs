@@ -0,0 +1,268 @@
+use crate::mock_sdk::{
+    AccountInfo,
+    Pubkey,
+    ProgramContext,
+    ProgramResult,
+    ProgramError,
+    TokenAccount,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use crate::{
+    error::OVTError,
+    events::OVTEvent,
+    system::{create_program_account, initialize_account, verify_uninitialized},
+    utxo::{validate_btc_address, verify_payment_to_address},
+    OVTProgram,
+    OVTState,
+};
+
+/// BTC the treasury owes a holder for redeemed OVT or a distribution claim
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct RedemptionObligation {
+    /// Holder that redeemed or claimed
+    pub holder: Pubkey,
+    /// OVT base units burned, zero for distribution claims
+    pub ovt_amount: u64,
+    /// Satoshis owed at the NAV of redemption
+    pub payout_sats: u64,
+    /// Address the payout must be sent to
+    pub btc_address: String,
+    /// Unix timestamp the obligation was created
+    pub created_at: u64,
+    /// Payout transaction, once settled
+    pub payout_txid: Option<String>,
+    /// Unix timestamp the obligation was settled
+    pub settled_at: u64,
+}
+
+impl RedemptionObligation {
+    pub fn is_settled(&self) -> bool {
+        self.payout_txid.is_some()
+    }
+
+    /// Load the obligation stored in `obligation_info`, checking it belongs to this program
+    pub fn load(program_id: &Pubkey, obligation_info: &AccountInfo) -> Result<Self, ProgramError> {
+        if *obligation_info.owner.borrow() != *program_id {
+            return Err(OVTError::RedemptionNotFound.into());
+        }
+        obligation_info.get_data()
+            .map_err(|_| OVTError::RedemptionNotFound.into())
+    }
+}
+
+impl OVTProgram {
+    /// Burn the holder's OVT and open a payout obligation. The holder's
+    /// signature on the debit is all a redemption needs; the admins approve
+    /// the payout when they settle it.
+    pub(crate) fn process_redeem(ctx: &ProgramContext, amount: u64, btc_address: String) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+        let obligation_info = ctx.get(1)?;
+        let holder_info = ctx.get(2)?;
+        let payer_info = ctx.get(3)?;
+        let system_program = ctx.get(4)?;
+        let holder_account_info = ctx.get(5)?;
+
+        if !holder_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        let mut state: OVTState = state_info.get_data()?;
+        validate_btc_address(&btc_address)?;
+        verify_uninitialized(obligation_info)?;
+        Self::accrue_fees(ctx, &mut state)?;
+
+        // Against a TWAP, pay out at the lower of it and the spot NAV so a
+        // fresh mark-up cannot be redeemed into
+        let now = ctx.clock()?.unix_timestamp;
+        let payout_sats = match Self::twap_nav_per_token(ctx, &state, now)? {
+            Some(twap) => state.sats_for_ovt_at(amount, twap.min(state.nav_per_token_sats()?))?,
+            None => state.sats_for_ovt(amount)?,
+        };
+        if payout_sats == 0 {
+            return Err(OVTError::InsufficientFunds.into());
+        }
+
+        let new_supply = state.total_supply
+            .checked_sub(amount)
+            .ok_or(OVTError::InsufficientFunds)?;
+        state.record_burn(amount, now)?;
+
+        // The payout leaves the treasury, so the per-token NAV is unchanged,
+        // or rises when paid out below the spot NAV
+        state.nav_sats = state.nav_sats
+            .checked_sub(payout_sats)
+            .ok_or(OVTError::ArithmeticOverflow)?;
+        state.total_supply = new_supply;
+
+        let mut holder_account = TokenAccount::load(&ctx.program_id, &state_info.key, holder_account_info)?;
+        holder_account.debit(holder_info, amount)?;
+        holder_account_info.set_data(&holder_account)?;
+
+        let obligation = RedemptionObligation {
+            holder: holder_info.key,
+            ovt_amount: amount,
+            payout_sats,
+            btc_address,
+            created_at: now,
+            payout_txid: None,
+            settled_at: 0,
+        };
+
+        // Room for the payout txid once settled
+        let space = borsh::object_length(&obligation)? + 4 + 64;
+        create_program_account(
+            &ctx.program_id,
+            obligation_info,
+            payer_info,
+            space as u64,
+            system_program,
+        )?;
+        initialize_account(&ctx.program_id, obligation_info, &obligation)?;
+
+        ctx.emit(&OVTEvent::Redeemed {
+            holder: obligation.holder,
+            obligation: obligation_info.key,
+            amount,
+            payout_sats,
+            btc_address: obligation.btc_address,
+        })?;
+        state_info.set_data(&state)
+    }
+
+    pub(crate) fn process_settle_redemption(
+        ctx: &ProgramContext,
+        obligation: Pubkey,
+        payout_txid: String,
+        payout_vout: u32,
+    ) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+        let obligation_info = ctx.get(1)?;
+        let authority_info = ctx.get(2)?;
+        let utxo_info = ctx.get(3)?;
+        let marker_info = ctx.get(4)?;
+        let system_program = ctx.get(5)?;
+
+        let state: OVTState = state_info.get_data()?;
+        Self::verify_admin_quorum(ctx, &state)?;
+
+        if obligation_info.key != obligation {
+            return Err(OVTError::RedemptionNotFound.into());
+        }
+        let mut redemption = RedemptionObligation::load(&ctx.program_id, obligation_info)?;
+        if redemption.is_settled() {
+            return Err(OVTError::RedemptionAlreadySettled.into());
+        }
+
+        verify_payment_to_address(utxo_info, redemption.payout_sats, &redemption.btc_address)?;
+        Self::consume_payment(ctx, marker_info, authority_info, system_program, &payout_txid, payout_vout)?;
+
+        redemption.settled_at = ctx.clock()?.unix_timestamp;
+        ctx.emit(&OVTEvent::RedemptionSettled {
+            obligation,
+            payout_txid: payout_txid.clone(),
+            payout_vout,
+        })?;
+        redemption.payout_txid = Some(payout_txid);
+        obligation_info.set_data(&redemption)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock_sdk::AccountMeta, test_fixtures::Fixture, OVTInstruction};
+
+    const BTC_ADDRESS: &str = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+
+    #[test]
+    fn test_redeem_and_settle() {
+        let mut fixture = Fixture::new();
+        let utxo_account = fixture.create_account();
+        let holder = fixture.create_signer();
+        let holder_account = fixture.token_account(holder);
+
+        // 1M OVT backed by 2M sats, all held by the holder
+        let mut state = fixture.state();
+        state.total_supply = 1_000_000;
+        state.nav_sats = 2_000_000;
+        fixture.set_state(&state);
+        fixture.set_balance(&holder_account, 1_000_000);
+
+        let redeem = |fixture: &mut Fixture, obligation: Pubkey, amount, btc_address: &str| {
+            let accounts = vec![
+                AccountMeta::new(fixture.state, true),
+                AccountMeta::new(obligation, true),
+                AccountMeta::new_readonly(holder, true),
+                AccountMeta::new(holder, true),
+                AccountMeta::new_readonly(fixture.system_program, false),
+                AccountMeta::new(holder_account, true),
+            ];
+            // No admin co-signs a redemption
+            fixture.process_raw(
+                accounts,
+                &OVTInstruction::Redeem {
+                    amount,
+                    btc_address: btc_address.to_string(),
+                },
+            )
+        };
+
+        let obligation_account = fixture.create_system_account();
+        let err = redeem(&mut fixture, obligation_account, 50_000, "not an address").unwrap_err();
+        assert_eq!(err.to_string(), "InvalidBitcoinAddress");
+
+        redeem(&mut fixture, obligation_account, 50_000, BTC_ADDRESS).unwrap();
+
+        let state = fixture.state();
+        assert_eq!(state.total_supply, 950_000);
+        assert_eq!(fixture.balance(&holder_account), 950_000);
+        assert_eq!(state.nav_sats, 1_900_000);
+
+        let obligation: RedemptionObligation = fixture.client.get_account_data(&obligation_account).unwrap();
+        assert_eq!(obligation.holder, holder);
+        assert_eq!(obligation.payout_sats, 100_000);
+        assert!(!obligation.is_settled());
+
+        // Neither an obligation nor any other existing account can be
+        // written over
+        let err = redeem(&mut fixture, obligation_account, 10_000, BTC_ADDRESS).unwrap_err();
+        assert_eq!(err.to_string(), "AccountAlreadyInitialized");
+        let err = redeem(&mut fixture, holder_account, 10_000, BTC_ADDRESS).unwrap_err();
+        assert_eq!(err.to_string(), "AccountAlreadyInitialized");
+        assert_eq!(fixture.balance(&holder_account), 950_000);
+
+        // More than the supply cannot be redeemed
+        let unused_obligation = fixture.create_system_account();
+        let err = redeem(&mut fixture, unused_obligation, 2_000_000, BTC_ADDRESS).unwrap_err();
+        assert_eq!(err.to_string(), "InsufficientFunds");
+
+        let settle = |fixture: &mut Fixture, obligation: Pubkey| {
+            let accounts = vec![
+                AccountMeta::new_readonly(fixture.state, false),
+                AccountMeta::new(obligation_account, true),
+                AccountMeta::new_readonly(fixture.authority(), true),
+                AccountMeta::new_readonly(utxo_account, false),
+                AccountMeta::new(fixture.payment_marker(Fixture::PAYMENT_TXID, 0), true),
+                AccountMeta::new_readonly(fixture.system_program, false),
+            ];
+            fixture.process(
+                accounts,
+                &OVTInstruction::SettleRedemption {
+                    obligation,
+                    payout_txid: Fixture::PAYMENT_TXID.to_string(),
+                    payout_vout: 0,
+                },
+            )
+        };
+
+        let err = settle(&mut fixture, Pubkey::new_unique()).unwrap_err();
+        assert_eq!(err.to_string(), "RedemptionNotFound");
+
+        settle(&mut fixture, obligation_account).unwrap();
+        let obligation: RedemptionObligation = fixture.client.get_account_data(&obligation_account).unwrap();
+        assert_eq!(obligation.payout_txid.as_deref(), Some(Fixture::PAYMENT_TXID));
+        assert_eq!(obligation.settled_at, fixture.client.clock().unix_timestamp);
+
+        let err = settle(&mut fixture, obligation_account).unwrap_err();
+        assert_eq!(err.to_string(), "RedemptionAlreadySettled");
+    }
+}
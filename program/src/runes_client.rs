@@ -1,4 +1,5 @@
 use bitcoin::{Network, PublicKey};
+use borsh::{BorshDeserialize, BorshSerialize};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -13,13 +14,13 @@ pub enum RunesError {
     BitcoinRPC(String),
 }
 
-#[derive(Debug, Clone)]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PositionType {
     PreTGE,
     PostTGE,
 }
 
-#[derive(Debug, Clone)]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PositionStatus {
     Active,
     Exited,
@@ -60,14 +61,10 @@ impl RunesClient {
         _amount: u64,  // Prefixed with _ since it's unused in mock
         signatures: Vec<String>,
         admin_pubkeys: Vec<PublicKey>,
+        threshold: usize,
     ) -> Result<String, RunesError> {
         // Mock implementation for testing
-        if signatures.len() < 3 {
-            return Err(RunesError::InsufficientSignatures);
-        }
-        if admin_pubkeys.len() != 5 {
-            return Err(RunesError::InvalidAdminKeys);
-        }
+        check_multisig(signatures.len(), &admin_pubkeys, threshold)?;
         Ok("mock_txid".to_string())
     }
 
@@ -76,14 +73,10 @@ impl RunesClient {
         _position: PortfolioPosition,
         signatures: &[String],
         admin_pubkeys: &[PublicKey],
+        threshold: usize,
     ) -> Result<String, RunesError> {
         // Mock implementation for testing
-        if signatures.len() < 3 {
-            return Err(RunesError::InsufficientSignatures);
-        }
-        if admin_pubkeys.len() != 5 {
-            return Err(RunesError::InvalidAdminKeys);
-        }
+        check_multisig(signatures.len(), admin_pubkeys, threshold)?;
         Ok("mock_position_id".to_string())
     }
 
@@ -92,14 +85,27 @@ impl RunesClient {
         signatures: &[String],
         _message: &[u8],
         admin_pubkeys: &[PublicKey],
+        threshold: usize,
     ) -> Result<bool, RunesError> {
         // Mock implementation for testing
-        if signatures.len() < 3 {
-            return Err(RunesError::InsufficientSignatures);
-        }
-        if admin_pubkeys.len() != 5 {
+        check_multisig(signatures.len(), admin_pubkeys, threshold)?;
+        Ok(true)
+    }
+}
+
+/// Check an M-of-N admin set: `threshold` must be between one and the number
+/// of distinct admin keys, and at least `threshold` signatures must be present
+fn check_multisig(signature_count: usize, admin_pubkeys: &[PublicKey], threshold: usize) -> Result<(), RunesError> {
+    if admin_pubkeys.is_empty() || threshold == 0 || threshold > admin_pubkeys.len() {
+        return Err(RunesError::InvalidAdminKeys);
+    }
+    for (i, pubkey) in admin_pubkeys.iter().enumerate() {
+        if admin_pubkeys[..i].contains(pubkey) {
             return Err(RunesError::InvalidAdminKeys);
         }
-        Ok(true)
     }
-} 
\ No newline at end of file
+    if signature_count < threshold {
+        return Err(RunesError::InsufficientSignatures);
+    }
+    Ok(())
+}
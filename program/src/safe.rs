@@ -0,0 +1,380 @@
+use crate::mock_sdk::{
+    AccountInfo,
+    Pubkey,
+    ProgramContext,
+    ProgramResult,
+    ProgramError,
+    TokenAccount,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use crate::{
+    error::OVTError,
+    events::OVTEvent,
+    math::{mul_div, BPS_DENOMINATOR},
+    system::{create_program_account, initialize_account, verify_uninitialized},
+    OVTProgram,
+    OVTState,
+    OVT_DECIMALS,
+};
+
+/// Seed prefix of SAFE addresses
+pub const SAFE_SEED: &[u8] = b"safe";
+
+/// Pre-TGE SAFE investment record, stored in its own program account
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct SAFEData {
+    /// State account of the fund the SAFE was issued by
+    pub state: Pubkey,
+    /// Identifier of the SAFE, unique per fund
+    pub safe_id: u64,
+    /// Investor holding the SAFE
+    pub investor: Pubkey,
+    /// Amount invested in satoshis
+    pub amount_sats: u64,
+    /// Valuation cap in satoshis
+    pub valuation_cap_sats: u64,
+    /// Conversion discount in basis points
+    pub discount_bps: u16,
+    /// Unix timestamp after which the SAFE may be converted
+    pub unlock_time: u64,
+    /// Whether the SAFE has been converted to a token position
+    pub converted: bool,
+}
+
+/// Address of SAFE `safe_id` in the fund whose state is `state`. Deriving
+/// it from the id keeps ids unique per fund.
+pub fn safe_address(program_id: &Pubkey, state: &Pubkey, safe_id: u64) -> Pubkey {
+    Pubkey::find_program_address(&[SAFE_SEED, &state.0, &safe_id.to_le_bytes()], program_id).0
+}
+
+impl SAFEData {
+    /// Maximum discount, 100% in basis points
+    pub const MAX_DISCOUNT_BPS: u16 = 10_000;
+
+    pub fn validate(&self) -> ProgramResult {
+        if self.amount_sats == 0 || self.valuation_cap_sats == 0 {
+            return Err(ProgramError::InvalidArgument);
+        }
+        if self.discount_bps > Self::MAX_DISCOUNT_BPS {
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(())
+    }
+
+    pub fn is_unlocked(&self, now: u64) -> bool {
+        now >= self.unlock_time
+    }
+
+    /// Price per whole OVT the SAFE converts at: the lower of the NAV less
+    /// the discount and the valuation cap spread over the supply. Before any
+    /// supply exists only the discount applies.
+    pub fn conversion_price(&self, state: &OVTState) -> Result<u64, ProgramError> {
+        let discount_price = mul_div(
+            state.nav_per_token_sats()?,
+            BPS_DENOMINATOR - self.discount_bps as u64,
+            BPS_DENOMINATOR,
+        )?;
+        let price = if state.total_supply == 0 {
+            discount_price
+        } else {
+            let cap_price = mul_div(self.valuation_cap_sats, 10u64.pow(OVT_DECIMALS as u32), state.total_supply)?;
+            discount_price.min(cap_price)
+        };
+        if price == 0 {
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(price)
+    }
+
+    /// Load the SAFE stored in `safe_info`, checking it belongs to this program
+    /// and to the fund whose state is `state`, and carries the expected id
+    pub fn load(
+        program_id: &Pubkey,
+        state: &Pubkey,
+        safe_info: &AccountInfo,
+        safe_id: u64,
+    ) -> Result<Self, ProgramError> {
+        if *safe_info.owner.borrow() != *program_id {
+            return Err(OVTError::SAFENotFound.into());
+        }
+        let safe: SAFEData = safe_info.get_data()
+            .map_err(|_| ProgramError::from(OVTError::SAFENotFound))?;
+        if safe.state != *state {
+            return Err(OVTError::SAFENotFound.into());
+        }
+        if safe.safe_id != safe_id || safe_info.key != safe_address(program_id, state, safe_id) {
+            return Err(OVTError::InvalidSAFEId.into());
+        }
+        Ok(safe)
+    }
+}
+
+impl OVTProgram {
+    pub(crate) fn process_add_safe(ctx: &ProgramContext, safe_data: SAFEData) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+        let safe_info = ctx.get(1)?;
+        let authority_info = ctx.get(2)?;
+        let system_program = ctx.get(3)?;
+
+        let state: OVTState = state_info.get_data()?;
+        Self::verify_admin_quorum(ctx, &state)?;
+        safe_data.validate()?;
+
+        if safe_data.state != state_info.key
+            || safe_info.key != safe_address(&ctx.program_id, &state_info.key, safe_data.safe_id)
+        {
+            return Err(OVTError::InvalidSAFEId.into());
+        }
+        // The account of an id in use already holds its SAFE
+        verify_uninitialized(safe_info)?;
+        if safe_data.converted {
+            return Err(OVTError::SAFEAlreadyConverted.into());
+        }
+
+        create_program_account(
+            &ctx.program_id,
+            safe_info,
+            authority_info,
+            borsh::object_length(&safe_data)? as u64,
+            system_program,
+        )?;
+        initialize_account(&ctx.program_id, safe_info, &safe_data)?;
+
+        ctx.emit(&OVTEvent::SAFEAdded {
+            safe_id: safe_data.safe_id,
+            amount_sats: safe_data.amount_sats,
+        })
+    }
+
+    pub(crate) fn process_update_safe(
+        ctx: &ProgramContext,
+        safe_id: u64,
+        new_data: SAFEData,
+    ) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+        let safe_info = ctx.get(1)?;
+
+        let state: OVTState = state_info.get_data()?;
+        Self::verify_admin_quorum(ctx, &state)?;
+
+        let safe = SAFEData::load(&ctx.program_id, &state_info.key, safe_info, safe_id)?;
+        if safe.converted {
+            return Err(OVTError::SAFEAlreadyConverted.into());
+        }
+        // The fund and id are the account's identity and the converted flag
+        // is only set through ConvertSAFE
+        if new_data.safe_id != safe_id || new_data.state != safe.state {
+            return Err(OVTError::InvalidSAFEId.into());
+        }
+        if new_data.converted {
+            return Err(ProgramError::InvalidArgument);
+        }
+        new_data.validate()?;
+
+        safe_info.set_data(&new_data)
+    }
+
+    /// Issue the investor OVT for an unlocked SAFE at its conversion price.
+    /// The investment was paid in before TGE and is already part of the NAV,
+    /// so conversion only adds supply, counted against the epoch mint budget.
+    pub(crate) fn process_convert_safe(ctx: &ProgramContext, safe_id: u64) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+        let safe_info = ctx.get(1)?;
+        let investor_info = ctx.get(2)?;
+
+        let mut state: OVTState = state_info.get_data()?;
+        Self::verify_admin_quorum(ctx, &state)?;
+        Self::accrue_fees(ctx, &mut state)?;
+
+        let mut safe = SAFEData::load(&ctx.program_id, &state_info.key, safe_info, safe_id)?;
+        if safe.converted {
+            return Err(OVTError::SAFEAlreadyConverted.into());
+        }
+        let now = ctx.clock()?.unix_timestamp;
+        if !safe.is_unlocked(now) {
+            return Err(OVTError::SAFENotUnlocked.into());
+        }
+        let mut investor_account = TokenAccount::load(&ctx.program_id, &state_info.key, investor_info)?;
+        if investor_account.owner != safe.investor {
+            return Err(OVTError::InvalidTokenAccount.into());
+        }
+
+        let amount = state.ovt_for_sats_at(safe.amount_sats, safe.conversion_price(&state)?)?;
+        if amount == 0 {
+            return Err(OVTError::InsufficientFunds.into());
+        }
+        state.record_mint(amount, now)?;
+        state.total_supply = state.total_supply
+            .checked_add(amount)
+            .ok_or(OVTError::ArithmeticOverflow)?;
+        investor_account.credit(amount)?;
+        Self::verify_allowlisted(ctx, &state, &safe.investor, &investor_info.key, investor_account.amount)?;
+
+        safe.converted = true;
+        ctx.emit(&OVTEvent::SAFEConverted {
+            safe_id,
+            investor: safe.investor,
+            amount,
+            total_supply: state.total_supply,
+        })?;
+        safe_info.set_data(&safe)?;
+        investor_info.set_data(&investor_account)?;
+        state_info.set_data(&state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock_sdk::AccountMeta, test_fixtures::Fixture, OVTInstruction};
+
+    fn safe(fixture: &Fixture, safe_id: u64, unlock_time: u64) -> SAFEData {
+        SAFEData {
+            state: fixture.state,
+            safe_id,
+            investor: Pubkey::new_unique(),
+            amount_sats: 50_000_000,
+            valuation_cap_sats: 10_000_000_000,
+            discount_bps: 2_000,
+            unlock_time,
+            converted: false,
+        }
+    }
+
+    fn add_safe(fixture: &mut Fixture, safe_account: Pubkey, safe_data: SAFEData) -> ProgramResult {
+        let accounts = vec![
+            AccountMeta::new_readonly(fixture.state, false),
+            AccountMeta::new(safe_account, true),
+            AccountMeta::new_readonly(fixture.authority(), true),
+            AccountMeta::new_readonly(fixture.system_program, false),
+        ];
+        fixture.process(accounts, &OVTInstruction::AddSAFE { safe_data }).map(|_| ())
+    }
+
+    #[test]
+    fn test_safe_lifecycle() {
+        let mut fixture = Fixture::new();
+        let mut state = fixture.state();
+        state.total_supply = 1_000_000_000;
+        state.nav_sats = 2_000_000_000;
+        fixture.set_state(&state);
+        let safe_account = fixture.safe_account(1);
+
+        // Add a SAFE that unlocked in the past
+        let terms = safe(&fixture, 1, 1);
+        let investor_account = fixture.token_account(terms.investor);
+        add_safe(&mut fixture, safe_account, terms.clone()).unwrap();
+
+        // Its id cannot be issued again, nor the SAFE stored elsewhere
+        let err = add_safe(&mut fixture, safe_account, terms.clone()).unwrap_err();
+        assert_eq!(err.to_string(), "AccountAlreadyInitialized");
+        let elsewhere = fixture.create_system_account();
+        let err = add_safe(&mut fixture, elsewhere, SAFEData { safe_id: 2, ..terms.clone() }).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidSAFEId");
+
+        let accounts = vec![
+            AccountMeta::new(fixture.state, true),
+            AccountMeta::new(safe_account, true),
+            AccountMeta::new(investor_account, true),
+        ];
+
+        // Updating with a mismatched id is rejected
+        let instruction = OVTInstruction::UpdateSAFE { safe_id: 2, new_data: safe(&fixture, 2, 1) };
+        let err = fixture.process(accounts.clone(), &instruction).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidSAFEId");
+
+        let moved = SAFEData { state: Pubkey::new_unique(), ..terms.clone() };
+        let instruction = OVTInstruction::UpdateSAFE { safe_id: 1, new_data: moved };
+        let err = fixture.process(accounts.clone(), &instruction).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidSAFEId");
+
+        let updated = SAFEData { amount_sats: 75_000_000, ..terms.clone() };
+        let instruction = OVTInstruction::UpdateSAFE { safe_id: 1, new_data: updated };
+        fixture.process(accounts.clone(), &instruction).unwrap();
+
+        // Conversion needs the admin quorum and an account of the investor
+        let instruction = OVTInstruction::ConvertSAFE { safe_id: 1 };
+        let err = fixture.process_with_signers(accounts.clone(), 2, &instruction).unwrap_err();
+        assert_eq!(err.to_string(), "Missing required signature");
+        let other_account = fixture.token_account(Pubkey::new_unique());
+        let err = fixture.process(vec![
+            AccountMeta::new(fixture.state, true),
+            AccountMeta::new(safe_account, true),
+            AccountMeta::new(other_account, true),
+        ], &instruction).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidTokenAccount");
+
+        // At 2 BTC per OVT, the 20% discount prices the SAFE at 1.6 BTC, well
+        // under the cap of 100 BTC over 10 OVT
+        let events = fixture.process(accounts.clone(), &instruction).unwrap();
+        assert_eq!(events, vec![OVTEvent::SAFEConverted {
+            safe_id: 1,
+            investor: terms.investor,
+            amount: 46_875_000,
+            total_supply: 1_046_875_000,
+        }]);
+        assert_eq!(fixture.balance(&investor_account), 46_875_000);
+        let state = fixture.state();
+        assert_eq!(state.total_supply, 1_046_875_000);
+        assert_eq!(state.nav_sats, 2_000_000_000);
+
+        let stored: SAFEData = fixture.client.get_account_data(&safe_account).unwrap();
+        assert_eq!(stored.amount_sats, 75_000_000);
+        assert!(stored.converted);
+
+        // A converted SAFE can neither be converted again nor updated
+        let err = fixture.process(accounts.clone(), &instruction).unwrap_err();
+        assert_eq!(err.to_string(), "SAFEAlreadyConverted");
+
+        let instruction = OVTInstruction::UpdateSAFE { safe_id: 1, new_data: safe(&fixture, 1, 1) };
+        let err = fixture.process(accounts, &instruction).unwrap_err();
+        assert_eq!(err.to_string(), "SAFEAlreadyConverted");
+
+        // A low enough cap prices below the discount
+        let capped = SAFEData { valuation_cap_sats: 1_000_000_000, ..terms };
+        assert_eq!(capped.conversion_price(&state).unwrap(), 95_522_388);
+    }
+
+    #[test]
+    fn test_safe_not_unlocked_or_missing() {
+        let mut fixture = Fixture::new();
+        let safe_account = fixture.safe_account(7);
+
+        let unlock_time = fixture.client.clock().unix_timestamp + 3_600;
+        let terms = safe(&fixture, 7, unlock_time);
+        add_safe(&mut fixture, safe_account, terms.clone()).unwrap();
+
+        let instruction = OVTInstruction::ConvertSAFE { safe_id: 7 };
+        let err = fixture.process(
+            vec![
+                AccountMeta::new(fixture.state, true),
+                AccountMeta::new(safe_account, true),
+            ],
+            &instruction,
+        ).unwrap_err();
+        assert_eq!(err.to_string(), "SAFENotUnlocked");
+
+        // An account that never held a SAFE
+        let empty_account = fixture.create_account();
+        let err = fixture.process(
+            vec![
+                AccountMeta::new(fixture.state, true),
+                AccountMeta::new(empty_account, true),
+            ],
+            &instruction,
+        ).unwrap_err();
+        assert_eq!(err.to_string(), "SAFENotFound");
+
+        // A SAFE of another fund
+        let foreign = SAFEData { state: Pubkey::new_unique(), ..terms };
+        fixture.client.set_account_data(&empty_account, &foreign).unwrap();
+        let err = fixture.process(
+            vec![
+                AccountMeta::new(fixture.state, true),
+                AccountMeta::new(empty_account, true),
+            ],
+            &instruction,
+        ).unwrap_err();
+        assert_eq!(err.to_string(), "SAFENotFound");
+    }
+}
@@ -4,6 +4,7 @@ use crate::mock_sdk::{
     ProgramResult,
 };
 use borsh::BorshSerialize;
+use crate::error::OVTError;
 
 pub fn create_program_account(
     program_id: &Pubkey,
@@ -19,10 +20,48 @@ pub fn create_program_account(
     Ok(())
 }
 
+/// Refuse an account that holds data or belongs to a program, so creating
+/// a record in it cannot overwrite another one
+pub fn verify_uninitialized(account_info: &AccountInfo) -> ProgramResult {
+    if !account_info.data.borrow().is_empty() || *account_info.owner.borrow() != Pubkey::new() {
+        return Err(OVTError::AccountAlreadyInitialized.into());
+    }
+    Ok(())
+}
+
+/// Grow `account_info` to at least `space` bytes
+pub fn resize_program_account(
+    account_info: &AccountInfo,
+    _payer_info: &AccountInfo,
+    space: u64,
+    _system_program: &AccountInfo,
+) -> ProgramResult {
+    // Mock implementation for testing
+    let mut data = account_info.data.borrow_mut();
+    if data.len() < space as usize {
+        data.resize(space as usize, 0);
+    }
+    Ok(())
+}
+
+/// Move the lamports of `account_info` to `destination_info` and hand the
+/// emptied account back to the system program
+pub fn close_program_account(
+    account_info: &AccountInfo,
+    destination_info: &AccountInfo,
+) -> ProgramResult {
+    // Mock implementation for testing
+    let lamports = account_info.lamports.replace(0);
+    *destination_info.lamports.borrow_mut() += lamports;
+    account_info.data.borrow_mut().clear();
+    *account_info.owner.borrow_mut() = Pubkey::new();
+    Ok(())
+}
+
 pub fn initialize_account<T: BorshSerialize>(
     _program_id: &Pubkey,
     account_info: &AccountInfo,
     data: &T,
 ) -> ProgramResult {
     account_info.set_data(data)
-} 
\ No newline at end of file
+}
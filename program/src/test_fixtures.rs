@@ -0,0 +1,318 @@
+//! Shared setup for the program's unit tests: an initialized fund with five
+//! admins and a threshold of three, and three guardians with a veto threshold
+//! of two
+
+use crate::mock_sdk::{
+    test_utils::TestClient,
+    AccountInfo,
+    AccountMeta,
+    Pubkey,
+    ProgramResult,
+    ProgramError,
+    TokenAccount,
+};
+use crate::{
+    allowlist::allowlist_entry_address,
+    circuit_breaker::CircuitBreakerParams,
+    distribution::{distribution_address, distribution_claim_address},
+    epoch::EpochParams,
+    fees::FeeParams,
+    payment::{parse_txid, payment_marker_address},
+    proposal::Proposal,
+    safe::safe_address,
+    timelock::TimelockParams,
+    OVTEvent,
+    OVTInstruction,
+    OVTState,
+};
+use bitcoin::secp256k1::Secp256k1;
+use rand::thread_rng;
+
+/// Result of running an instruction: the events it emitted
+pub(crate) type Events = Result<Vec<OVTEvent>, ProgramError>;
+
+pub(crate) struct Fixture {
+    pub client: TestClient,
+    pub program_id: Pubkey,
+    pub admins: Vec<Pubkey>,
+    pub guardians: Vec<Pubkey>,
+    pub system_program: Pubkey,
+    pub oracle_program: Pubkey,
+    pub oracle_publisher: Pubkey,
+    pub state: Pubkey,
+    pub admin_config: Pubkey,
+}
+
+impl Fixture {
+    pub const ADMIN_COUNT: usize = 5;
+    pub const THRESHOLD: u8 = 3;
+    pub const TIMELOCK_DELAY_SECS: u64 = 86_400;
+    pub const PROPOSAL_LIFETIME_SECS: u64 = 3_600;
+    /// A well-formed txid for payments in tests
+    pub const PAYMENT_TXID: &'static str = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+
+    pub fn new() -> Self {
+        let mut client = TestClient::with_multisig(Self::THRESHOLD as usize, Self::ADMIN_COUNT);
+        let program_id = Pubkey::new_unique();
+        let admins = (0..Self::ADMIN_COUNT)
+            .map(|_| client.create_admin_account(program_id).unwrap().key)
+            .collect::<Vec<_>>();
+        let system_program = client.create_system_program(program_id).unwrap().key;
+        let state = client.create_account(program_id).unwrap().key;
+        let admin_config = client.create_account(program_id).unwrap().key;
+
+        let secp = Secp256k1::new();
+        let (_, treasury) = secp.generate_keypair(&mut thread_rng());
+        let oracle_program = Pubkey::new_unique();
+        let oracle_publisher = Pubkey::new_unique();
+
+        let mut fixture = Self {
+            client,
+            program_id,
+            admins,
+            guardians: Vec::new(),
+            system_program,
+            oracle_program,
+            oracle_publisher,
+            state,
+            admin_config,
+        };
+        fixture.guardians = (0..3).map(|_| fixture.create_signer()).collect();
+        let instruction = OVTInstruction::Initialize {
+            treasury_pubkey_bytes: treasury.serialize(),
+            oracle_program_id: oracle_program,
+            oracle_publisher,
+            circuit_breaker: CircuitBreakerParams::default(),
+            admins: fixture.admins.clone(),
+            threshold: Self::THRESHOLD,
+            timelock: TimelockParams {
+                delay_secs: Self::TIMELOCK_DELAY_SECS,
+                guardians: fixture.guardians.clone(),
+                guardian_threshold: 2,
+            },
+            fees: FeeParams::default(),
+            epoch: EpochParams::default(),
+        };
+        fixture.process_raw(
+            vec![
+                AccountMeta::new(state, true),
+                AccountMeta::new_readonly(fixture.admins[0], true),
+                AccountMeta::new_readonly(system_program, false),
+                AccountMeta::new(admin_config, true),
+            ],
+            &instruction,
+        ).unwrap();
+        fixture
+    }
+
+    /// Admin that pays for and signs instructions
+    pub fn authority(&self) -> Pubkey {
+        self.admins[0]
+    }
+
+    /// A fresh account owned by the program
+    pub fn create_account(&mut self) -> Pubkey {
+        self.client.create_account(self.program_id).unwrap().key
+    }
+
+    /// A fresh empty account owned by the system program, for instructions
+    /// that create a record in it
+    pub fn create_system_account(&mut self) -> Pubkey {
+        self.client.create_system_account(Pubkey::new_unique()).unwrap().key
+    }
+
+    /// A fresh signer that is not an admin
+    pub fn create_signer(&mut self) -> Pubkey {
+        let key = Pubkey::new_unique();
+        self.client.accounts.lock().unwrap().insert(key, AccountInfo::new(key, true, false));
+        key
+    }
+
+    /// The marker account for output `vout` of `txid`, registered empty if
+    /// it does not exist yet
+    pub fn payment_marker(&mut self, txid: &str, vout: u32) -> Pubkey {
+        let key = payment_marker_address(&self.program_id, &parse_txid(txid).unwrap(), vout);
+        if !self.client.accounts.lock().unwrap().contains_key(&key) {
+            self.client.create_system_account(key).unwrap();
+        }
+        key
+    }
+
+    /// The allowlist entry account of `holder`, registered empty if it does
+    /// not exist yet
+    pub fn allowlist_entry(&mut self, holder: &Pubkey) -> Pubkey {
+        let key = allowlist_entry_address(&self.program_id, &self.state, holder);
+        if !self.client.accounts.lock().unwrap().contains_key(&key) {
+            self.client.create_system_account(key).unwrap();
+        }
+        key
+    }
+
+    /// The account of SAFE `safe_id`, registered empty if it does not exist
+    /// yet
+    pub fn safe_account(&mut self, safe_id: u64) -> Pubkey {
+        let key = safe_address(&self.program_id, &self.state, safe_id);
+        if !self.client.accounts.lock().unwrap().contains_key(&key) {
+            self.client.create_system_account(key).unwrap();
+        }
+        key
+    }
+
+    /// The distribution account of the proceeds of `position`, registered
+    /// empty if it does not exist yet
+    pub fn distribution(&mut self, position: &Pubkey) -> Pubkey {
+        let key = distribution_address(&self.program_id, &self.state, position);
+        if !self.client.accounts.lock().unwrap().contains_key(&key) {
+            self.client.create_system_account(key).unwrap();
+        }
+        key
+    }
+
+    /// The account `holder` claims from `distribution` into, registered
+    /// empty if it does not exist yet
+    pub fn distribution_claim(&mut self, distribution: &Pubkey, holder: &Pubkey) -> Pubkey {
+        let key = distribution_claim_address(&self.program_id, distribution, holder);
+        if !self.client.accounts.lock().unwrap().contains_key(&key) {
+            self.client.create_system_account(key).unwrap();
+        }
+        key
+    }
+
+    /// A fresh OVT account for `owner`, opened through the program
+    pub fn token_account(&mut self, owner: Pubkey) -> Pubkey {
+        let token_account = self.create_account();
+        let accounts = vec![
+            AccountMeta::new_readonly(self.state, false),
+            AccountMeta::new(token_account, true),
+            AccountMeta::new_readonly(self.authority(), true),
+            AccountMeta::new_readonly(self.system_program, false),
+        ];
+        self.process_raw(accounts, &OVTInstruction::InitializeTokenAccount { owner }).unwrap();
+        token_account
+    }
+
+    pub fn balance(&self, token_account: &Pubkey) -> u64 {
+        self.client.get_account_data::<TokenAccount>(token_account).unwrap().amount
+    }
+
+    /// Overwrite the balance of `token_account`, for tests that also set
+    /// `total_supply` directly
+    pub fn set_balance(&self, token_account: &Pubkey, amount: u64) {
+        let mut account: TokenAccount = self.client.get_account_data(token_account).unwrap();
+        account.amount = amount;
+        self.client.set_account_data(token_account, &account).unwrap();
+    }
+
+    /// The admin config account followed by the first `count` admins as signers
+    pub fn signers(&self, count: usize) -> Vec<AccountMeta> {
+        let mut accounts = vec![AccountMeta::new_readonly(self.admin_config, false)];
+        accounts.extend(self.admins[..count].iter().map(|admin| AccountMeta::new_readonly(*admin, true)));
+        accounts
+    }
+
+    /// Run `instruction` over `accounts` followed by a signing quorum of admins
+    pub fn process(&mut self, accounts: Vec<AccountMeta>, instruction: &OVTInstruction) -> Events {
+        self.process_with_signers(accounts, Self::THRESHOLD as usize, instruction)
+    }
+
+    /// Run `instruction` over `accounts` followed by the first `count` admins as signers
+    pub fn process_with_signers(
+        &mut self,
+        mut accounts: Vec<AccountMeta>,
+        count: usize,
+        instruction: &OVTInstruction,
+    ) -> Events {
+        accounts.extend(self.signers(count));
+        self.process_raw(accounts, instruction)
+    }
+
+    /// Run `instruction` over exactly `accounts`
+    pub fn process_raw(&mut self, accounts: Vec<AccountMeta>, instruction: &OVTInstruction) -> Events {
+        self.client.process_transaction(self.program_id, accounts, borsh::to_vec(instruction).unwrap())
+    }
+
+    /// Propose `instruction` over `accounts` as `proposer`
+    pub fn propose(
+        &mut self,
+        proposal: Pubkey,
+        proposer: Pubkey,
+        instruction: &OVTInstruction,
+        accounts: &[AccountMeta],
+    ) -> Events {
+        let propose_accounts = vec![
+            AccountMeta::new_readonly(self.state, false),
+            AccountMeta::new(proposal, true),
+            AccountMeta::new_readonly(proposer, true),
+            AccountMeta::new_readonly(self.system_program, false),
+            AccountMeta::new_readonly(self.admin_config, false),
+        ];
+        self.process_raw(propose_accounts, &OVTInstruction::Propose {
+            instruction: borsh::to_vec(instruction).unwrap(),
+            accounts: accounts.iter().map(|meta| meta.pubkey).collect(),
+            lifetime_secs: Self::PROPOSAL_LIFETIME_SECS,
+        })
+    }
+
+    pub fn approve(&mut self, proposal: Pubkey, approver: Pubkey, digest: [u8; 32]) -> Events {
+        let accounts = vec![
+            AccountMeta::new_readonly(self.state, false),
+            AccountMeta::new(proposal, true),
+            AccountMeta::new_readonly(approver, true),
+            AccountMeta::new_readonly(self.admin_config, false),
+        ];
+        self.process_raw(accounts, &OVTInstruction::Approve { digest })
+    }
+
+    /// Propose `instruction` as the first of `approvers` and approve it with the rest
+    pub fn propose_and_approve(
+        &mut self,
+        proposal: Pubkey,
+        instruction: &OVTInstruction,
+        accounts: &[AccountMeta],
+        approvers: &[Pubkey],
+    ) -> ProgramResult {
+        self.propose(proposal, approvers[0], instruction, accounts)?;
+        let digest = self.proposal(&proposal).digest();
+        for approver in &approvers[1..] {
+            self.approve(proposal, *approver, digest)?;
+        }
+        Ok(())
+    }
+
+    pub fn execute(&mut self, proposal: Pubkey, accounts: &[AccountMeta]) -> Events {
+        let mut execute_accounts = vec![AccountMeta::new(proposal, true)];
+        execute_accounts.extend_from_slice(accounts);
+        self.process_raw(execute_accounts, &OVTInstruction::Execute)
+    }
+
+    /// Run `instruction` through a proposal approved by `approvers`, waiting
+    /// out the timelock if it queues. Returns the events of the execution
+    /// that ran it.
+    pub fn process_timelocked(
+        &mut self,
+        accounts: Vec<AccountMeta>,
+        instruction: &OVTInstruction,
+        approvers: &[Pubkey],
+    ) -> Events {
+        let proposal = self.create_system_account();
+        self.propose_and_approve(proposal, instruction, &accounts, approvers)?;
+        let events = self.execute(proposal, &accounts)?;
+        if self.proposal(&proposal).executed {
+            return Ok(events);
+        }
+        self.client.advance_clock(Self::TIMELOCK_DELAY_SECS);
+        self.execute(proposal, &accounts)
+    }
+
+    pub fn proposal(&self, proposal: &Pubkey) -> Proposal {
+        self.client.get_account_data(proposal).unwrap()
+    }
+
+    pub fn state(&self) -> OVTState {
+        self.client.get_account_data(&self.state).unwrap()
+    }
+
+    pub fn set_state(&self, state: &OVTState) {
+        self.client.set_account_data(&self.state, state).unwrap();
+    }
+}
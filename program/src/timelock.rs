@@ -0,0 +1,264 @@
+use crate::mock_sdk::{
+    AccountInfo,
+    Pubkey,
+    ProgramContext,
+    ProgramResult,
+    ProgramError,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use crate::{
+    admin::MAX_ADMINS,
+    error::OVTError,
+    events::OVTEvent,
+    msg,
+    proposal::Proposal,
+    OVTInstruction,
+    OVTProgram,
+    OVTState,
+};
+
+/// Longest delay the timelock can be configured with, in seconds
+pub const MAX_TIMELOCK_DELAY_SECS: u64 = 30 * 24 * 3_600;
+
+/// Delay applied to sensitive operations and the guardians who can veto them
+/// while they wait
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct TimelockParams {
+    /// Seconds between a sensitive proposal being queued and it becoming executable
+    pub delay_secs: u64,
+    /// Keys that can veto queued operations, separate from the admins
+    pub guardians: Vec<Pubkey>,
+    /// Distinct guardian signatures required to veto. Zero only when there
+    /// are no guardians.
+    pub guardian_threshold: u8,
+}
+
+impl Default for TimelockParams {
+    fn default() -> Self {
+        Self {
+            delay_secs: 2 * 24 * 3_600,
+            guardians: Vec::new(),
+            guardian_threshold: 0,
+        }
+    }
+}
+
+impl TimelockParams {
+    /// Account space for the largest guardian set
+    pub const MAX_SPACE: usize = 8 + 4 + 32 * MAX_ADMINS + 1;
+
+    pub fn validate(&self) -> ProgramResult {
+        if self.delay_secs > MAX_TIMELOCK_DELAY_SECS || self.guardians.len() > MAX_ADMINS {
+            return Err(OVTError::InvalidAdminConfig.into());
+        }
+        let threshold = self.guardian_threshold as usize;
+        if threshold > self.guardians.len() || (threshold == 0 && !self.guardians.is_empty()) {
+            return Err(OVTError::InvalidAdminConfig.into());
+        }
+        for (i, guardian) in self.guardians.iter().enumerate() {
+            if self.guardians[..i].contains(guardian) {
+                return Err(OVTError::InvalidAdminConfig.into());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_guardian(&self, key: &Pubkey) -> bool {
+        self.guardians.contains(key)
+    }
+
+    /// Whether at least the guardian threshold of distinct guardians signed.
+    /// Always false when there are no guardians.
+    pub fn has_guardian_quorum(&self, account_infos: &[AccountInfo]) -> bool {
+        let mut signers: Vec<Pubkey> = Vec::new();
+        for info in account_infos {
+            if info.is_signer && self.is_guardian(&info.key) && !signers.contains(&info.key) {
+                signers.push(info.key);
+            }
+        }
+        self.guardian_threshold > 0 && signers.len() >= self.guardian_threshold as usize
+    }
+}
+
+/// Whether `instruction` must be queued through a proposal and wait out the
+/// timelock before it runs. Changes to the admin set, the timelock itself,
+/// the treasury key or the oracle publisher always do; circuit breaker changes only when they widen
+/// the bounds or move the baseline, fee changes when they raise a rate,
+/// allowlist changes when they restrict an open fund, and epoch changes when
+/// they widen a budget.
+pub fn requires_timelock(instruction: &OVTInstruction, state: &OVTState) -> bool {
+    match instruction {
+        OVTInstruction::AddAdmin { .. }
+        | OVTInstruction::RemoveAdmin { .. }
+        | OVTInstruction::ReplaceAdmin { .. }
+        | OVTInstruction::SetThreshold { .. }
+        | OVTInstruction::SetTimelock { .. }
+        | OVTInstruction::RotateTreasury { .. }
+        | OVTInstruction::SetOraclePublisher { .. } => true,
+        OVTInstruction::SetCircuitBreaker { params, rebase } => {
+            *rebase || params.widens(&state.circuit_breaker)
+        }
+        OVTInstruction::SetFeeParams { fees } => fees.raises(&state.fees),
+        OVTInstruction::SetAllowlist { allowlist } => allowlist.restricts(&state.allowlist),
+        OVTInstruction::SetEpochParams { epoch } => epoch.widens(&state.epoch),
+        _ => false,
+    }
+}
+
+impl OVTProgram {
+    /// Queue `proposal` if it has not been queued yet, returning whether it is
+    /// ready to execute at `now`
+    pub(crate) fn advance_timelock(
+        ctx: &ProgramContext,
+        state: &OVTState,
+        proposal_key: &Pubkey,
+        proposal: &mut Proposal,
+        now: u64,
+    ) -> Result<bool, ProgramError> {
+        if proposal.eta == 0 {
+            let (_, config) = Self::load_admin_config(ctx, state)?;
+            proposal.eta = now
+                .checked_add(config.timelock.delay_secs)
+                .ok_or(OVTError::ArithmeticOverflow)?;
+            ctx.emit(&OVTEvent::TimelockQueued { proposal: *proposal_key, eta: proposal.eta })?;
+            return Ok(false);
+        }
+        if now < proposal.eta {
+            msg!("Timelock pending: proposal {:?} executable at {}", proposal_key, proposal.eta);
+            return Err(OVTError::TimelockNotElapsed.into());
+        }
+        Ok(true)
+    }
+
+    /// Veto a proposal with the guardian quorum, whether queued or still
+    /// collecting approvals
+    pub(crate) fn process_veto(ctx: &ProgramContext) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+        let proposal_info = ctx.get(1)?;
+
+        let state: OVTState = state_info.get_data()?;
+        let (_, config) = Self::load_admin_config(ctx, &state)?;
+        let timelock = &config.timelock;
+
+        if !timelock.has_guardian_quorum(&ctx.accounts) {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let mut proposal = Proposal::load(&ctx.program_id, proposal_info, &state_info.key)?;
+        if proposal.executed {
+            return Err(OVTError::ProposalAlreadyExecuted.into());
+        }
+        if proposal.cancelled {
+            return Err(OVTError::ProposalCancelled.into());
+        }
+
+        proposal.cancelled = true;
+        ctx.emit(&OVTEvent::TimelockVetoed { proposal: proposal_info.key })?;
+        proposal_info.set_data(&proposal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        circuit_breaker::CircuitBreakerParams,
+        mock_sdk::AccountMeta,
+        test_fixtures::Fixture,
+    };
+
+    #[test]
+    fn test_timelock_params_validation() {
+        let guardians: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let params = TimelockParams { delay_secs: 86_400, guardians: guardians.clone(), guardian_threshold: 2 };
+        assert!(params.validate().is_ok());
+        assert!(TimelockParams::default().validate().is_ok());
+
+        assert!(TimelockParams { guardian_threshold: 0, ..params.clone() }.validate().is_err());
+        assert!(TimelockParams { guardian_threshold: 4, ..params.clone() }.validate().is_err());
+        assert!(TimelockParams { delay_secs: MAX_TIMELOCK_DELAY_SECS + 1, ..params.clone() }.validate().is_err());
+        assert!(TimelockParams { guardians: vec![guardians[0], guardians[0]], ..params }.validate().is_err());
+    }
+
+    #[test]
+    fn test_sensitive_operations_are_timelocked() {
+        let mut fixture = Fixture::new();
+        let new_admin = fixture.create_signer();
+        let add_admin = OVTInstruction::AddAdmin { admin: new_admin };
+        let accounts = vec![
+            AccountMeta::new_readonly(fixture.state, false),
+            AccountMeta::new(fixture.admin_config, true),
+        ];
+
+        // Even a full quorum cannot change the admin set directly
+        let err = fixture.process(accounts.clone(), &add_admin).unwrap_err();
+        assert_eq!(err.to_string(), "TimelockRequired");
+
+        // The first execution queues the proposal
+        let proposal = fixture.create_system_account();
+        let approvers = fixture.admins[..3].to_vec();
+        fixture.propose_and_approve(proposal, &add_admin, &accounts, &approvers).unwrap();
+        fixture.execute(proposal, &accounts).unwrap();
+        let queued: Proposal = fixture.client.get_account_data(&proposal).unwrap();
+        assert_eq!(queued.eta, fixture.client.clock().unix_timestamp + Fixture::TIMELOCK_DELAY_SECS);
+        assert!(!queued.executed);
+
+        // and it runs once the delay has passed
+        fixture.client.advance_clock(Fixture::TIMELOCK_DELAY_SECS - 1);
+        let err = fixture.execute(proposal, &accounts).unwrap_err();
+        assert_eq!(err.to_string(), "TimelockNotElapsed");
+        fixture.client.advance_clock(1);
+        fixture.execute(proposal, &accounts).unwrap();
+        let config: crate::AdminConfig = fixture.client.get_account_data(&fixture.admin_config).unwrap();
+        assert!(config.is_admin(&new_admin));
+
+        // Narrowing the circuit breaker is not sensitive, widening it is
+        let state_accounts = vec![AccountMeta::new(fixture.state, true)];
+        let tight = CircuitBreakerParams { max_cumulative_bps: 15_000, ..CircuitBreakerParams::default() };
+        fixture.process(state_accounts.clone(), &OVTInstruction::SetCircuitBreaker { params: tight, rebase: false })
+            .unwrap();
+        let err = fixture.process(
+            state_accounts.clone(),
+            &OVTInstruction::SetCircuitBreaker { params: CircuitBreakerParams::default(), rebase: false },
+        ).unwrap_err();
+        assert_eq!(err.to_string(), "TimelockRequired");
+        let err = fixture.process(state_accounts, &OVTInstruction::SetCircuitBreaker { params: tight, rebase: true })
+            .unwrap_err();
+        assert_eq!(err.to_string(), "TimelockRequired");
+    }
+
+    #[test]
+    fn test_guardian_veto() {
+        let mut fixture = Fixture::new();
+        let accounts = vec![
+            AccountMeta::new_readonly(fixture.state, false),
+            AccountMeta::new(fixture.admin_config, true),
+        ];
+        let proposal = fixture.create_system_account();
+        let approvers = fixture.admins[..3].to_vec();
+        fixture.propose_and_approve(proposal, &OVTInstruction::SetThreshold { threshold: 1 }, &accounts, &approvers)
+            .unwrap();
+        fixture.execute(proposal, &accounts).unwrap();
+
+        let veto = |fixture: &mut Fixture, signers: &[Pubkey]| {
+            let mut veto_accounts = vec![
+                AccountMeta::new_readonly(fixture.state, false),
+                AccountMeta::new(proposal, true),
+                AccountMeta::new_readonly(fixture.admin_config, false),
+            ];
+            veto_accounts.extend(signers.iter().map(|key| AccountMeta::new_readonly(*key, true)));
+            fixture.process_raw(veto_accounts, &OVTInstruction::Veto)
+        };
+
+        // Admins are not guardians, and one guardian is not a quorum
+        let admins = fixture.admins.clone();
+        assert!(veto(&mut fixture, &admins).is_err());
+        let guardians = fixture.guardians.clone();
+        assert!(veto(&mut fixture, &guardians[..1]).is_err());
+
+        veto(&mut fixture, &guardians[..2]).unwrap();
+        fixture.client.advance_clock(Fixture::TIMELOCK_DELAY_SECS);
+        let err = fixture.execute(proposal, &accounts).unwrap_err();
+        assert_eq!(err.to_string(), "ProposalCancelled");
+    }
+}
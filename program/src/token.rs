@@ -0,0 +1,321 @@
+//! Holder balances, kept in program-owned `TokenAccount`s. The state account
+//! key stands in for the mint, and mints, burns and redemptions credit and
+//! debit these accounts so their balances always sum to `total_supply`.
+
+use crate::mock_sdk::{
+    AccountInfo,
+    Pubkey,
+    ProgramContext,
+    ProgramResult,
+    ProgramError,
+    TokenAccount,
+};
+use crate::{
+    error::OVTError,
+    events::OVTEvent,
+    msg,
+    system::{close_program_account, create_program_account, initialize_account},
+    OVTProgram,
+    OVTState,
+};
+
+/// `TokenAccount::state` of an account in use
+pub const TOKEN_ACCOUNT_INITIALIZED: u8 = 1;
+
+impl TokenAccount {
+    /// Serialized size with every optional field set
+    pub const MAX_SPACE: usize = 32 + 32 + 8 + 33 + 1 + 9 + 8 + 33;
+
+    pub fn new(mint: Pubkey, owner: Pubkey) -> Self {
+        Self {
+            mint,
+            owner,
+            amount: 0,
+            delegate: None,
+            state: TOKEN_ACCOUNT_INITIALIZED,
+            is_native: None,
+            delegated_amount: 0,
+            close_authority: None,
+        }
+    }
+
+    /// Load the OVT account stored in `token_info`, checking it belongs to
+    /// this program and holds OVT of the fund whose state is `mint`
+    pub fn load(program_id: &Pubkey, mint: &Pubkey, token_info: &AccountInfo) -> Result<Self, ProgramError> {
+        if *token_info.owner.borrow() != *program_id {
+            return Err(OVTError::InvalidTokenAccount.into());
+        }
+        let token: Self = token_info.get_data()
+            .map_err(|_| OVTError::InvalidTokenAccount)?;
+        if token.mint != *mint || token.state != TOKEN_ACCOUNT_INITIALIZED {
+            return Err(OVTError::InvalidTokenAccount.into());
+        }
+        Ok(token)
+    }
+
+    /// Take `amount` out of the account on the signature of `authority`: the
+    /// owner, or the delegate within its remaining allowance
+    pub fn debit(&mut self, authority: &AccountInfo, amount: u64) -> ProgramResult {
+        if !authority.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        let remaining = self.amount.checked_sub(amount).ok_or(OVTError::InsufficientFunds)?;
+        if authority.key != self.owner {
+            if self.delegate != Some(authority.key) || self.delegated_amount < amount {
+                return Err(OVTError::InvalidAuthority.into());
+            }
+            self.delegated_amount -= amount;
+            if self.delegated_amount == 0 {
+                self.delegate = None;
+            }
+        }
+        self.amount = remaining;
+        Ok(())
+    }
+
+    pub fn credit(&mut self, amount: u64) -> ProgramResult {
+        self.amount = self.amount.checked_add(amount).ok_or(OVTError::ArithmeticOverflow)?;
+        Ok(())
+    }
+}
+
+impl OVTProgram {
+    pub(crate) fn process_initialize_token_account(ctx: &ProgramContext, owner: Pubkey) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+        let token_info = ctx.get(1)?;
+        let payer_info = ctx.get(2)?;
+        let system_program = ctx.get(3)?;
+
+        if !payer_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        state_info.get_data::<OVTState>()?;
+        if *token_info.owner.borrow() == ctx.program_id && token_info.get_data::<TokenAccount>().is_ok() {
+            return Err(OVTError::InvalidTokenAccount.into());
+        }
+
+        create_program_account(
+            &ctx.program_id,
+            token_info,
+            payer_info,
+            TokenAccount::MAX_SPACE as u64,
+            system_program,
+        )?;
+        initialize_account(&ctx.program_id, token_info, &TokenAccount::new(state_info.key, owner))?;
+
+        ctx.emit(&OVTEvent::TokenAccountInitialized {
+            token_account: token_info.key,
+            owner,
+        })
+    }
+
+    pub(crate) fn process_transfer(ctx: &ProgramContext, amount: u64) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+        let source_info = ctx.get(1)?;
+        let destination_info = ctx.get(2)?;
+        let authority_info = ctx.get(3)?;
+
+        if source_info.key == destination_info.key {
+            return Err(ProgramError::InvalidArgument);
+        }
+        let state: OVTState = state_info.get_data()?;
+        let mut source = TokenAccount::load(&ctx.program_id, &state_info.key, source_info)?;
+        let mut destination = TokenAccount::load(&ctx.program_id, &state_info.key, destination_info)?;
+
+        source.debit(authority_info, amount)?;
+        destination.credit(amount)?;
+        Self::verify_allowlisted(ctx, &state, &destination.owner, &destination_info.key, destination.amount)?;
+
+        ctx.emit(&OVTEvent::Transferred {
+            source: source_info.key,
+            destination: destination_info.key,
+            amount,
+        })?;
+        source_info.set_data(&source)?;
+        destination_info.set_data(&destination)
+    }
+
+    /// Let `delegate` move up to `amount` out of the owner's account,
+    /// replacing any earlier approval
+    pub(crate) fn process_approve_delegate(ctx: &ProgramContext, delegate: Pubkey, amount: u64) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+        let token_info = ctx.get(1)?;
+        let owner_info = ctx.get(2)?;
+
+        let mut token = TokenAccount::load(&ctx.program_id, &state_info.key, token_info)?;
+        Self::verify_token_owner(&token, owner_info)?;
+
+        token.delegate = Some(delegate);
+        token.delegated_amount = amount;
+
+        ctx.emit(&OVTEvent::DelegateApproved {
+            token_account: token_info.key,
+            delegate,
+            amount,
+        })?;
+        token_info.set_data(&token)
+    }
+
+    pub(crate) fn process_revoke_delegate(ctx: &ProgramContext) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+        let token_info = ctx.get(1)?;
+        let owner_info = ctx.get(2)?;
+
+        let mut token = TokenAccount::load(&ctx.program_id, &state_info.key, token_info)?;
+        Self::verify_token_owner(&token, owner_info)?;
+
+        token.delegate = None;
+        token.delegated_amount = 0;
+
+        ctx.emit(&OVTEvent::DelegateRevoked { token_account: token_info.key })?;
+        token_info.set_data(&token)
+    }
+
+    /// Close an empty account, returning its lamports to `destination`
+    pub(crate) fn process_close_account(ctx: &ProgramContext) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+        let token_info = ctx.get(1)?;
+        let destination_info = ctx.get(2)?;
+        let authority_info = ctx.get(3)?;
+
+        let token = TokenAccount::load(&ctx.program_id, &state_info.key, token_info)?;
+        if !authority_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if authority_info.key != token.close_authority.unwrap_or(token.owner) {
+            return Err(OVTError::InvalidAuthority.into());
+        }
+        if token.amount > 0 {
+            msg!("Cannot close an account holding {} OVT", token.amount);
+            return Err(OVTError::InvalidTokenAccount.into());
+        }
+
+        close_program_account(token_info, destination_info)?;
+        ctx.emit(&OVTEvent::TokenAccountClosed {
+            token_account: token_info.key,
+            destination: destination_info.key,
+        })
+    }
+
+    fn verify_token_owner(token: &TokenAccount, owner_info: &AccountInfo) -> ProgramResult {
+        if !owner_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if owner_info.key != token.owner {
+            return Err(OVTError::InvalidAuthority.into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock_sdk::AccountMeta, test_fixtures::Fixture, OVTInstruction};
+
+    #[test]
+    fn test_transfer_and_delegate() {
+        let mut fixture = Fixture::new();
+        let alice = fixture.create_signer();
+        let bob = fixture.create_signer();
+        let carol = fixture.create_signer();
+        let alice_account = fixture.token_account(alice);
+        let bob_account = fixture.token_account(bob);
+        fixture.set_balance(&alice_account, 1_000);
+
+        // Accounts are opened once
+        let err = fixture.process_raw(vec![
+            AccountMeta::new_readonly(fixture.state, false),
+            AccountMeta::new(alice_account, true),
+            AccountMeta::new_readonly(fixture.authority(), true),
+            AccountMeta::new_readonly(fixture.system_program, false),
+        ], &OVTInstruction::InitializeTokenAccount { owner: alice }).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidTokenAccount");
+
+        let transfer = |fixture: &mut Fixture, source, destination, authority, amount| {
+            fixture.process_raw(vec![
+                AccountMeta::new_readonly(fixture.state, false),
+                AccountMeta::new(source, true),
+                AccountMeta::new(destination, true),
+                AccountMeta::new_readonly(authority, true),
+            ], &OVTInstruction::Transfer { amount })
+        };
+
+        let events = transfer(&mut fixture, alice_account, bob_account, alice, 400).unwrap();
+        assert_eq!(events, vec![OVTEvent::Transferred { source: alice_account, destination: bob_account, amount: 400 }]);
+        assert_eq!(fixture.balance(&alice_account), 600);
+        assert_eq!(fixture.balance(&bob_account), 400);
+
+        // Only the owner moves funds, and never more than the balance
+        let err = transfer(&mut fixture, alice_account, bob_account, bob, 1).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidAuthority");
+        let err = transfer(&mut fixture, alice_account, bob_account, alice, 601).unwrap_err();
+        assert_eq!(err.to_string(), "InsufficientFunds");
+        assert!(transfer(&mut fixture, alice_account, alice_account, alice, 1).is_err());
+
+        // A delegate spends within its allowance, which lapses once used up
+        let delegate = |fixture: &mut Fixture, instruction: &OVTInstruction, owner| {
+            fixture.process_raw(vec![
+                AccountMeta::new_readonly(fixture.state, false),
+                AccountMeta::new(alice_account, true),
+                AccountMeta::new_readonly(owner, true),
+            ], instruction)
+        };
+        let approve = OVTInstruction::ApproveDelegate { delegate: carol, amount: 250 };
+        let err = delegate(&mut fixture, &approve, carol).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidAuthority");
+        delegate(&mut fixture, &approve, alice).unwrap();
+
+        let err = transfer(&mut fixture, alice_account, bob_account, carol, 251).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidAuthority");
+        transfer(&mut fixture, alice_account, bob_account, carol, 200).unwrap();
+        transfer(&mut fixture, alice_account, bob_account, carol, 50).unwrap();
+        let account: TokenAccount = fixture.client.get_account_data(&alice_account).unwrap();
+        assert_eq!((account.amount, account.delegate, account.delegated_amount), (350, None, 0));
+        assert!(transfer(&mut fixture, alice_account, bob_account, carol, 1).is_err());
+
+        delegate(&mut fixture, &approve, alice).unwrap();
+        delegate(&mut fixture, &OVTInstruction::RevokeDelegate, alice).unwrap();
+        assert!(transfer(&mut fixture, alice_account, bob_account, carol, 1).is_err());
+
+        // Accounts of another fund are refused
+        let other = Fixture::new();
+        let foreign = fixture.create_account();
+        let mut foreign_account = TokenAccount::new(other.state, alice);
+        foreign_account.amount = 1_000;
+        fixture.client.set_account_data(&foreign, &foreign_account).unwrap();
+        let err = transfer(&mut fixture, foreign, bob_account, alice, 1).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidTokenAccount");
+    }
+
+    #[test]
+    fn test_close_account() {
+        let mut fixture = Fixture::new();
+        let alice = fixture.create_signer();
+        let alice_account = fixture.token_account(alice);
+        fixture.set_balance(&alice_account, 1);
+        let destination = fixture.create_signer();
+
+        let close = |fixture: &mut Fixture, authority| {
+            fixture.process_raw(vec![
+                AccountMeta::new_readonly(fixture.state, false),
+                AccountMeta::new(alice_account, true),
+                AccountMeta::new(destination, true),
+                AccountMeta::new_readonly(authority, true),
+            ], &OVTInstruction::CloseAccount)
+        };
+
+        let err = close(&mut fixture, alice).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidTokenAccount");
+        fixture.set_balance(&alice_account, 0);
+        let err = close(&mut fixture, destination).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidAuthority");
+
+        let lamports = *fixture.client.accounts.lock().unwrap()[&alice_account].lamports.borrow();
+        close(&mut fixture, alice).unwrap();
+        let accounts = fixture.client.accounts.lock().unwrap();
+        assert!(accounts[&alice_account].data.borrow().is_empty());
+        assert_eq!(*accounts[&alice_account].lamports.borrow(), 0);
+        assert_eq!(*accounts[&destination].lamports.borrow(), lamports);
+    }
+}
@@ -3,6 +3,9 @@ use crate::mock_sdk::{
     Pubkey,
     ProgramResult,
 };
+use crate::error::OVTError;
+use bitcoin::{address::NetworkUnchecked, Address};
+use std::str::FromStr;
 
 #[derive(Debug, Clone)]
 pub struct UtxoMeta {
@@ -28,6 +31,40 @@ pub fn verify_bitcoin_payment(
     Ok(())
 }
 
+/// Verify a payment to any of `treasury_keys`, such as the current treasury
+/// and one still in its rotation grace period
+pub fn verify_treasury_payment(
+    utxo_info: &AccountInfo,
+    expected_amount: u64,
+    treasury_keys: &[[u8; 33]],
+) -> ProgramResult {
+    let mut result = Err(OVTError::InvalidBitcoinPayment.into());
+    for treasury_pubkey in treasury_keys {
+        result = verify_bitcoin_payment(utxo_info, expected_amount, treasury_pubkey);
+        if result.is_ok() {
+            break;
+        }
+    }
+    result
+}
+
+/// Check that `btc_address` is a well-formed Bitcoin address
+pub fn validate_btc_address(btc_address: &str) -> ProgramResult {
+    Address::<NetworkUnchecked>::from_str(btc_address)
+        .map(|_| ())
+        .map_err(|_| OVTError::InvalidBitcoinAddress.into())
+}
+
+pub fn verify_payment_to_address(
+    _utxo_info: &AccountInfo,
+    _expected_amount: u64,
+    btc_address: &str,
+) -> ProgramResult {
+    validate_btc_address(btc_address)?;
+    // Mock implementation for testing
+    Ok(())
+}
+
 pub fn verify_utxo_ownership(
     _utxo_info: &AccountInfo,
     _program_id: &Pubkey,
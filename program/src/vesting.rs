@@ -0,0 +1,428 @@
+//! Vesting of team and advisor allocations. The allocation is moved into an
+//! escrow OVT account owned by the vesting account when it is created, and
+//! released to the beneficiary as it unlocks.
+
+use crate::mock_sdk::{
+    AccountInfo,
+    Pubkey,
+    ProgramContext,
+    ProgramResult,
+    ProgramError,
+    TokenAccount,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use crate::{
+    error::OVTError,
+    events::OVTEvent,
+    math::mul_div,
+    system::{create_program_account, initialize_account, verify_uninitialized},
+    OVTProgram,
+    OVTState,
+};
+
+/// How vested OVT unlocks between the cliff and the end of the schedule
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VestingSchedule {
+    /// Continuously, in proportion to the time elapsed
+    Linear,
+    /// In equal tranches at the end of each interval
+    Step { interval_secs: u64 },
+}
+
+/// Terms of an allocation, fixed when it is created
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct VestingTerms {
+    /// Owner of the OVT accounts unlocked OVT may be claimed to
+    pub beneficiary: Pubkey,
+    /// OVT base units allocated
+    pub total_amount: u64,
+    /// Unix timestamp vesting starts from
+    pub start_ts: u64,
+    /// Nothing unlocks until this long after the start
+    pub cliff_secs: u64,
+    /// Everything has unlocked this long after the start
+    pub duration_secs: u64,
+    pub schedule: VestingSchedule,
+    /// Whether the admins may revoke the unvested remainder
+    pub revocable: bool,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct VestingAccount {
+    pub terms: VestingTerms,
+    /// OVT account holding the unreleased allocation
+    pub escrow: Pubkey,
+    /// OVT base units claimed so far
+    pub released_amount: u64,
+    /// Unix timestamp of revocation, zero if not revoked. Vesting stops then.
+    pub revoked_at: u64,
+}
+
+impl VestingTerms {
+    pub fn validate(&self) -> ProgramResult {
+        let valid_schedule = match self.schedule {
+            VestingSchedule::Linear => true,
+            VestingSchedule::Step { interval_secs } => interval_secs > 0 && interval_secs <= self.duration_secs,
+        };
+        if self.total_amount == 0 || self.duration_secs == 0 || self.cliff_secs > self.duration_secs || !valid_schedule {
+            return Err(OVTError::InvalidVesting.into());
+        }
+        Ok(())
+    }
+
+    /// OVT base units unlocked by `now` under the schedule alone
+    pub fn unlocked_at(&self, now: u64) -> Result<u64, ProgramError> {
+        let elapsed = now.saturating_sub(self.start_ts);
+        if now < self.start_ts || elapsed < self.cliff_secs {
+            return Ok(0);
+        }
+        if elapsed >= self.duration_secs {
+            return Ok(self.total_amount);
+        }
+        let vested_secs = match self.schedule {
+            VestingSchedule::Linear => elapsed,
+            VestingSchedule::Step { interval_secs } => elapsed - elapsed % interval_secs,
+        };
+        mul_div(self.total_amount, vested_secs, self.duration_secs)
+    }
+}
+
+impl VestingAccount {
+    /// OVT base units vested by `now`. Nothing vests after revocation.
+    pub fn vested_at(&self, now: u64) -> Result<u64, ProgramError> {
+        let now = if self.revoked_at > 0 { now.min(self.revoked_at) } else { now };
+        self.terms.unlocked_at(now)
+    }
+
+    /// Vested OVT not yet claimed
+    pub fn claimable_at(&self, now: u64) -> Result<u64, ProgramError> {
+        Ok(self.vested_at(now)?.saturating_sub(self.released_amount))
+    }
+
+    /// Load the vesting account stored in `vesting_info`, checking it belongs to this program
+    pub fn load(program_id: &Pubkey, vesting_info: &AccountInfo) -> Result<Self, ProgramError> {
+        if *vesting_info.owner.borrow() != *program_id {
+            return Err(OVTError::InvalidVesting.into());
+        }
+        vesting_info.get_data()
+            .map_err(|_| OVTError::InvalidVesting.into())
+    }
+}
+
+impl OVTProgram {
+    /// Escrow `terms.total_amount` from the funding account, which the
+    /// authority owns or is delegate of, under a new vesting account. The
+    /// vesting and escrow accounts must be fresh and distinct from each
+    /// other and the funding account. Requires the admin quorum.
+    pub(crate) fn process_create_vesting(ctx: &ProgramContext, terms: VestingTerms) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+        let vesting_info = ctx.get(1)?;
+        let escrow_info = ctx.get(2)?;
+        let funding_info = ctx.get(3)?;
+        let authority_info = ctx.get(4)?;
+        let system_program = ctx.get(5)?;
+
+        let state: OVTState = state_info.get_data()?;
+        Self::verify_admin_quorum(ctx, &state)?;
+        terms.validate()?;
+        if vesting_info.key == escrow_info.key
+            || vesting_info.key == funding_info.key
+            || escrow_info.key == funding_info.key
+        {
+            return Err(OVTError::InvalidVesting.into());
+        }
+        verify_uninitialized(vesting_info)?;
+        verify_uninitialized(escrow_info)?;
+
+        let mut funding = TokenAccount::load(&ctx.program_id, &state_info.key, funding_info)?;
+        funding.debit(authority_info, terms.total_amount)?;
+        let mut escrow = TokenAccount::new(state_info.key, vesting_info.key);
+        escrow.credit(terms.total_amount)?;
+
+        let vesting = VestingAccount {
+            terms,
+            escrow: escrow_info.key,
+            released_amount: 0,
+            revoked_at: 0,
+        };
+        create_program_account(
+            &ctx.program_id,
+            vesting_info,
+            authority_info,
+            borsh::object_length(&vesting)? as u64,
+            system_program,
+        )?;
+        initialize_account(&ctx.program_id, vesting_info, &vesting)?;
+        create_program_account(
+            &ctx.program_id,
+            escrow_info,
+            authority_info,
+            TokenAccount::MAX_SPACE as u64,
+            system_program,
+        )?;
+        initialize_account(&ctx.program_id, escrow_info, &escrow)?;
+        funding_info.set_data(&funding)?;
+
+        ctx.emit(&OVTEvent::VestingCreated {
+            vesting: vesting_info.key,
+            beneficiary: vesting.terms.beneficiary,
+            total_amount: vesting.terms.total_amount,
+        })
+    }
+
+    /// Release everything vested so far to an OVT account of the beneficiary.
+    /// While the fund is restricted the beneficiary must be allowlisted.
+    pub(crate) fn process_claim_vested(ctx: &ProgramContext) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+        let vesting_info = ctx.get(1)?;
+        let escrow_info = ctx.get(2)?;
+        let destination_info = ctx.get(3)?;
+        let beneficiary_info = ctx.get(4)?;
+
+        let mut vesting = VestingAccount::load(&ctx.program_id, vesting_info)?;
+        if !beneficiary_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if beneficiary_info.key != vesting.terms.beneficiary {
+            return Err(OVTError::InvalidAuthority.into());
+        }
+        // Loaded before the escrow is debited, so it must not be the escrow
+        if destination_info.key == vesting.escrow {
+            return Err(OVTError::InvalidVesting.into());
+        }
+        let mut destination = TokenAccount::load(&ctx.program_id, &state_info.key, destination_info)?;
+        if destination.owner != vesting.terms.beneficiary {
+            return Err(OVTError::InvalidTokenAccount.into());
+        }
+
+        let amount = vesting.claimable_at(ctx.clock()?.unix_timestamp)?;
+        if amount == 0 {
+            return Err(OVTError::NothingVested.into());
+        }
+        Self::release_escrow(ctx, state_info, &vesting, escrow_info, &mut destination, amount)?;
+        let state: OVTState = state_info.get_data()?;
+        Self::verify_allowlisted(ctx, &state, &destination.owner, &destination_info.key, destination.amount)?;
+        vesting.released_amount += amount;
+
+        ctx.emit(&OVTEvent::VestedClaimed { vesting: vesting_info.key, amount })?;
+        destination_info.set_data(&destination)?;
+        vesting_info.set_data(&vesting)
+    }
+
+    /// Stop vesting and return the unvested remainder to `refund`. What has
+    /// vested stays claimable. Requires the admin quorum.
+    pub(crate) fn process_revoke_vesting(ctx: &ProgramContext) -> ProgramResult {
+        let state_info = ctx.get(0)?;
+        let vesting_info = ctx.get(1)?;
+        let escrow_info = ctx.get(2)?;
+        let refund_info = ctx.get(3)?;
+
+        let state: OVTState = state_info.get_data()?;
+        Self::verify_admin_quorum(ctx, &state)?;
+        let mut vesting = VestingAccount::load(&ctx.program_id, vesting_info)?;
+        if !vesting.terms.revocable || vesting.revoked_at > 0 {
+            return Err(OVTError::InvalidVesting.into());
+        }
+
+        if refund_info.key == vesting.escrow {
+            return Err(OVTError::InvalidVesting.into());
+        }
+
+        vesting.revoked_at = ctx.clock()?.unix_timestamp;
+        let returned = vesting.terms.total_amount - vesting.vested_at(vesting.revoked_at)?;
+        let mut refund = TokenAccount::load(&ctx.program_id, &state_info.key, refund_info)?;
+        if returned > 0 {
+            Self::release_escrow(ctx, state_info, &vesting, escrow_info, &mut refund, returned)?;
+        }
+
+        ctx.emit(&OVTEvent::VestingRevoked { vesting: vesting_info.key, returned })?;
+        refund_info.set_data(&refund)?;
+        vesting_info.set_data(&vesting)
+    }
+
+    /// Move `amount` out of the escrow of `vesting` into `destination`. The
+    /// escrow is owned by the vesting account, so the program moves it itself.
+    fn release_escrow(
+        ctx: &ProgramContext,
+        state_info: &AccountInfo,
+        vesting: &VestingAccount,
+        escrow_info: &AccountInfo,
+        destination: &mut TokenAccount,
+        amount: u64,
+    ) -> ProgramResult {
+        if escrow_info.key != vesting.escrow {
+            return Err(OVTError::InvalidVesting.into());
+        }
+        let mut escrow = TokenAccount::load(&ctx.program_id, &state_info.key, escrow_info)?;
+        escrow.amount = escrow.amount.checked_sub(amount).ok_or(OVTError::InsufficientFunds)?;
+        destination.credit(amount)?;
+        escrow_info.set_data(&escrow)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock_sdk::AccountMeta, test_fixtures::Fixture, OVTInstruction};
+
+    const MONTH: u64 = 30 * 24 * 3_600;
+
+    fn terms(schedule: VestingSchedule, revocable: bool) -> VestingTerms {
+        VestingTerms {
+            beneficiary: Pubkey::new_unique(),
+            total_amount: 48_000,
+            start_ts: 1_000_000,
+            cliff_secs: 12 * MONTH,
+            duration_secs: 48 * MONTH,
+            schedule,
+            revocable,
+        }
+    }
+
+    #[test]
+    fn test_cliff_and_schedules() {
+        let linear = terms(VestingSchedule::Linear, false);
+        let cliff = linear.start_ts + linear.cliff_secs;
+        assert_eq!(linear.unlocked_at(0).unwrap(), 0);
+        assert_eq!(linear.unlocked_at(cliff - 1).unwrap(), 0);
+        // The first year unlocks at once on the cliff
+        assert_eq!(linear.unlocked_at(cliff).unwrap(), 12_000);
+        assert_eq!(linear.unlocked_at(cliff + MONTH / 2).unwrap(), 12_500);
+        assert_eq!(linear.unlocked_at(linear.start_ts + linear.duration_secs - 1).unwrap(), 47_999);
+        assert_eq!(linear.unlocked_at(linear.start_ts + linear.duration_secs).unwrap(), 48_000);
+        assert_eq!(linear.unlocked_at(u64::MAX).unwrap(), 48_000);
+
+        // Quarterly steps only unlock at the end of each quarter
+        let step = terms(VestingSchedule::Step { interval_secs: 3 * MONTH }, false);
+        assert_eq!(step.unlocked_at(cliff - 1).unwrap(), 0);
+        assert_eq!(step.unlocked_at(cliff).unwrap(), 12_000);
+        assert_eq!(step.unlocked_at(cliff + 3 * MONTH - 1).unwrap(), 12_000);
+        assert_eq!(step.unlocked_at(cliff + 3 * MONTH).unwrap(), 15_000);
+
+        assert!(linear.validate().is_ok());
+        assert!(VestingTerms { cliff_secs: 49 * MONTH, ..linear.clone() }.validate().is_err());
+        assert!(VestingTerms { total_amount: 0, ..linear.clone() }.validate().is_err());
+        assert!(terms(VestingSchedule::Step { interval_secs: 0 }, false).validate().is_err());
+    }
+
+    #[test]
+    fn test_claim_and_revoke() {
+        let mut fixture = Fixture::new();
+        let beneficiary = fixture.create_signer();
+        let beneficiary_account = fixture.token_account(beneficiary);
+        let funding_account = fixture.token_account(fixture.authority());
+        fixture.set_balance(&funding_account, 100_000);
+
+        let now = fixture.client.clock().unix_timestamp;
+        let vesting_terms = VestingTerms {
+            beneficiary,
+            start_ts: now,
+            ..terms(VestingSchedule::Linear, true)
+        };
+        let create = |fixture: &mut Fixture, vesting: Pubkey, escrow: Pubkey, terms: VestingTerms, signers: usize| {
+            let accounts = vec![
+                AccountMeta::new_readonly(fixture.state, false),
+                AccountMeta::new(vesting, true),
+                AccountMeta::new(escrow, true),
+                AccountMeta::new(funding_account, true),
+                AccountMeta::new_readonly(fixture.authority(), true),
+                AccountMeta::new_readonly(fixture.system_program, false),
+            ];
+            fixture.process_with_signers(accounts, signers, &OVTInstruction::CreateVesting { terms })
+        };
+        let vesting = fixture.create_system_account();
+        let escrow = fixture.create_system_account();
+        let err = create(&mut fixture, vesting, escrow, vesting_terms.clone(), 2).unwrap_err();
+        assert_eq!(err.to_string(), "Missing required signature");
+
+        // The vesting and escrow accounts are fresh and distinct from each
+        // other and the funding account
+        let err = create(&mut fixture, vesting, vesting, vesting_terms.clone(), 3).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidVesting");
+        let err = create(&mut fixture, vesting, funding_account, vesting_terms.clone(), 3).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidVesting");
+        let err = create(&mut fixture, funding_account, escrow, vesting_terms.clone(), 3).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidVesting");
+        let err = create(&mut fixture, vesting, beneficiary_account, vesting_terms.clone(), 3).unwrap_err();
+        assert_eq!(err.to_string(), "AccountAlreadyInitialized");
+        assert_eq!(fixture.balance(&funding_account), 100_000);
+
+        create(&mut fixture, vesting, escrow, vesting_terms.clone(), 3).unwrap();
+        assert_eq!(fixture.balance(&funding_account), 52_000);
+        assert_eq!(fixture.balance(&escrow), 48_000);
+
+        let claim = |fixture: &mut Fixture, signer: Pubkey| {
+            fixture.process_raw(vec![
+                AccountMeta::new_readonly(fixture.state, false),
+                AccountMeta::new(vesting, true),
+                AccountMeta::new(escrow, true),
+                AccountMeta::new(beneficiary_account, true),
+                AccountMeta::new_readonly(signer, true),
+            ], &OVTInstruction::ClaimVested)
+        };
+
+        // Nothing before the cliff, the first year's worth on it
+        fixture.client.advance_clock(12 * MONTH - 1);
+        let err = claim(&mut fixture, beneficiary).unwrap_err();
+        assert_eq!(err.to_string(), "NothingVested");
+        fixture.client.advance_clock(1);
+        let authority = fixture.authority();
+        let err = claim(&mut fixture, authority).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidAuthority");
+
+        // While the fund is restricted, claims go only to an allowlisted
+        // beneficiary
+        let mut state = fixture.state();
+        state.allowlist.restricted = true;
+        fixture.set_state(&state);
+        let err = claim(&mut fixture, beneficiary).unwrap_err();
+        assert_eq!(err.to_string(), "AllowlistRejected");
+        state.allowlist.restricted = false;
+        fixture.set_state(&state);
+
+        claim(&mut fixture, beneficiary).unwrap();
+        assert_eq!(fixture.balance(&beneficiary_account), 12_000);
+        let err = claim(&mut fixture, beneficiary).unwrap_err();
+        assert_eq!(err.to_string(), "NothingVested");
+
+        // Revoking half way returns the unvested half; the vested but
+        // unclaimed part stays with the beneficiary
+        fixture.client.advance_clock(12 * MONTH);
+        let revoke_to = |fixture: &mut Fixture, vesting: Pubkey, escrow: Pubkey, refund: Pubkey| {
+            fixture.process(vec![
+                AccountMeta::new_readonly(fixture.state, false),
+                AccountMeta::new(vesting, true),
+                AccountMeta::new(escrow, true),
+                AccountMeta::new(refund, true),
+            ], &OVTInstruction::RevokeVesting)
+        };
+        let revoke = |fixture: &mut Fixture, vesting: Pubkey, escrow: Pubkey| {
+            revoke_to(fixture, vesting, escrow, funding_account)
+        };
+
+        // The escrow cannot refund itself, which would undo its own debit
+        let err = revoke_to(&mut fixture, vesting, escrow, escrow).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidVesting");
+        assert_eq!(fixture.balance(&escrow), 36_000);
+
+        let events = revoke(&mut fixture, vesting, escrow).unwrap();
+        assert_eq!(events, vec![OVTEvent::VestingRevoked { vesting, returned: 24_000 }]);
+        assert_eq!(fixture.balance(&funding_account), 76_000);
+        assert!(revoke(&mut fixture, vesting, escrow).is_err());
+
+        fixture.client.advance_clock(12 * MONTH);
+        claim(&mut fixture, beneficiary).unwrap();
+        assert_eq!(fixture.balance(&beneficiary_account), 24_000);
+        assert_eq!(fixture.balance(&escrow), 0);
+        let err = claim(&mut fixture, beneficiary).unwrap_err();
+        assert_eq!(err.to_string(), "NothingVested");
+
+        // Irrevocable allocations cannot be clawed back
+        let vesting = fixture.create_system_account();
+        let escrow = fixture.create_system_account();
+        let irrevocable = VestingTerms { revocable: false, ..vesting_terms };
+        create(&mut fixture, vesting, escrow, irrevocable, 3).unwrap();
+        let err = revoke(&mut fixture, vesting, escrow).unwrap_err();
+        assert_eq!(err.to_string(), "InvalidVesting");
+    }
+}
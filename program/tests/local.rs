@@ -5,17 +5,25 @@ use ovt_program::{
         test_utils::TestClient,
         AccountMeta,
     },
+    allowlist::AllowlistParams,
+    circuit_breaker::CircuitBreakerParams,
+    epoch::EpochParams,
+    fees::FeeParams,
+    oracle::{OracleAccount, NAV_ASSET_ID},
+    timelock::TimelockParams,
+    AdminConfig,
     OVTInstruction,
     OVTState,
 };
 use std::cell::RefCell;
-use borsh::BorshSerialize;
 
 #[test]
 fn test_initialize() -> Result<(), Box<dyn std::error::Error>> {
     let mut client = TestClient::new();
     let program_id = Pubkey::new_unique();
     let system_program = Pubkey::new_unique();
+    let oracle_program = Pubkey::new_unique();
+    let oracle_publisher = Pubkey::new_unique();
     
     // Create multiple admin accounts (3 out of 5 required)
     let mut admin_accounts = Vec::new();
@@ -25,6 +33,8 @@ fn test_initialize() -> Result<(), Box<dyn std::error::Error>> {
     
     // Create state account with enough space for OVTState
     let state_account = client.create_account(program_id)?;
+    let admin_config = client.create_account(program_id)?;
+    let admin_keys: Vec<Pubkey> = admin_accounts.iter().map(|admin| admin.key).collect();
     {
         let mut accounts = client.accounts.lock().unwrap();
         let account = accounts.get_mut(&state_account.key).unwrap();
@@ -34,6 +44,25 @@ fn test_initialize() -> Result<(), Box<dyn std::error::Error>> {
             treasury_pubkey_bytes: [0u8; 33],
             total_supply: 0,
             last_nav_update: 0,
+            position_count: 0,
+            oracle_program_id: Pubkey::new(),
+            circuit_breaker: CircuitBreakerParams::default(),
+            baseline_nav_sats: 0,
+            admin_config: Pubkey::new(),
+            previous_treasury_pubkey_bytes: [0u8; 33],
+            treasury_rotated_at: 0,
+            paused: 0,
+            nav_history: Pubkey::new(),
+            fees: FeeParams::default(),
+            high_water_mark_sats: 0,
+            last_fee_accrual: 0,
+            allowlist: AllowlistParams::default(),
+            epoch: EpochParams::default(),
+            epoch_start: 0,
+            epoch_start_supply: 0,
+            epoch_minted: 0,
+            epoch_burned: 0,
+            oracle_publisher: Pubkey::new(),
         };
         let serialized = borsh::to_vec(&initial_state)?;
         account.data = RefCell::new(serialized);
@@ -58,10 +87,10 @@ fn test_initialize() -> Result<(), Box<dyn std::error::Error>> {
     let description = "Initialize OVT program state".to_string();
     
     // First 3 admins sign the action
-    for i in 0..3 {
+    for (i, admin) in admin_accounts.iter().enumerate().take(3) {
         let signature = format!("sig_{}", i);
         client.sign_action(
-            &admin_accounts[i].key,
+            &admin.key,
             action_type.clone(),
             description.clone(),
             signature,
@@ -75,6 +104,14 @@ fn test_initialize() -> Result<(), Box<dyn std::error::Error>> {
     // Initialize program with multi-sig approval
     let instruction = OVTInstruction::Initialize {
         treasury_pubkey_bytes: [0u8; 33],
+        oracle_program_id: oracle_program,
+        oracle_publisher,
+        circuit_breaker: CircuitBreakerParams::default(),
+        admins: admin_keys.clone(),
+        threshold: 3,
+        timelock: TimelockParams::default(),
+        fees: FeeParams::default(),
+        epoch: EpochParams::default(),
     };
 
     client.process_transaction(
@@ -83,6 +120,7 @@ fn test_initialize() -> Result<(), Box<dyn std::error::Error>> {
             AccountMeta::new(state_account.key, true),
             AccountMeta::new_readonly(admin_accounts[0].key, true),
             AccountMeta::new_readonly(system_program, false),
+            AccountMeta::new(admin_config.key, true),
         ],
         borsh::to_vec(&instruction)?,
     )?;
@@ -92,6 +130,11 @@ fn test_initialize() -> Result<(), Box<dyn std::error::Error>> {
     assert_eq!(state.nav_sats, 0);
     assert_eq!(state.total_supply, 0);
     assert_eq!(state.last_nav_update, 0);
+    assert_eq!(state.admin_config, admin_config.key);
+
+    let config: AdminConfig = client.get_account_data(&admin_config.key)?;
+    assert_eq!(config.admins, admin_keys);
+    assert_eq!(config.threshold, 3);
 
     // Verify admin status
     for admin in &admin_accounts {
@@ -106,6 +149,8 @@ fn test_nav_update() -> Result<(), Box<dyn std::error::Error>> {
     let mut client = TestClient::new();
     let program_id = Pubkey::new_unique();
     let system_program = Pubkey::new_unique();
+    let oracle_program = Pubkey::new_unique();
+    let oracle_publisher = Pubkey::new_unique();
     
     // Create multiple admin accounts
     let mut admin_accounts = Vec::new();
@@ -115,6 +160,8 @@ fn test_nav_update() -> Result<(), Box<dyn std::error::Error>> {
     
     // Create state account
     let state_account = client.create_account(program_id)?;
+    let admin_config = client.create_account(program_id)?;
+    let admin_keys: Vec<Pubkey> = admin_accounts.iter().map(|admin| admin.key).collect();
     
     // Initialize account data structure properly
     {
@@ -126,6 +173,25 @@ fn test_nav_update() -> Result<(), Box<dyn std::error::Error>> {
             treasury_pubkey_bytes: [0u8; 33],
             total_supply: 0,
             last_nav_update: 0,
+            position_count: 0,
+            oracle_program_id: Pubkey::new(),
+            circuit_breaker: CircuitBreakerParams::default(),
+            baseline_nav_sats: 0,
+            admin_config: Pubkey::new(),
+            previous_treasury_pubkey_bytes: [0u8; 33],
+            treasury_rotated_at: 0,
+            paused: 0,
+            nav_history: Pubkey::new(),
+            fees: FeeParams::default(),
+            high_water_mark_sats: 0,
+            last_fee_accrual: 0,
+            allowlist: AllowlistParams::default(),
+            epoch: EpochParams::default(),
+            epoch_start: 0,
+            epoch_start_supply: 0,
+            epoch_minted: 0,
+            epoch_burned: 0,
+            oracle_publisher: Pubkey::new(),
         };
         account.set_data(&initial_state)?;
         *account.owner.borrow_mut() = program_id;
@@ -149,10 +215,10 @@ fn test_nav_update() -> Result<(), Box<dyn std::error::Error>> {
     let init_description = "Initialize OVT program state".to_string();
     
     // Collect signatures for initialization
-    for i in 0..3 {
+    for (i, admin) in admin_accounts.iter().enumerate().take(3) {
         let signature = format!("init_sig_{}", i);
         client.sign_action(
-            &admin_accounts[i].key,
+            &admin.key,
             init_action_type.clone(),
             init_description.clone(),
             signature,
@@ -165,6 +231,14 @@ fn test_nav_update() -> Result<(), Box<dyn std::error::Error>> {
     // Initialize through proper instruction flow
     let instruction = OVTInstruction::Initialize {
         treasury_pubkey_bytes: [0u8; 33],
+        oracle_program_id: oracle_program,
+        oracle_publisher,
+        circuit_breaker: CircuitBreakerParams::default(),
+        admins: admin_keys.clone(),
+        threshold: 3,
+        timelock: TimelockParams::default(),
+        fees: FeeParams::default(),
+        epoch: EpochParams::default(),
     };
 
     client.process_transaction(
@@ -173,6 +247,7 @@ fn test_nav_update() -> Result<(), Box<dyn std::error::Error>> {
             AccountMeta::new(state_account.key, true),
             AccountMeta::new_readonly(admin_accounts[0].key, true),
             AccountMeta::new_readonly(system_program, false),
+            AccountMeta::new(admin_config.key, true),
         ],
         borsh::to_vec(&instruction)?,
     )?;
@@ -183,10 +258,10 @@ fn test_nav_update() -> Result<(), Box<dyn std::error::Error>> {
     let new_nav = 2_000_000; // 2M sats
     
     // Collect signatures for NAV update
-    for i in 0..3 {
+    for (i, admin) in admin_accounts.iter().enumerate().take(3) {
         let signature = format!("nav_sig_{}", i);
         client.sign_action(
-            &admin_accounts[i].key,
+            &admin.key,
             nav_action_type.clone(),
             nav_description.clone(),
             signature,
@@ -196,6 +271,16 @@ fn test_nav_update() -> Result<(), Box<dyn std::error::Error>> {
     let nav_signatures: Vec<String> = (0..3).map(|i| format!("nav_sig_{}", i)).collect();
     assert!(client.verify_action(&nav_action_type, &nav_signatures)?);
 
+    // Publish the new NAV through the oracle feed
+    let oracle_account = client.create_account(oracle_program)?;
+    client.set_account_data(&oracle_account.key, &OracleAccount {
+        asset_id: NAV_ASSET_ID,
+        price_sats: new_nav,
+        publish_time: client.clock().unix_timestamp,
+        publisher: oracle_publisher,
+        confidence_sats: 0,
+    })?;
+
     let instruction = OVTInstruction::UpdateNAV {
         btc_price_sats: new_nav,
     };
@@ -206,6 +291,10 @@ fn test_nav_update() -> Result<(), Box<dyn std::error::Error>> {
         vec![
             AccountMeta::new(state_account.key, true),  // writable = true
             AccountMeta::new_readonly(admin_accounts[0].key, true),  // is_signer = true
+            AccountMeta::new_readonly(oracle_account.key, false),
+            AccountMeta::new_readonly(admin_config.key, false),
+            AccountMeta::new_readonly(admin_accounts[1].key, true),
+            AccountMeta::new_readonly(admin_accounts[2].key, true),
         ],
         borsh::to_vec(&instruction)?,
     )?;
@@ -223,6 +312,8 @@ fn test_nav_validation() -> Result<(), Box<dyn std::error::Error>> {
     let mut client = TestClient::new();
     let program_id = Pubkey::new_unique();
     let system_program = Pubkey::new_unique();
+    let oracle_program = Pubkey::new_unique();
+    let oracle_publisher = Pubkey::new_unique();
     
     // Create multiple admin accounts
     let mut admin_accounts = Vec::new();
@@ -232,6 +323,8 @@ fn test_nav_validation() -> Result<(), Box<dyn std::error::Error>> {
     
     // Create state account with enough space for OVTState
     let state_account = client.create_account(program_id)?;
+    let admin_config = client.create_account(program_id)?;
+    let admin_keys: Vec<Pubkey> = admin_accounts.iter().map(|admin| admin.key).collect();
     {
         let mut accounts = client.accounts.lock().unwrap();
         let account = accounts.get_mut(&state_account.key).unwrap();
@@ -241,6 +334,25 @@ fn test_nav_validation() -> Result<(), Box<dyn std::error::Error>> {
             treasury_pubkey_bytes: [0u8; 33],
             total_supply: 0,
             last_nav_update: 0,
+            position_count: 0,
+            oracle_program_id: Pubkey::new(),
+            circuit_breaker: CircuitBreakerParams::default(),
+            baseline_nav_sats: 0,
+            admin_config: Pubkey::new(),
+            previous_treasury_pubkey_bytes: [0u8; 33],
+            treasury_rotated_at: 0,
+            paused: 0,
+            nav_history: Pubkey::new(),
+            fees: FeeParams::default(),
+            high_water_mark_sats: 0,
+            last_fee_accrual: 0,
+            allowlist: AllowlistParams::default(),
+            epoch: EpochParams::default(),
+            epoch_start: 0,
+            epoch_start_supply: 0,
+            epoch_minted: 0,
+            epoch_burned: 0,
+            oracle_publisher: Pubkey::new(),
         };
         account.set_data(&initial_state)?;
         *account.owner.borrow_mut() = program_id;
@@ -264,10 +376,10 @@ fn test_nav_validation() -> Result<(), Box<dyn std::error::Error>> {
     let init_description = "Initialize OVT program state".to_string();
     
     // Collect signatures for initialization
-    for i in 0..3 {
+    for (i, admin) in admin_accounts.iter().enumerate().take(3) {
         let signature = format!("init_sig_{}", i);
         client.sign_action(
-            &admin_accounts[i].key,
+            &admin.key,
             init_action_type.clone(),
             init_description.clone(),
             signature,
@@ -279,6 +391,14 @@ fn test_nav_validation() -> Result<(), Box<dyn std::error::Error>> {
 
     let instruction = OVTInstruction::Initialize {
         treasury_pubkey_bytes: [0u8; 33],
+        oracle_program_id: oracle_program,
+        oracle_publisher,
+        circuit_breaker: CircuitBreakerParams::default(),
+        admins: admin_keys.clone(),
+        threshold: 3,
+        timelock: TimelockParams::default(),
+        fees: FeeParams::default(),
+        epoch: EpochParams::default(),
     };
 
     client.process_transaction(
@@ -287,6 +407,7 @@ fn test_nav_validation() -> Result<(), Box<dyn std::error::Error>> {
             AccountMeta::new(state_account.key, true),
             AccountMeta::new_readonly(admin_accounts[0].key, true),
             AccountMeta::new_readonly(system_program, false),
+            AccountMeta::new(admin_config.key, true),
         ],
         borsh::to_vec(&instruction)?,
     )?;
@@ -297,6 +418,25 @@ fn test_nav_validation() -> Result<(), Box<dyn std::error::Error>> {
         treasury_pubkey_bytes: [0u8; 33],
         total_supply: 1_000_000,
         last_nav_update: 0,
+        position_count: 0,
+        oracle_program_id: Pubkey::new(),
+        circuit_breaker: CircuitBreakerParams::default(),
+        baseline_nav_sats: 1_000_000,
+        admin_config: admin_config.key,
+        previous_treasury_pubkey_bytes: [0u8; 33],
+        treasury_rotated_at: 0,
+        paused: 0,
+        nav_history: Pubkey::new(),
+        fees: FeeParams::default(),
+        high_water_mark_sats: 0,
+        last_fee_accrual: 0,
+        allowlist: AllowlistParams::default(),
+        epoch: EpochParams::default(),
+        epoch_start: 0,
+        epoch_start_supply: 0,
+        epoch_minted: 0,
+        epoch_burned: 0,
+        oracle_publisher: Pubkey::new(),
     };
 
     {
@@ -36,6 +36,7 @@ async fn test_multisig_flow() {
         amount,
         signatures.clone(),
         admin_pubkeys.clone(),
+        3,
     ).await;
     assert!(mint_result.is_ok());
 
@@ -56,6 +57,7 @@ async fn test_multisig_flow() {
         position,
         &signatures,
         &admin_pubkeys,
+        3,
     ).await;
     assert!(add_position_result.is_ok());
 
@@ -65,16 +67,38 @@ async fn test_multisig_flow() {
         amount,
         insufficient_sigs,
         admin_pubkeys.clone(),
+        3,
     ).await;
     assert!(invalid_result.is_err());
 
-    // Test with invalid admin keys
+    // Admin sets of any size work once the threshold is met
+    let mut smaller_pubkeys = admin_pubkeys.clone();
+    smaller_pubkeys.pop();
+    let smaller_result = client.mint_tokens(
+        amount,
+        signatures.clone(),
+        smaller_pubkeys.clone(),
+        3,
+    ).await;
+    assert!(smaller_result.is_ok());
+
+    // Test with a threshold above the admin count
+    let invalid_threshold_result = client.mint_tokens(
+        amount,
+        signatures.clone(),
+        smaller_pubkeys,
+        5,
+    ).await;
+    assert!(invalid_threshold_result.is_err());
+
+    // Test with duplicated admin keys
     let mut invalid_pubkeys = admin_pubkeys.clone();
-    invalid_pubkeys.pop(); // Remove one key to make it invalid
+    invalid_pubkeys[4] = invalid_pubkeys[0];
     let invalid_keys_result = client.mint_tokens(
         amount,
         signatures,
         invalid_pubkeys,
+        3,
     ).await;
     assert!(invalid_keys_result.is_err());
 }
@@ -112,6 +136,7 @@ async fn test_real_signatures() {
         &signatures,
         message,
         &admin_pubkeys,
+        3,
     ).await;
     assert!(result.is_ok());
     assert!(result.unwrap());